@@ -0,0 +1,131 @@
+//! Time-to-depth of the main search by thread count.
+//!
+//! Run with `cargo bench --bench search`, optionally followed by the thread
+//! counts to try (`cargo bench --bench search -- 1 2 4 8`). Each position is
+//! searched to a fixed depth with no node or time limit and a fresh
+//! transposition table, so every run does the same job and only the wall time
+//! differs.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use gomoku::ai;
+use gomoku::engine::Board;
+use gomoku::tt::TranspositionTable;
+use gomoku::types::{AiConfig, EvalWeights, Player, RuleSetKind};
+
+const BOARD_SIZE: usize = 15;
+const HASH_MB: usize = 64;
+const RUNS: u32 = 3;
+
+struct Position {
+  name: &'static str,
+  depth: u8,
+  moves: &'static [(usize, usize)], // Black first
+}
+
+const POSITIONS: [Position; 4] = [
+  Position {
+    name: "opening",
+    depth: 7,
+    moves: &[(7, 7), (8, 8), (8, 6), (6, 8)],
+  },
+  Position {
+    name: "early-middlegame",
+    depth: 7,
+    moves: &[(7, 7), (8, 7), (7, 8), (7, 6), (8, 9), (6, 7), (9, 10), (10, 11)],
+  },
+  Position {
+    name: "middlegame",
+    depth: 7,
+    moves: &[
+      (7, 7), (7, 8), (8, 8), (6, 6), (9, 9), (10, 10), (8, 6), (8, 7), (9, 7), (10, 8),
+      (6, 9), (9, 6),
+    ],
+  },
+  Position {
+    name: "crowded",
+    depth: 8,
+    moves: &[
+      (7, 7), (8, 8), (6, 8), (8, 6), (8, 7), (6, 7), (5, 9), (4, 10), (9, 6), (7, 9),
+      (6, 6), (5, 5), (9, 8), (10, 9), (7, 5), (8, 4), (9, 5), (10, 4),
+    ],
+  },
+];
+
+fn main() {
+  let thread_counts = thread_counts();
+  println!("{:<18} {:>5} {:>8} {:>12} {:>8}", "position", "depth", "threads", "time", "speedup");
+  for position in &POSITIONS {
+    let (board, player) = setup(position);
+    let mut baseline = None;
+    for &threads in &thread_counts {
+      let elapsed = time_search(&board, player, position.depth, threads);
+      let base = *baseline.get_or_insert(elapsed);
+      println!(
+        "{:<18} {:>5} {:>8} {:>10.1}ms {:>7.2}x",
+        position.name,
+        position.depth,
+        threads,
+        elapsed.as_secs_f64() * 1000.0,
+        base.as_secs_f64() / elapsed.as_secs_f64()
+      );
+    }
+  }
+}
+
+// Counts given on the command line, or powers of two up to the core count
+fn thread_counts() -> Vec<usize> {
+  let requested: Vec<usize> = std::env::args().skip(1).filter_map(|arg| arg.parse().ok()).collect();
+  if !requested.is_empty() {
+    return requested;
+  }
+  let cores = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+  let mut counts = vec![1];
+  while counts[counts.len() - 1] * 2 <= cores {
+    counts.push(counts[counts.len() - 1] * 2);
+  }
+  if counts[counts.len() - 1] != cores {
+    counts.push(cores);
+  }
+  counts
+}
+
+fn setup(position: &Position) -> (Board, Player) {
+  let mut board = Board::new(BOARD_SIZE);
+  let mut player = Player::B;
+  for &(x, y) in position.moves {
+    board.set(x, y, player);
+    player = player.other();
+  }
+  (board, player)
+}
+
+// Best of RUNS searches, each with an empty table
+fn time_search(board: &Board, player: Player, depth: u8, threads: usize) -> Duration {
+  let config = AiConfig {
+    depth,
+    max_candidates: 12,
+    randomness: 0,
+    max_nodes: u32::MAX,
+    defense_weight: 12,
+    threat_nodes: 0,
+    quiescence_nodes: 0,
+    move_time_ms: 0,
+    hash_mb: HASH_MB,
+    threads,
+    weights: EvalWeights::default(),
+    use_book: false,
+    book_variety: 0,
+    seed: None,
+  };
+  (0..RUNS)
+    .map(|_| {
+      let tt = TranspositionTable::new(HASH_MB);
+      let start = Instant::now();
+      ai::choose_move(board, RuleSetKind::Standard, player, config, &tt);
+      start.elapsed()
+    })
+    .min()
+    .unwrap_or_default()
+}
@@ -1,64 +1,93 @@
-use std::collections::HashSet;
-use std::sync::atomic::{AtomicU32, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rayon::prelude::*;
+use rand::SeedableRng;
 
 use crate::engine::Board;
+use crate::eval::SearchBoard;
 use crate::rules::{rules_for, RuleSet};
-use crate::types::{AiConfig, Coord, Move, Player, RuleSetKind};
+use crate::threat;
+use crate::tt::{TTFlag, TranspositionTable};
+use crate::types::{AiConfig, Coord, EvalWeights, Move, MoveAnalysis, OpeningChoice, OpeningPhase, OpeningRule, OpeningState, Player, RuleSetKind};
 
 const WIN_SCORE: i32 = 1_000_000;
-const SCORE_FIVE: i32 = 200_000;
-const SCORE_OPEN_FOUR: i32 = 50_000;
-const SCORE_SEMI_FOUR: i32 = 10_000;
-const SCORE_BROKEN_FOUR: i32 = 7_000;
-const SCORE_OPEN_THREE: i32 = 3_000;
-const SCORE_BROKEN_THREE: i32 = 1_200;
-const SCORE_OPEN_TWO: i32 = 300;
-const SCORE_SEMI_THREE: i32 = 400;
-const SCORE_SEMI_TWO: i32 = 80;
-const SCORE_OPEN_ONE: i32 = 6;
+// Below this margin a swap opening counts as balanced
+const OPENING_BALANCE_MARGIN: i32 = 500;
+
+// Depth cap for open-ended analysis; in practice the search is stopped long before
+const MAX_ANALYSIS_DEPTH: u8 = 64;
 
 const MAX_KILLER_DEPTH: usize = 16; // Maximum depth for killer move tracking
 const KILLERS_PER_DEPTH: usize = 2; // Number of killer moves to store per depth
 
-#[derive(Default)]
-struct ScoreBreakdown {
-  score: i32,
-  open_threes: i32,
-  open_fours: i32,
-}
-
 struct SearchContext {
   nodes: u32,
-  candidate_set: HashSet<(usize, usize)>,
+  // Points near a stone, row-major; filled and cleared again by candidate_moves.
+  // Collected in board order so the same position always gives the same move order
+  candidate_mask: Vec<bool>,
   killer_moves: [[Option<Coord>; KILLERS_PER_DEPTH]; MAX_KILLER_DEPTH], // Killer moves per depth
-  history: [[u32; 15]; 15], // History heuristic: counts of beta cutoffs per position
+  board_size: usize,
+  history: Vec<u32>, // History heuristic: counts of beta cutoffs per position, row-major
+  quiescence_left: u32, // Nodes left to the quiescence search at the current leaf
+  // Helper threads only: set once the main thread has finished the iteration being helped with
+  helper_done: Option<Arc<AtomicBool>>,
 }
 
-pub fn candidate_moves_for_llm(board: &Board, player: Player, max_candidates: usize) -> Vec<Coord> {
-  let mut work_board = board.clone();
-  let mut ctx = SearchContext {
-    nodes: 0,
-    candidate_set: HashSet::new(),
-    killer_moves: [[None; KILLERS_PER_DEPTH]; MAX_KILLER_DEPTH],
-    history: [[0; 15]; 15],
-  };
-  candidate_moves(&mut work_board, player, max_candidates, &mut ctx, 0)
+impl SearchContext {
+  fn new(board_size: usize) -> Self {
+    Self {
+      nodes: 0,
+      candidate_mask: vec![false; board_size * board_size],
+      killer_moves: [[None; KILLERS_PER_DEPTH]; MAX_KILLER_DEPTH],
+      board_size,
+      history: vec![0; board_size * board_size],
+      quiescence_left: 0,
+      helper_done: None,
+    }
+  }
+
+  fn history_at(&self, coord: &Coord) -> u32 {
+    self.history[coord.y * self.board_size + coord.x]
+  }
+
+  fn is_helper(&self) -> bool {
+    self.helper_done.is_some()
+  }
+
+  // Whether this thread has to stop searching. The node budget only binds the
+  // main thread; helpers stop when the main thread is done with the iteration.
+  fn cut_off(&self, shared_ctx: &SharedSearchContext) -> bool {
+    match &self.helper_done {
+      Some(done) => done.load(Ordering::Relaxed) || shared_ctx.stopped(),
+      None => shared_ctx.exhausted(self.nodes),
+    }
+  }
+}
+
+pub fn candidate_moves_for_llm(
+  board: &Board,
+  rule_set: RuleSetKind,
+  player: Player,
+  max_candidates: usize,
+) -> Vec<Coord> {
+  let rules = rules_for(rule_set);
+  let mut work_board = SearchBoard::new(board, rules.as_ref(), EvalWeights::default());
+  let mut ctx = SearchContext::new(board.size());
+  let mut candidates = candidate_moves(&mut work_board, player, max_candidates, &mut ctx, 0);
+  retain_legal(&work_board, player, &mut candidates, rules.as_ref());
+  candidates
 }
 
 pub fn tactical_move(board: &Board, rule_set: RuleSetKind, player: Player) -> Option<Coord> {
   let rules = rules_for(rule_set);
-  let mut work_board = board.clone();
-  let mut ctx = SearchContext {
-    nodes: 0,
-    candidate_set: HashSet::new(),
-    killer_moves: [[None; KILLERS_PER_DEPTH]; MAX_KILLER_DEPTH],
-    history: [[0; 15]; 15],
-  };
-  let candidates = candidate_moves(&mut work_board, player, usize::MAX, &mut ctx, 0);
+  let mut work_board = SearchBoard::new(board, rules.as_ref(), EvalWeights::default());
+  let mut ctx = SearchContext::new(board.size());
+  let mut candidates = candidate_moves(&mut work_board, player, usize::MAX, &mut ctx, 0);
+  retain_legal(&work_board, player, &mut candidates, rules.as_ref());
   if candidates.is_empty() {
     return None;
   }
@@ -68,7 +97,7 @@ pub fn tactical_move(board: &Board, rule_set: RuleSetKind, player: Player) -> Op
     return Some(winning[0]);
   }
 
-  let blocks = immediate_wins(&mut work_board, player.other(), &candidates, rules.as_ref());
+  let blocks = opponent_wins(&mut work_board, player, &candidates, rules.as_ref());
   if !blocks.is_empty() {
     return Some(blocks[0]);
   }
@@ -76,114 +105,367 @@ pub fn tactical_move(board: &Board, rule_set: RuleSetKind, player: Player) -> Op
   None
 }
 
-// Shared context for parallel search with atomic node counter
-struct SharedSearchContext {
-  nodes: AtomicU32,
-  max_nodes: u32,
+/// Stone to place during a Swap/Swap2 placement phase. Whoever places wants
+/// the colour choice that follows to be as hard as possible, so we pick among
+/// the placements that leave the position closest to level.
+pub fn opening_placement(
+  board: &Board,
+  rule_set: RuleSetKind,
+  stone: Player,
+  config: AiConfig,
+) -> Option<Coord> {
+  let rules = rules_for(rule_set);
+  let mut work_board = SearchBoard::new(board, rules.as_ref(), config.weights);
+  let mut ctx = SearchContext::new(board.size());
+  let mut candidates = candidate_moves(&mut work_board, stone, usize::MAX, &mut ctx, 0);
+  retain_legal(&work_board, stone, &mut candidates, rules.as_ref());
+
+  let mut scored = Vec::with_capacity(candidates.len());
+  for coord in candidates {
+    work_board.set(coord.x, coord.y, stone);
+    let imbalance = work_board.evaluate(Player::B, 10).abs();
+    scored.push((-imbalance, coord));
+    work_board.clear(coord.x, coord.y);
+  }
+  scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+  // Always keep a little variety so the same opening is not proposed every game
+  pick_with_randomness(&scored, config.randomness.max(2), &mut move_rng(config.seed, board))
 }
 
-// Transposition table entry flag
-#[derive(Clone, Copy, PartialEq)]
-enum TTFlag {
-  Exact,      // Exact score
-  LowerBound, // Alpha cutoff (score >= beta)
-  UpperBound, // Beta cutoff (score <= alpha)
+/// Colour choice (or Swap2 "place two more") for the seat that has to decide.
+pub fn opening_choice(
+  board: &Board,
+  rule_set: RuleSetKind,
+  opening: &OpeningState,
+  config: AiConfig,
+) -> OpeningChoice {
+  let rules = rules_for(rule_set);
+  // After three or five opening stones it is always White to move
+  let white_score = search_score(board, rules.as_ref(), Player::W, config);
+
+  if opening.phase == OpeningPhase::ResponderChoice
+    && opening.rule == OpeningRule::Swap2
+    && white_score.abs() <= OPENING_BALANCE_MARGIN
+  {
+    // Level position: hand the decision back after rebalancing it ourselves
+    return OpeningChoice::PlaceTwo;
+  }
+
+  if white_score >= 0 {
+    OpeningChoice::TakeWhite
+  } else {
+    OpeningChoice::TakeBlack
+  }
 }
 
-// Transposition table entry
-#[derive(Clone, Copy)]
-struct TTEntry {
-  hash: u64,
-  depth: u8,
-  score: i32,
-  flag: TTFlag,
+// Shallow fixed-depth score of the position from `player`'s point of view.
+fn search_score(board: &Board, rules: &dyn RuleSet, player: Player, config: AiConfig) -> i32 {
+  let mut work_board = SearchBoard::new(board, rules, config.weights);
+  let mut ctx = SearchContext::new(board.size());
+  let shared_ctx = Arc::new(SharedSearchContext::new(&config, None, None));
+  let tt = TranspositionTable::with_entries(4096);
+  negamax_parallel(
+    &mut work_board,
+    player,
+    config.depth.clamp(1, 2),
+    -WIN_SCORE,
+    WIN_SCORE,
+    rules,
+    10,
+    config.max_candidates,
+    &mut ctx,
+    &shared_ctx,
+    &tt,
+    0,
+    true,
+  )
 }
 
-// Fixed-size transposition table with replacement
-struct TranspositionTable {
-  entries: Vec<Option<TTEntry>>,
-  size: usize,
+// Shared context for parallel search with atomic node counters
+struct SharedSearchContext {
+  nodes: AtomicU64,      // Every thread's nodes, for reporting
+  main_nodes: AtomicU64, // The main thread's nodes, which the node budget applies to
+  max_nodes: u64,
+  quiescence_nodes: u32,         // Node cap of the quiescence search at each leaf
+  stop: Option<Arc<AtomicBool>>, // Set from outside to abandon the search
+  deadline: Option<Instant>,      // Wall-clock limit; reaching it abandons the search like `stop`
 }
 
-impl TranspositionTable {
-  fn new(size: usize) -> Self {
+impl SharedSearchContext {
+  fn new(config: &AiConfig, stop: Option<Arc<AtomicBool>>, deadline: Option<Instant>) -> Self {
     Self {
-      entries: vec![None; size],
-      size,
+      nodes: AtomicU64::new(0),
+      main_nodes: AtomicU64::new(0),
+      max_nodes: config.max_nodes.max(1) as u64,
+      quiescence_nodes: config.quiescence_nodes,
+      stop,
+      deadline,
     }
   }
 
-  fn probe(&self, hash: u64, depth: u8) -> Option<(i32, TTFlag)> {
-    let index = (hash as usize) % self.size;
-    if let Some(entry) = &self.entries[index] {
-      if entry.hash == hash && entry.depth >= depth {
-        return Some((entry.score, entry.flag));
-      }
-    }
-    None
+  fn stopped(&self) -> bool {
+    let cancelled = self
+      .stop
+      .as_ref()
+      .map(|flag| flag.load(Ordering::Relaxed))
+      .unwrap_or(false);
+    cancelled || self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
   }
 
-  fn store(&mut self, hash: u64, depth: u8, score: i32, flag: TTFlag) {
-    let index = (hash as usize) % self.size;
-    // Replace if slot is empty or new entry has greater/equal depth
-    let should_replace = match &self.entries[index] {
-      None => true,
-      Some(existing) => depth >= existing.depth,
-    };
-    if should_replace {
-      self.entries[index] = Some(TTEntry {
-        hash,
-        depth,
-        score,
-        flag,
-      });
+  // Node budget used up, or the search was cancelled or ran out of time
+  fn exhausted(&self, local_nodes: u32) -> bool {
+    self.main_nodes.load(Ordering::Relaxed) + local_nodes as u64 >= self.max_nodes || self.stopped()
+  }
+
+  fn add_nodes(&self, nodes: u32, main: bool) {
+    self.nodes.fetch_add(nodes as u64, Ordering::Relaxed);
+    if main {
+      self.main_nodes.fetch_add(nodes as u64, Ordering::Relaxed);
     }
   }
 }
 
+// Called after each completed depth with the root moves (best first) and total nodes
+type IterationCallback<'a> = &'a mut dyn FnMut(&[MoveAnalysis], u64);
+
+// Ways for a caller to cut a root search short and to follow it iteration by iteration.
+#[derive(Default)]
+struct SearchControl<'a> {
+  stop: Option<Arc<AtomicBool>>,
+  deadline: Option<Instant>,
+  // Search every root move with a full window so each gets an exact score,
+  // instead of proving only the best one
+  exact_scores: bool,
+  on_iteration: Option<IterationCallback<'a>>,
+}
+
 pub fn choose_move(
   board: &Board,
   rule_set: RuleSetKind,
   player: Player,
   config: AiConfig,
+  tt: &TranspositionTable,
 ) -> Option<Coord> {
+  let deadline = (config.move_time_ms > 0).then(|| Instant::now() + Duration::from_millis(config.move_time_ms));
   let rules = rules_for(rule_set);
-  // 只克隆一次，整个函数复用
-  let mut work_board = board.clone();
-  // 创建搜索上下文，复用HashSet
-  let mut ctx = SearchContext {
-    nodes: 0,
-    candidate_set: HashSet::new(),
-    killer_moves: [[None; KILLERS_PER_DEPTH]; MAX_KILLER_DEPTH],
-    history: [[0; 15]; 15],
-  };
+  // 只构建一次，整个函数复用
+  let mut work_board = SearchBoard::new(board, rules.as_ref(), config.weights);
+  // 创建搜索上下文，复用候选点缓冲
+  let mut ctx = SearchContext::new(board.size());
 
   let mut candidates = candidate_moves(&mut work_board, player, config.max_candidates, &mut ctx, 0);
+  retain_legal(&work_board, player, &mut candidates, rules.as_ref());
   if candidates.is_empty() {
     return None;
   }
 
+  let mut rng = move_rng(config.seed, board);
   let winning = immediate_wins(&mut work_board, player, &candidates, rules.as_ref());
   if !winning.is_empty() {
-    return pick_best(&mut work_board, player, &winning, config);
+    return pick_best(&mut work_board, player, &winning, config, &mut rng);
   }
 
-  let blocks = immediate_wins(&mut work_board, player.other(), &candidates, rules.as_ref());
+  // A forced win by continuous threats beats anything the shallow search finds
+  if let Some(win) = threat::find_forced_win(board, rule_set, player, config.threat_nodes) {
+    return win.moves.first().copied();
+  }
+
+  let blocks = opponent_wins(&mut work_board, player, &candidates, rules.as_ref());
   if !blocks.is_empty() {
     candidates = blocks;
   }
 
-  // Use parallel evaluation for candidates with iterative deepening
-  let shared_ctx = Arc::new(SharedSearchContext {
-    nodes: AtomicU32::new(0),
-    max_nodes: config.max_nodes.max(1),
-  });
+  let control = SearchControl {
+    deadline,
+    // Randomised play picks among the top few moves, so they need comparable scores
+    exact_scores: config.randomness > 0,
+    ..SearchControl::default()
+  };
+  let lines = search_root(board, rule_set, player, config, candidates, tt, control);
+  let best = lines.first()?;
+
+  // Apply randomness to final selection from the best candidates
+  if config.randomness > 0 {
+    // Re-score at final depth for randomness selection
+    let final_scored: Vec<(i32, Coord)> = lines
+      .iter()
+      .take(config.randomness as usize + 1)
+      .enumerate()
+      .map(|(idx, line)| {
+        // Quick estimate based on previous search
+        let score = best.score - (idx as i32 * 100); // Rough ordering
+        (score, line.coord)
+      })
+      .collect();
+    pick_with_randomness(&final_scored, config.randomness, &mut rng)
+  } else {
+    Some(best.coord)
+  }
+}
+
+/// Searches the position like `choose_move` but reports the `top_n` best root
+/// moves with their scores and expected continuations instead of picking one.
+/// Scores are from `player`'s point of view.
+pub fn analyze(
+  board: &Board,
+  rule_set: RuleSetKind,
+  player: Player,
+  config: AiConfig,
+  top_n: usize,
+  tt: &TranspositionTable,
+) -> Vec<MoveAnalysis> {
+  let candidates = analysis_candidates(board, rule_set, player, config);
+  if candidates.is_empty() {
+    return Vec::new();
+  }
+  let control = SearchControl {
+    exact_scores: true,
+    ..SearchControl::default()
+  };
+  let mut lines = search_root(board, rule_set, player, config, candidates, tt, control);
+  lines.truncate(top_n.max(1));
+  lines
+}
+
+/// Scores every root move of the position, best first, making sure `played`
+/// is among them so it can be compared with the engine's choice.
+pub fn analyze_with_move(
+  board: &Board,
+  rule_set: RuleSetKind,
+  player: Player,
+  config: AiConfig,
+  played: Coord,
+  tt: &TranspositionTable,
+) -> Vec<MoveAnalysis> {
+  let mut candidates = analysis_candidates(board, rule_set, player, config);
+  if !candidates.contains(&played) {
+    candidates.push(played);
+  }
+  let control = SearchControl {
+    exact_scores: true,
+    ..SearchControl::default()
+  };
+  search_root(board, rule_set, player, config, candidates, tt, control)
+}
+
+/// Whether a search score means the side it belongs to has a forced win.
+pub fn is_forced_win(score: i32) -> bool {
+  score >= WIN_SCORE - 100
+}
 
-  // Transposition table size: ~64K entries, should be enough for typical searches
-  const TT_SIZE: usize = 65536;
+/// Whether a search score means the side it belongs to loses by force.
+pub fn is_forced_loss(score: i32) -> bool {
+  score <= -WIN_SCORE + 100
+}
+
+/// Open-ended version of `analyze` for the analysis mode: keeps deepening
+/// until `stop` is set or a forced win shows up, and reports the `top_n` best
+/// lines plus the total node count after every completed depth. Each run
+/// starts from an empty transposition table of `config.hash_mb`.
+pub fn analyze_until_stopped(
+  board: &Board,
+  rule_set: RuleSetKind,
+  player: Player,
+  config: AiConfig,
+  top_n: usize,
+  stop: Arc<AtomicBool>,
+  mut on_depth: impl FnMut(&[MoveAnalysis], u64),
+) -> Vec<MoveAnalysis> {
+  let config = AiConfig {
+    depth: MAX_ANALYSIS_DEPTH,
+    max_nodes: u32::MAX,
+    randomness: 0,
+    ..config
+  };
+  let top_n = top_n.max(1);
+  let candidates = analysis_candidates(board, rule_set, player, config);
+  if candidates.is_empty() {
+    return Vec::new();
+  }
+
+  let mut on_iteration = |lines: &[MoveAnalysis], nodes: u64| {
+    on_depth(&lines[..lines.len().min(top_n)], nodes);
+  };
+  let control = SearchControl {
+    stop: Some(stop),
+    deadline: None,
+    exact_scores: true,
+    on_iteration: Some(&mut on_iteration),
+  };
+  let tt = TranspositionTable::new(config.hash_mb);
+  let mut lines = search_root(board, rule_set, player, config, candidates, &tt, control);
+  lines.truncate(top_n);
+  lines
+}
+
+// Root moves worth analysing. Same pruning as choose_move, so a hint matches
+// what the AI would play.
+fn analysis_candidates(board: &Board, rule_set: RuleSetKind, player: Player, config: AiConfig) -> Vec<Coord> {
+  let rules = rules_for(rule_set);
+  let mut work_board = SearchBoard::new(board, rules.as_ref(), config.weights);
+  let mut ctx = SearchContext::new(board.size());
+
+  let mut candidates = candidate_moves(&mut work_board, player, config.max_candidates, &mut ctx, 0);
+  retain_legal(&work_board, player, &mut candidates, rules.as_ref());
+  if candidates.is_empty() {
+    return candidates;
+  }
+
+  let winning = immediate_wins(&mut work_board, player, &candidates, rules.as_ref());
+  if !winning.is_empty() {
+    return winning;
+  }
+  let blocks = opponent_wins(&mut work_board, player, &candidates, rules.as_ref());
+  if !blocks.is_empty() {
+    return blocks;
+  }
+  candidates
+}
 
-  let mut best_move: Option<Coord> = None;
-  let mut best_score = -WIN_SCORE;
+// Iterative-deepening root search shared by choose_move and analyze.
+// Returns every root move, best first. Scores come from the last iteration;
+// depth and PV from the deepest one that finished within the node budget.
+// An iteration interrupted by a stop or the deadline is discarded, leaving the
+// result of the last completed one (or the candidate order if none completed).
+fn search_root(
+  board: &Board,
+  rule_set: RuleSetKind,
+  player: Player,
+  config: AiConfig,
+  candidates: Vec<Coord>,
+  tt: &TranspositionTable,
+  mut control: SearchControl,
+) -> Vec<MoveAnalysis> {
+  let shared_ctx = Arc::new(SharedSearchContext::new(&config, control.stop.take(), control.deadline));
+  tt.new_search();
+  let root = RootSearch {
+    board,
+    rule_set,
+    player,
+    config,
+    exact_scores: control.exact_scores,
+    tt,
+    shared_ctx: &shared_ctx,
+  };
+  let rules = rules_for(rule_set);
+  let mut pv_board = board.clone();
+
+  // Killers and history carry over from one iteration to the next, per thread
+  let mut main_ctx = SearchContext::new(board.size());
+  let mut helper_ctxs: Vec<SearchContext> = (1..search_threads(config.threads))
+    .map(|_| SearchContext::new(board.size()))
+    .collect();
+
+  let mut lines: Vec<MoveAnalysis> = candidates
+    .into_iter()
+    .map(|coord| MoveAnalysis {
+      coord,
+      score: -WIN_SCORE,
+      depth: 0,
+      nodes: 0,
+      pv: vec![coord],
+    })
+    .collect();
 
   // Aspiration window constants
   const ASPIRATION_WINDOW: i32 = 50;
@@ -193,20 +475,12 @@ pub fn choose_move(
   // This fills the transposition table progressively and allows early termination
   let start_depth = if config.depth <= 2 { config.depth } else { 1 };
 
-  for current_depth in start_depth..=config.depth {
+  'deepening: for current_depth in start_depth..=config.depth {
     // Check if we've exceeded node budget before starting new iteration
-    if shared_ctx.nodes.load(Ordering::Relaxed) >= shared_ctx.max_nodes {
+    if shared_ctx.exhausted(0) {
       break;
     }
 
-    // Order candidates: put best move from previous iteration first
-    if let Some(prev_best) = best_move {
-      if let Some(pos) = candidates.iter().position(|&c| c == prev_best) {
-        candidates.remove(pos);
-        candidates.insert(0, prev_best);
-      }
-    }
-
     // Aspiration window: use previous score as guess for narrower search
     let (mut alpha, mut beta) = match aspiration_guess {
       Some(guess) if current_depth > 1 => {
@@ -215,60 +489,63 @@ pub fn choose_move(
       _ => (-WIN_SCORE, WIN_SCORE),
     };
 
-    let mut iteration_complete = false;
-
-    while !iteration_complete {
-      let scored: Vec<(i32, Coord)> = candidates
-        .par_iter()
-        .map(|&coord| {
-          // Each thread gets its own rules, board clone, local context, and transposition table
-          let local_rules = rules_for(rule_set);
-          let mut local_board = board.clone();
-          let mut local_ctx = SearchContext {
-            nodes: 0,
-            candidate_set: HashSet::new(),
-            killer_moves: [[None; KILLERS_PER_DEPTH]; MAX_KILLER_DEPTH],
-            history: [[0; 15]; 15],
-          };
-          let mut local_tt = TranspositionTable::new(TT_SIZE);
-
-          local_board.set(coord.x, coord.y, player);
-          let mv = Move {
-            x: coord.x,
-            y: coord.y,
-            player,
-            t: None,
-          };
-
-          let score = if local_rules.check_win(&local_board, &mv).is_some() {
-            WIN_SCORE
-          } else {
-            -negamax_parallel(
-              &mut local_board,
-              player.other(),
-              current_depth.saturating_sub(1),
-              -beta,  // Note: negated for negamax
-              -alpha,
-              local_rules.as_ref(),
-              config.defense_weight,
-              config.max_candidates,
-              &mut local_ctx,
-              &shared_ctx,
-              &mut local_tt,
-              1,    // Start at depth level 1 since we've already made one move
-              true, // All root moves are treated as PV for parallel search
-            )
-          };
-
-          // Accumulate local nodes to shared counter
-          shared_ctx.nodes.fetch_add(local_ctx.nodes, Ordering::Relaxed);
-
-          (score, coord)
-        })
-        .collect();
+    // Results of the last finished iteration, in case this one is cancelled
+    let settled = lines.clone();
+
+    loop {
+      let order: Vec<Coord> = lines.iter().map(|line| line.coord).collect();
+      let best = Mutex::new(None);
+      let done = Arc::new(AtomicBool::new(false));
+
+      // Lazy SMP: helpers search the same root in rotated orders, so they reach
+      // different subtrees first and leave their results in the shared table
+      let scored = thread::scope(|scope| {
+        for (index, helper_ctx) in helper_ctxs.iter_mut().enumerate() {
+          helper_ctx.helper_done = Some(done.clone());
+          let mut helper_order = order.clone();
+          helper_order.rotate_left((index + 1) % order.len());
+          let (root, best) = (&root, &best);
+          scope.spawn(move || root.search_moves(&helper_order, current_depth, (alpha, beta), best, helper_ctx));
+        }
+        let scored = root.search_moves(&order, current_depth, (alpha, beta), &best, &mut main_ctx);
+        done.store(true, Ordering::Relaxed);
+        scored
+      });
+
+      // A cancelled or timed-out iteration is cut off at arbitrary nodes, so its scores are dropped
+      if shared_ctx.stopped() {
+        lines = settled;
+        break 'deepening;
+      }
+
+      let best = best.into_inner().unwrap_or(None);
+      for (line, result) in lines.iter_mut().zip(scored) {
+        line.score = result.score;
+        line.nodes += result.nodes;
+        // A helper may have proved a better score for this move than the main thread's bound
+        if let Some((score, coord)) = best {
+          if coord == line.coord && score > line.score {
+            line.score = score;
+          }
+        }
+        // A search cut short by the node budget has no reliable line below the root move
+        if result.complete {
+          line.depth = current_depth;
+          pv_board.set(line.coord.x, line.coord.y, player);
+          line.pv = principal_variation(
+            &mut pv_board,
+            line.coord,
+            player.other(),
+            current_depth as usize,
+            rules.as_ref(),
+            tt,
+          );
+          pv_board.clear(line.coord.x, line.coord.y);
+        }
+      }
 
       // Find best score from this search
-      let iter_best_score = scored.iter().map(|(s, _)| *s).max().unwrap_or(-WIN_SCORE);
+      let iter_best_score = lines.iter().map(|line| line.score).max().unwrap_or(-WIN_SCORE);
 
       // Check if we need to re-search with wider window (aspiration fail)
       if iter_best_score <= alpha && alpha > -WIN_SCORE {
@@ -283,54 +560,202 @@ pub fn choose_move(
       }
 
       // Search succeeded within window
-      iteration_complete = true;
-
-      // Find best move from this iteration
-      if let Some(&(score, coord)) = scored.iter().max_by_key(|(s, _)| *s) {
-        best_move = Some(coord);
-        best_score = score;
-        aspiration_guess = Some(score);
-
-        // Early termination if we found a winning move
-        if score >= WIN_SCORE - 100 {
-          // Signal to break outer loop
-          candidates = scored.into_iter().map(|(_, c)| c).collect();
-          break;
-        }
-      }
+      aspiration_guess = Some(iter_best_score);
+      break;
+    }
+
+    // Re-sort candidates based on scores for next iteration; the best move goes first
+    lines.sort_by_key(|line| std::cmp::Reverse(line.score));
 
-      // Re-sort candidates based on scores for next iteration
-      let mut sorted_scored = scored;
-      sorted_scored.sort_by(|a, b| b.0.cmp(&a.0));
-      candidates = sorted_scored.into_iter().map(|(_, c)| c).collect();
+    if let Some(on_iteration) = control.on_iteration.as_mut() {
+      on_iteration(&lines, shared_ctx.nodes.load(Ordering::Relaxed));
     }
 
     // Check for winning move found
-    if best_score >= WIN_SCORE - 100 {
+    if lines[0].score >= WIN_SCORE - 100 {
       break;
     }
   }
 
-  // Apply randomness to final selection from the best candidates
-  if config.randomness > 0 {
-    // Re-score at final depth for randomness selection
-    let final_scored: Vec<(i32, Coord)> = candidates.iter().take(config.randomness as usize + 1)
-      .map(|&c| {
-        // Quick estimate based on previous search
-        let idx = candidates.iter().position(|&x| x == c).unwrap_or(0);
-        let score = best_score - (idx as i32 * 100); // Rough ordering
-        (score, c)
-      })
-      .collect();
-    pick_with_randomness(&final_scored, config.randomness)
+  lines
+}
+
+// 0 means one thread per available core
+fn search_threads(configured: usize) -> usize {
+  if configured > 0 {
+    configured
   } else {
-    best_move
+    thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
   }
 }
 
+// What one thread's pass over the root moves found for a move
+struct RootScore {
+  score: i32,
+  nodes: u64,
+  complete: bool, // Searched before the node budget ran out
+}
+
+// The parts of a root search every thread shares.
+struct RootSearch<'a> {
+  board: &'a Board,
+  rule_set: RuleSetKind,
+  player: Player,
+  config: AiConfig,
+  exact_scores: bool,
+  tt: &'a TranspositionTable,
+  shared_ctx: &'a Arc<SharedSearchContext>,
+}
+
+impl RootSearch<'_> {
+  // One thread's pass over the root moves in `order`. Unless every move needs
+  // an exact score, moves after the first only have to prove they beat the
+  // best score any thread has found so far (`best`, which doubles as the
+  // shared alpha bound) with a zero-window search. A helper stops as soon as
+  // the main thread is done, and its results are only used through `best`.
+  fn search_moves(
+    &self,
+    order: &[Coord],
+    depth: u8,
+    (alpha, beta): (i32, i32),
+    best: &Mutex<Option<(i32, Coord)>>,
+    ctx: &mut SearchContext,
+  ) -> Vec<RootScore> {
+    let rules = rules_for(self.rule_set);
+    let mut board = SearchBoard::new(self.board, rules.as_ref(), self.config.weights);
+    let mut results = Vec::with_capacity(order.len());
+
+    for (index, &coord) in order.iter().enumerate() {
+      if ctx.is_helper() && ctx.cut_off(self.shared_ctx) {
+        break;
+      }
+      board.set(coord.x, coord.y, self.player);
+      let mv = Move {
+        x: coord.x,
+        y: coord.y,
+        player: self.player,
+        t: None,
+      };
+
+      let floor = if self.exact_scores {
+        alpha
+      } else {
+        best_score(best).map_or(alpha, |score| score.max(alpha))
+      };
+      let score = if rules.check_win(&board, &mv).is_some() {
+        WIN_SCORE
+      } else if self.exact_scores || index == 0 {
+        self.search_child(&mut board, rules.as_ref(), depth, (floor, beta), true, ctx)
+      } else {
+        // PVS at the root: zero-window first, full window only if the move may be better
+        let score = self.search_child(&mut board, rules.as_ref(), depth, (floor, floor + 1), false, ctx);
+        if score > floor && score < beta {
+          self.search_child(&mut board, rules.as_ref(), depth, (floor, beta), true, ctx)
+        } else {
+          score
+        }
+      };
+      board.clear(coord.x, coord.y);
+      // A helper interrupted mid-move has only a static guess; drop it
+      if ctx.is_helper() && ctx.cut_off(self.shared_ctx) {
+        self.shared_ctx.add_nodes(ctx.nodes, false);
+        ctx.nodes = 0;
+        break;
+      }
+
+      if score > floor {
+        if let Ok(mut best) = best.lock() {
+          if best.is_none_or(|(best_score, _)| score > best_score) {
+            *best = Some((score, coord));
+          }
+        }
+      }
+
+      let nodes = ctx.nodes;
+      self.shared_ctx.add_nodes(nodes, !ctx.is_helper());
+      ctx.nodes = 0;
+      results.push(RootScore {
+        score,
+        nodes: nodes as u64,
+        complete: !self.shared_ctx.exhausted(0),
+      });
+    }
+    results
+  }
+
+  // Score of the root move just placed on `board`, from the root player's point of view
+  fn search_child(
+    &self,
+    board: &mut SearchBoard,
+    rules: &dyn RuleSet,
+    depth: u8,
+    (alpha, beta): (i32, i32),
+    is_pv_node: bool,
+    ctx: &mut SearchContext,
+  ) -> i32 {
+    -negamax_parallel(
+      board,
+      self.player.other(),
+      depth.saturating_sub(1),
+      -beta, // Note: negated for negamax
+      -alpha,
+      rules,
+      self.config.defense_weight,
+      self.config.max_candidates,
+      ctx,
+      self.shared_ctx,
+      self.tt,
+      1, // Start at depth level 1 since we've already made one move
+      is_pv_node,
+    )
+  }
+}
+
+fn best_score(best: &Mutex<Option<(i32, Coord)>>) -> Option<i32> {
+  best.lock().ok().and_then(|best| best.map(|(score, _)| score))
+}
+
+// Follows the best moves stored in the transposition table from the position
+// after `first`. The board is restored before returning.
+fn principal_variation(
+  board: &mut Board,
+  first: Coord,
+  player: Player,
+  max_len: usize,
+  rules: &dyn RuleSet,
+  tt: &TranspositionTable,
+) -> Vec<Coord> {
+  let mut pv = vec![first];
+  let mut to_move = player;
+  while pv.len() < max_len {
+    let Some(next) = tt.best_move(board.zobrist_hash()) else {
+      break;
+    };
+    if !board.is_empty(next.x, next.y) {
+      break;
+    }
+    board.set(next.x, next.y, to_move);
+    pv.push(next);
+    let mv = Move {
+      x: next.x,
+      y: next.y,
+      player: to_move,
+      t: None,
+    };
+    if rules.check_win(board, &mv).is_some() {
+      break;
+    }
+    to_move = to_move.other();
+  }
+  for coord in pv.iter().skip(1) {
+    board.clear(coord.x, coord.y);
+  }
+  pv
+}
+
 // PVS (Principal Variation Search) with negamax, transposition table, and killer moves
 fn negamax_parallel(
-  board: &mut Board,
+  board: &mut SearchBoard,
   player: Player,
   depth: u8,
   mut alpha: i32,
@@ -340,16 +765,19 @@ fn negamax_parallel(
   max_candidates: usize,
   ctx: &mut SearchContext,
   shared_ctx: &Arc<SharedSearchContext>,
-  tt: &mut TranspositionTable,
+  tt: &TranspositionTable,
   depth_level: usize, // Track current depth for killer move indexing
   is_pv_node: bool,   // Whether this is a Principal Variation node
 ) -> i32 {
   ctx.nodes += 1;
 
   // Check both local and shared node limits
-  let total_nodes = shared_ctx.nodes.load(Ordering::Relaxed) + ctx.nodes;
-  if depth == 0 || board.is_full() || total_nodes >= shared_ctx.max_nodes {
-    return evaluate_board(board, player, defense_weight);
+  if board.is_full() || ctx.cut_off(shared_ctx) {
+    return board.evaluate(player, defense_weight);
+  }
+  if depth == 0 {
+    ctx.quiescence_left = shared_ctx.quiescence_nodes;
+    return quiescence(board, player, alpha, beta, rules, defense_weight, ctx);
   }
 
   // Check transposition table
@@ -371,12 +799,20 @@ fn negamax_parallel(
     }
   }
 
-  let candidates = candidate_moves(board, player, max_candidates, ctx, depth_level);
+  let mut candidates = candidate_moves(board, player, max_candidates, ctx, depth_level);
+  retain_legal(board, player, &mut candidates, rules);
   if candidates.is_empty() {
     return 0;
   }
+  // The best move from an earlier visit, by any thread or search, goes first
+  if let Some(tt_move) = tt.best_move(hash) {
+    if let Some(pos) = candidates.iter().position(|coord| *coord == tt_move) {
+      candidates[..=pos].rotate_right(1);
+    }
+  }
 
   let mut best = -WIN_SCORE;
+  let mut best_move = None;
   let mut first_move = true;
 
   for coord in candidates {
@@ -451,6 +887,7 @@ fn negamax_parallel(
 
     if score > best {
       best = score;
+      best_move = Some(coord);
     }
     if score > alpha {
       alpha = score;
@@ -470,13 +907,75 @@ fn negamax_parallel(
   } else {
     TTFlag::Exact
   };
-  tt.store(hash, depth, best, flag);
+  // A search cut off below this node returned static scores; keep them out of the shared table
+  if !ctx.cut_off(shared_ctx) {
+    tt.store(hash, depth, best, flag, best_move);
+  }
 
   best
 }
 
+// Plays out forcing moves past the search depth so a pending four is not
+// left to the static evaluation: a side facing a four must block it, and
+// otherwise may either stand on the evaluation or make a four of its own.
+// Its nodes come out of the leaf's own cap rather than the search's node budget.
+fn quiescence(
+  board: &mut SearchBoard,
+  player: Player,
+  mut alpha: i32,
+  beta: i32,
+  rules: &dyn RuleSet,
+  defense_weight: i32,
+  ctx: &mut SearchContext,
+) -> i32 {
+  if ctx.quiescence_left == 0 {
+    return board.evaluate(player, defense_weight);
+  }
+  ctx.quiescence_left -= 1;
+
+  if !board.five_points(player).is_empty() {
+    return WIN_SCORE - 1;
+  }
+  let mut blocks = board.five_points(player.other());
+  if !blocks.is_empty() {
+    retain_legal(board, player, &mut blocks, rules);
+    // Two fives to stop, or the only block is forbidden
+    if blocks.len() != 1 {
+      return -(WIN_SCORE - 1);
+    }
+    let block = blocks[0];
+    board.set(block.x, block.y, player);
+    let score = -quiescence(board, player.other(), -beta, -alpha, rules, defense_weight, ctx);
+    board.clear(block.x, block.y);
+    return score;
+  }
+
+  let stand_pat = board.evaluate(player, defense_weight);
+  if stand_pat >= beta {
+    return stand_pat;
+  }
+  alpha = alpha.max(stand_pat);
+  let mut best = stand_pat;
+  let mut fours = board.four_points(player);
+  retain_legal(board, player, &mut fours, rules);
+  for coord in fours {
+    if ctx.quiescence_left == 0 {
+      break;
+    }
+    board.set(coord.x, coord.y, player);
+    let score = -quiescence(board, player.other(), -beta, -alpha, rules, defense_weight, ctx);
+    board.clear(coord.x, coord.y);
+    best = best.max(score);
+    alpha = alpha.max(score);
+    if alpha >= beta {
+      break;
+    }
+  }
+  best
+}
+
 fn candidate_moves(
-  board: &mut Board,
+  board: &mut SearchBoard,
   player: Player,
   max_candidates: usize,
   ctx: &mut SearchContext,
@@ -484,8 +983,6 @@ fn candidate_moves(
 ) -> Vec<Coord> {
   let size = board.size();
   let mut has_stones = false;
-  // 复用HashSet，避免重复分配
-  ctx.candidate_set.clear();
   let radius: i32 = 2;
 
   for y in 0..size {
@@ -500,7 +997,7 @@ fn candidate_moves(
               let ux = nx as usize;
               let uy = ny as usize;
               if board.in_bounds(ux, uy) && board.get(ux, uy).is_none() {
-                ctx.candidate_set.insert((ux, uy));
+                ctx.candidate_mask[uy * size + ux] = true;
               }
             }
           }
@@ -515,10 +1012,17 @@ fn candidate_moves(
       y: size / 2,
     }]
   } else {
-    ctx.candidate_set
-      .iter()
-      .map(|&(x, y)| Coord { x, y })
-      .collect()
+    let mut near = Vec::new();
+    for (idx, marked) in ctx.candidate_mask.iter_mut().enumerate() {
+      if *marked {
+        *marked = false;
+        near.push(Coord {
+          x: idx % size,
+          y: idx / size,
+        });
+      }
+    }
+    near
   };
 
   if candidates.len() > max_candidates {
@@ -532,17 +1036,15 @@ fn candidate_moves(
 }
 
 fn rank_candidates(
-  board: &mut Board,
+  board: &mut SearchBoard,
   player: Player,
   candidates: Vec<Coord>,
   max_candidates: usize,
 ) -> Vec<Coord> {
   let mut scored = Vec::with_capacity(candidates.len());
   for coord in candidates {
-    board.set(coord.x, coord.y, player);
-    let score = evaluate_board(board, player, 11);
+    let score = board.move_value(coord, player, 11);
     scored.push((score, coord));
-    board.clear(coord.x, coord.y);
   }
 
   scored.sort_by(|a, b| b.0.cmp(&a.0));
@@ -551,7 +1053,7 @@ fn rank_candidates(
 }
 
 fn rank_candidates_with_killers(
-  board: &mut Board,
+  board: &mut SearchBoard,
   player: Player,
   candidates: Vec<Coord>,
   max_candidates: usize,
@@ -560,14 +1062,13 @@ fn rank_candidates_with_killers(
 ) -> Vec<Coord> {
   let mut scored = Vec::with_capacity(candidates.len());
   for coord in candidates {
-    board.set(coord.x, coord.y, player);
-    let base_score = evaluate_board(board, player, 11);
+    // Read from the move scores cached on the board; only points near the last change are rescored
+    let base_score = board.move_value(coord, player, 11);
     // Boost killer moves to prioritize them in the search order
     let killer_bonus = killer_priority(&coord, ctx, depth) * 100_000;
     // Add history heuristic bonus (scaled to not overpower killer moves)
-    let history_bonus = ctx.history[coord.y][coord.x] as i32;
+    let history_bonus = ctx.history_at(&coord) as i32;
     scored.push((base_score + killer_bonus + history_bonus, coord));
-    board.clear(coord.x, coord.y);
   }
 
   scored.sort_by(|a, b| b.0.cmp(&a.0));
@@ -581,8 +1082,8 @@ fn sort_by_killer_and_history(candidates: &mut Vec<Coord>, ctx: &SearchContext,
     let killer_a = killer_priority(a, ctx, depth) * 1_000_000;
     let killer_b = killer_priority(b, ctx, depth) * 1_000_000;
     // Secondary: history heuristic
-    let history_a = ctx.history[a.y][a.x] as i32;
-    let history_b = ctx.history[b.y][b.x] as i32;
+    let history_a = ctx.history_at(a) as i32;
+    let history_b = ctx.history_at(b) as i32;
     (killer_b + history_b).cmp(&(killer_a + history_a))
   });
 }
@@ -604,7 +1105,8 @@ fn killer_priority(coord: &Coord, ctx: &SearchContext, depth: usize) -> i32 {
 fn record_cutoff(ctx: &mut SearchContext, depth_level: usize, remaining_depth: u8, coord: Coord) {
   // Record history heuristic (depth^2 bonus - deeper cutoffs are more valuable)
   let history_bonus = (remaining_depth as u32) * (remaining_depth as u32);
-  ctx.history[coord.y][coord.x] = ctx.history[coord.y][coord.x].saturating_add(history_bonus);
+  let idx = coord.y * ctx.board_size + coord.x;
+  ctx.history[idx] = ctx.history[idx].saturating_add(history_bonus);
 
   // Record killer move
   if depth_level >= MAX_KILLER_DEPTH {
@@ -620,7 +1122,7 @@ fn record_cutoff(ctx: &mut SearchContext, depth_level: usize, remaining_depth: u
 }
 
 fn immediate_wins(
-  board: &mut Board,
+  board: &mut SearchBoard,
   player: Player,
   candidates: &[Coord],
   rules: &dyn RuleSet,
@@ -644,20 +1146,58 @@ fn immediate_wins(
   wins
 }
 
-fn pick_best(board: &mut Board, player: Player, candidates: &[Coord], config: AiConfig) -> Option<Coord> {
+// Blocking points for the opponent's immediate wins that the player may legally occupy.
+fn opponent_wins(
+  board: &mut SearchBoard,
+  player: Player,
+  candidates: &[Coord],
+  rules: &dyn RuleSet,
+) -> Vec<Coord> {
+  let mut blocks = immediate_wins(board, player.other(), candidates, rules);
+  retain_legal(board, player, &mut blocks, rules);
+  blocks
+}
+
+// Drop candidates the rule set forbids (e.g. Renju double-threes for Black).
+fn retain_legal(board: &Board, player: Player, candidates: &mut Vec<Coord>, rules: &dyn RuleSet) {
+  candidates.retain(|coord| {
+    let mv = Move {
+      x: coord.x,
+      y: coord.y,
+      player,
+      t: None,
+    };
+    rules.is_legal(board, &mv)
+  });
+}
+
+fn pick_best(
+  board: &mut SearchBoard,
+  player: Player,
+  candidates: &[Coord],
+  config: AiConfig,
+  rng: &mut StdRng,
+) -> Option<Coord> {
   let mut scored = Vec::new();
   for coord in candidates.iter() {
-    board.set(coord.x, coord.y, player);
-    let score = evaluate_board(board, player, config.defense_weight);
+    let score = board.move_value(*coord, player, config.defense_weight);
     scored.push((score, *coord));
-    board.clear(coord.x, coord.y);
   }
 
   scored.sort_by(|a, b| b.0.cmp(&a.0));
-  pick_with_randomness(&scored, config.randomness)
+  pick_with_randomness(&scored, config.randomness, rng)
 }
 
-fn pick_with_randomness(scored: &[(i32, Coord)], randomness: u8) -> Option<Coord> {
+/// Random source for one AI move. With a seed it depends only on the seed and
+/// the position, so replaying a game with the same seed repeats every choice.
+pub fn move_rng(seed: Option<u64>, board: &Board) -> StdRng {
+  match seed {
+    Some(seed) => StdRng::seed_from_u64(seed ^ board.hash()),
+    None => StdRng::from_entropy(),
+  }
+}
+
+fn pick_with_randomness(scored: &[(i32, Coord)], randomness: u8, rng: &mut StdRng) -> Option<Coord> {
   if scored.is_empty() {
     return None;
   }
@@ -667,240 +1207,71 @@ fn pick_with_randomness(scored: &[(i32, Coord)], randomness: u8) -> Option<Coord
   }
 
   let bucket = usize::min(scored.len(), randomness as usize + 1);
-  let mut rng = rand::thread_rng();
-  scored[..bucket].choose(&mut rng).map(|(_, coord)| *coord)
+  scored[..bucket].choose(rng).map(|(_, coord)| *coord)
 }
 
-fn evaluate_board(board: &Board, player: Player, defense_weight: i32) -> i32 {
-  let my = score_for_player(board, player);
-  let opp = score_for_player(board, player.other());
-  let defense = (opp.score * defense_weight) / 10;
-  my.score - defense
-}
-
-fn score_for_player(board: &Board, player: Player) -> ScoreBreakdown {
-  let size = board.size();
-  let mut total = ScoreBreakdown::default();
-
-  // 横向扫描
-  for y in 0..size {
-    let scored = score_line_direct(board, player, 0, y, 1, 0, size);
-    total.score += scored.score;
-    total.open_threes += scored.open_threes;
-    total.open_fours += scored.open_fours;
-  }
-
-  // 纵向扫描
-  for x in 0..size {
-    let scored = score_line_direct(board, player, x, 0, 0, 1, size);
-    total.score += scored.score;
-    total.open_threes += scored.open_threes;
-    total.open_fours += scored.open_fours;
-  }
-
-  // 主对角线方向 (左上到右下)
-  for start_x in 0..size {
-    let line_len = size - start_x;
-    if line_len >= 5 {
-      let scored = score_line_direct(board, player, start_x, 0, 1, 1, line_len);
-      total.score += scored.score;
-      total.open_threes += scored.open_threes;
-      total.open_fours += scored.open_fours;
-    }
-  }
-  for start_y in 1..size {
-    let line_len = size - start_y;
-    if line_len >= 5 {
-      let scored = score_line_direct(board, player, 0, start_y, 1, 1, line_len);
-      total.score += scored.score;
-      total.open_threes += scored.open_threes;
-      total.open_fours += scored.open_fours;
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn search_config() -> AiConfig {
+    AiConfig {
+      depth: 4,
+      max_candidates: 10,
+      randomness: 0,
+      max_nodes: u32::MAX,
+      defense_weight: 12,
+      threat_nodes: 0,
+      quiescence_nodes: 0,
+      move_time_ms: 0,
+      hash_mb: 4,
+      threads: 1,
+      weights: EvalWeights::default(),
+      use_book: false,
+      book_variety: 0,
+      seed: Some(1),
     }
   }
 
-  // 副对角线方向 (右上到左下)
-  for start_x in 0..size {
-    let line_len = start_x + 1;
-    if line_len >= 5 {
-      let scored = score_line_direct(board, player, start_x, 0, -1, 1, line_len);
-      total.score += scored.score;
-      total.open_threes += scored.open_threes;
-      total.open_fours += scored.open_fours;
-    }
-  }
-  for start_y in 1..size {
-    let line_len = size - start_y;
-    if line_len >= 5 {
-      let scored = score_line_direct(board, player, size - 1, start_y, -1, 1, line_len);
-      total.score += scored.score;
-      total.open_threes += scored.open_threes;
-      total.open_fours += scored.open_fours;
+  fn middlegame() -> Board {
+    let mut board = Board::new(15);
+    for (x, y, player) in [(7, 7, Player::B), (8, 8, Player::W), (6, 8, Player::B), (8, 6, Player::W), (7, 9, Player::B)] {
+      board.set(x, y, player);
     }
+    board
   }
 
-  total.score += center_bonus(board, player);
-  if total.open_fours > 0 {
-    total.score += 10_000;
-  }
-  if total.open_threes >= 2 {
-    total.score += 6_000;
-  }
-
-  total
-}
-
-/// 直接在board上评估一条线，避免分配Vec
-fn score_line_direct(
-  board: &Board,
-  player: Player,
-  start_x: usize,
-  start_y: usize,
-  dx: i32,
-  dy: i32,
-  len: usize,
-) -> ScoreBreakdown {
-  let mut out = ScoreBreakdown::default();
-
-  // 使用固定大小数组作为滑动窗口 (最大支持6格窗口用于模式匹配)
-  let mut window: [i8; 6] = [0; 6];
-  let mut window_pos = 0usize;
-
-  let mut x = start_x as i32;
-  let mut y = start_y as i32;
-
-  // 连续棋子序列追踪
-  let mut run_start_idx: Option<usize> = None;
-  let mut prev_val: i8 = -1; // 用于追踪左侧是否开放
-
-  for i in 0..len {
-    let val = cell_value(board, x as usize, y as usize, player);
-
-    // 处理连续序列
-    if val == 1 {
-      if run_start_idx.is_none() {
-        run_start_idx = Some(i);
-      }
-    } else if let Some(start) = run_start_idx {
-      // 连续序列结束
-      let run_len = (i - start) as i32;
-      let left_open = start > 0 && prev_val == 0;
-      let right_open = val == 0;
-      let open_ends = left_open as i32 + right_open as i32;
-
-      out.score += run_score(run_len, open_ends);
-      if run_len == 4 && open_ends == 2 {
-        out.open_fours += 1;
-      }
-      if run_len == 3 && open_ends == 2 {
-        out.open_threes += 1;
-      }
-      run_start_idx = None;
-    }
-
-    // 更新前一个值 (用于下一个序列的左侧开放判断)
-    if val != 1 {
-      prev_val = val;
-    }
-
-    // 滑动窗口模式匹配
-    window[window_pos % 6] = val;
-    window_pos += 1;
-
-    if window_pos >= 6 {
-      // 重构窗口为正确顺序
-      let w = [
-        window[(window_pos + 0) % 6],
-        window[(window_pos + 1) % 6],
-        window[(window_pos + 2) % 6],
-        window[(window_pos + 3) % 6],
-        window[(window_pos + 4) % 6],
-        window[(window_pos + 5) % 6],
-      ];
-      // 6格模式
-      if w == [0, 1, 1, 0, 1, 0] || w == [0, 1, 0, 1, 1, 0] {
-        out.score += SCORE_BROKEN_THREE;
-        out.open_threes += 1;
-      }
-      if w == [0, 1, 1, 1, 0, 1] || w == [1, 0, 1, 1, 1, 0] {
-        out.score += SCORE_BROKEN_FOUR;
-      }
-    }
-
-    if window_pos >= 5 {
-      // 5格模式
-      let w5 = [
-        window[(window_pos + 1) % 6],
-        window[(window_pos + 2) % 6],
-        window[(window_pos + 3) % 6],
-        window[(window_pos + 4) % 6],
-        window[(window_pos + 5) % 6],
-      ];
-      if w5 == [0, 1, 0, 1, 0] {
-        out.score += SCORE_OPEN_TWO;
-      }
-      if w5 == [1, 1, 1, 0, 1] || w5 == [1, 0, 1, 1, 1] {
-        out.score += SCORE_BROKEN_FOUR;
-      }
-    }
-
-    x += dx;
-    y += dy;
-  }
-
-  // 处理末尾的连续序列
-  if let Some(start) = run_start_idx {
-    let run_len = (len - start) as i32;
-    let left_open = start > 0 && prev_val == 0;
-    let right_open = false; // 到达边界
-    let open_ends = left_open as i32 + right_open as i32;
-
-    out.score += run_score(run_len, open_ends);
-    if run_len == 4 && open_ends == 2 {
-      out.open_fours += 1;
-    }
-    if run_len == 3 && open_ends == 2 {
-      out.open_threes += 1;
-    }
+  fn search_nodes(board: &Board, tt: &TranspositionTable) -> u64 {
+    analyze(board, RuleSetKind::Standard, Player::W, search_config(), usize::MAX, tt)
+      .iter()
+      .map(|line| line.nodes)
+      .sum()
   }
 
-  out
-}
+  #[test]
+  fn persistent_table_saves_nodes() {
+    let board = middlegame();
+    let tt = TranspositionTable::new(4);
+    let cold = search_nodes(&board, &tt);
+    let warm = search_nodes(&board, &tt);
+    assert!(warm < cold, "warm search took {} nodes, cold {}", warm, cold);
 
-fn cell_value(board: &Board, x: usize, y: usize, player: Player) -> i8 {
-  match board.get(x, y) {
-    None => 0,
-    Some(p) if p == player => 1,
-    _ => 2,
+    // The same search from an empty table repeats the cold count exactly
+    assert_eq!(search_nodes(&board, &TranspositionTable::new(4)), cold);
   }
-}
 
-fn run_score(len: i32, open_ends: i32) -> i32 {
-  match (len, open_ends) {
-    (5..=i32::MAX, _) => SCORE_FIVE,
-    (4, 2) => SCORE_OPEN_FOUR,
-    (4, 1) => SCORE_SEMI_FOUR,
-    (3, 2) => SCORE_OPEN_THREE,
-    (3, 1) => SCORE_SEMI_THREE,
-    (2, 2) => SCORE_OPEN_TWO,
-    (2, 1) => SCORE_SEMI_TWO,
-    (1, 2) => SCORE_OPEN_ONE,
-    _ => 0,
+  #[test]
+  fn table_carries_over_to_the_next_move() {
+    let mut board = middlegame();
+    let tt = TranspositionTable::new(4);
+    search_nodes(&board, &tt);
+    let reply = choose_move(&board, RuleSetKind::Standard, Player::W, search_config(), &tt).expect("a move");
+    board.set(reply.x, reply.y, Player::W);
+    let next = choose_move(&board, RuleSetKind::Standard, Player::B, search_config(), &tt).expect("a move");
+    board.set(next.x, next.y, Player::B);
+
+    let warm = search_nodes(&board, &tt);
+    let cold = search_nodes(&board, &TranspositionTable::new(4));
+    assert!(warm < cold, "warm search took {} nodes, cold {}", warm, cold);
   }
 }
-
-fn center_bonus(board: &Board, player: Player) -> i32 {
-  let size = board.size() as i32;
-  let center = (size - 1) / 2;
-  let mut score = 0;
-
-  for y in 0..size {
-    for x in 0..size {
-      if board.get(x as usize, y as usize) == Some(player) {
-        let dist = (x - center).abs() + (y - center).abs();
-        score += (size - dist) / 3;
-      }
-    }
-  }
-
-  score
-}
@@ -0,0 +1,99 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::GameRecord;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveFormat {
+  /// One saved-game file per game, loadable like any other saved game.
+  Directory,
+  /// One `GameRecord` per line in a single file.
+  Jsonl,
+}
+
+/// Where a self-play run or tournament writes the games it plays. Each game
+/// is written as soon as it finishes, so a run that is stopped or crashes
+/// keeps the games it got through.
+pub struct GameArchive {
+  path: PathBuf,
+  jsonl: Option<BufWriter<File>>,
+  games: u32,
+}
+
+impl GameArchive {
+  /// Creates the directory, or opens the JSONL file for appending so
+  /// several runs can share one archive.
+  pub fn create(path: &Path, format: ArchiveFormat) -> Result<Self, String> {
+    let jsonl = match format {
+      ArchiveFormat::Directory => {
+        fs::create_dir_all(path).map_err(|e| format!("Cannot create {}: {}", path.display(), e))?;
+        None
+      }
+      ArchiveFormat::Jsonl => {
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+          fs::create_dir_all(parent).map_err(|e| format!("Cannot create {}: {}", parent.display(), e))?;
+        }
+        let file = OpenOptions::new()
+          .create(true)
+          .append(true)
+          .open(path)
+          .map_err(|e| format!("Cannot open {}: {}", path.display(), e))?;
+        Some(BufWriter::new(file))
+      }
+    };
+    Ok(Self {
+      path: path.to_path_buf(),
+      jsonl,
+      games: 0,
+    })
+  }
+
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  /// Games written so far.
+  pub fn games(&self) -> u32 {
+    self.games
+  }
+
+  pub fn write(&mut self, record: &GameRecord) -> Result<(), String> {
+    match self.jsonl.as_mut() {
+      Some(writer) => {
+        let line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+        writeln!(writer, "{}", line).map_err(|e| e.to_string())?;
+        writer.flush().map_err(|e| e.to_string())?;
+      }
+      None => {
+        // Numbered by the game's place in the run, so files sort in schedule order
+        let number = record
+          .meta
+          .self_play
+          .as_ref()
+          .map_or(self.games as u64, |self_play| self_play.game);
+        let name = format!(
+          "{:05}-{}-{}.json",
+          number + 1,
+          file_name(&record.players.black),
+          file_name(&record.players.white)
+        );
+        let data = serde_json::to_string_pretty(record).map_err(|e| e.to_string())?;
+        fs::write(self.path.join(name), data).map_err(|e| e.to_string())?;
+      }
+    }
+    self.games += 1;
+    Ok(())
+  }
+}
+
+// Player names as they appear in file names
+fn file_name(name: &str) -> String {
+  name
+    .chars()
+    .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+    .collect()
+}
@@ -0,0 +1,377 @@
+// Headless tournaments and ladder calibration, for long runs on a server
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use gomoku::archive::{ArchiveFormat, GameArchive};
+use gomoku::rating::{run_self_play, RatingStore, SelfPlaySettings};
+use gomoku::sprt::{run_sprt, SprtSettings, SprtVerdict};
+use gomoku::tournament::{
+  format_crosstable, resolve_players, run_tournament, save_report, TournamentFormat, TournamentPlayer,
+  TournamentSettings,
+};
+use gomoku::types::{EngineConfig, GameResult, RuleSetKind};
+
+const DEFAULT_ENGINE_TIME_MS: u64 = 5000;
+const ENGINE_MEMORY_MB: u64 = 256;
+
+const USAGE: &str = "Usage: gomoku-tournament [options] [PLAYER ...]
+
+Plays a tournament between ladder profiles (l01..l12), each optionally with
+its own evaluation weights file, or external Gomocup engines, and writes
+every game (to games/, or to games.jsonl with --jsonl) plus results.json and
+crosstable.txt to the output directory.
+
+Players are [NAME=]PROFILE[:WEIGHTS] or [NAME=]engine:PATH.
+
+With --sprt the first player is tested against the second in pairs of games
+sharing a random opening, until the result is clear either way.
+
+Options:
+  --config FILE      JSON settings (format, players, gamesPerPair, parallelism,
+                     boardSize, ruleSet, seed, outputDir, and sprt with
+                     elo0, elo1, alpha, beta, maxPairs, openingPlies);
+                     flags override them
+  --gauntlet         The first player meets each of the others
+  --round-robin      Every player meets every other player (default)
+  --games N          Games per pairing, colours alternating (default 2)
+  --parallel N       Games played at once (default: one per core)
+  --board-size N     Board size, 9 to 20 (default 15)
+  --rule RULE        standard, renju, exact_five or exact_five_black
+  --seed N           Seed of the games, to repeat a run
+  --out DIR          Output directory (default: tournament)
+  --jsonl            Write the games to one games.jsonl file
+  --calibrate        Run ladder self-play instead, updating the --ratings file
+  --levels A-B       Levels calibrated with --calibrate (default 1-12)
+  --ratings FILE     Ratings file to calibrate, created if missing (required
+                     with --calibrate)
+  --engine-time MS   Time per move of engine players (default 5000)
+  --sprt             Test the first player against the second
+  --elo0 N           Elo gain of the null hypothesis (default 0)
+  --elo1 N           Elo gain of the alternative hypothesis (default 10)
+  --alpha P          False positive rate (default 0.05)
+  --beta P           False negative rate (default 0.05)
+  --max-pairs N      Pairs played before giving up undecided (default 2000)
+  --opening-plies N  Random moves opening each pair, up to 8 (default 4)
+  --help             Show this help";
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfigFile {
+  #[serde(flatten)]
+  settings: TournamentSettings,
+  #[serde(default)]
+  output_dir: Option<String>,
+  #[serde(default)]
+  sprt: SprtSettings,
+}
+
+struct Options {
+  settings: TournamentSettings,
+  output_dir: PathBuf,
+  ratings_path: Option<PathBuf>,
+  archive_format: ArchiveFormat,
+  calibrate: bool,
+  levels: (u8, u8),
+  sprt: Option<SprtSettings>,
+}
+
+fn main() {
+  let result = parse_args(env::args().skip(1).collect()).and_then(|options| {
+    if options.calibrate {
+      calibrate(&options)
+    } else if options.sprt.is_some() {
+      sprt(&options)
+    } else {
+      tournament(&options)
+    }
+  });
+  if let Err(err) = result {
+    eprintln!("{}", err);
+    process::exit(1);
+  }
+}
+
+fn parse_args(args: Vec<String>) -> Result<Options, String> {
+  // The config file comes first so flags anywhere on the line override it
+  let mut file = ConfigFile::default();
+  if let Some(pos) = args.iter().position(|arg| arg == "--config") {
+    let path = args.get(pos + 1).ok_or("--config needs a file")?;
+    let data = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    file = serde_json::from_str(&data).map_err(|e| format!("Invalid config {}: {}", path, e))?;
+  }
+  let mut options = Options {
+    settings: file.settings,
+    output_dir: PathBuf::from(file.output_dir.unwrap_or_else(|| "tournament".to_string())),
+    ratings_path: None,
+    archive_format: ArchiveFormat::Directory,
+    calibrate: false,
+    levels: (1, 12),
+    sprt: None,
+  };
+  let mut sprt = file.sprt;
+  let mut engine_time = DEFAULT_ENGINE_TIME_MS;
+
+  let mut specs = Vec::new();
+  let mut args = args.into_iter();
+  while let Some(arg) = args.next() {
+    let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+    match arg.as_str() {
+      "--help" | "-h" => {
+        println!("{}", USAGE);
+        process::exit(0);
+      }
+      "--config" => {
+        value("--config")?;
+      }
+      "--gauntlet" => options.settings.format = TournamentFormat::Gauntlet,
+      "--round-robin" => options.settings.format = TournamentFormat::RoundRobin,
+      "--games" => options.settings.games_per_pair = parse_number("--games", &value("--games")?)?,
+      "--parallel" => options.settings.parallelism = parse_number("--parallel", &value("--parallel")?)?,
+      "--board-size" => options.settings.board_size = parse_number("--board-size", &value("--board-size")?)?,
+      "--rule" => options.settings.rule_set = parse_rule(&value("--rule")?)?,
+      "--seed" => options.settings.seed = Some(parse_number("--seed", &value("--seed")?)?),
+      "--engine-time" => engine_time = parse_number("--engine-time", &value("--engine-time")?)?,
+      "--sprt" => options.sprt = Some(SprtSettings::default()),
+      "--elo0" => sprt.elo0 = parse_number("--elo0", &value("--elo0")?)?,
+      "--elo1" => sprt.elo1 = parse_number("--elo1", &value("--elo1")?)?,
+      "--alpha" => sprt.alpha = parse_number("--alpha", &value("--alpha")?)?,
+      "--beta" => sprt.beta = parse_number("--beta", &value("--beta")?)?,
+      "--max-pairs" => sprt.max_pairs = parse_number("--max-pairs", &value("--max-pairs")?)?,
+      "--opening-plies" => sprt.opening_plies = parse_number("--opening-plies", &value("--opening-plies")?)?,
+      "--out" => options.output_dir = PathBuf::from(value("--out")?),
+      "--jsonl" => options.archive_format = ArchiveFormat::Jsonl,
+      "--calibrate" => options.calibrate = true,
+      "--levels" => options.levels = parse_levels(&value("--levels")?)?,
+      "--ratings" => options.ratings_path = Some(PathBuf::from(value("--ratings")?)),
+      flag if flag.starts_with("--") => return Err(format!("Unknown option {}\n\n{}", flag, USAGE)),
+      spec => specs.push(spec.to_string()),
+    }
+  }
+  if !specs.is_empty() {
+    options.settings.players = specs.iter().map(|spec| parse_player(spec, engine_time)).collect();
+  }
+  if options.sprt.is_some() {
+    // The match settings shared with tournaments come from the same flags
+    sprt.parallelism = options.settings.parallelism;
+    sprt.board_size = options.settings.board_size;
+    sprt.rule_set = options.settings.rule_set;
+    sprt.seed = options.settings.seed.or(sprt.seed);
+    options.sprt = Some(sprt);
+  }
+  Ok(options)
+}
+
+fn parse_number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+  value.parse().map_err(|_| format!("Invalid value for {}: {}", name, value))
+}
+
+fn parse_rule(value: &str) -> Result<RuleSetKind, String> {
+  serde_json::from_value(serde_json::Value::String(value.replace('-', "_")))
+    .map_err(|_| format!("Unknown rule set {}", value))
+}
+
+fn parse_levels(value: &str) -> Result<(u8, u8), String> {
+  let (min, max) = value.split_once('-').unwrap_or((value, value));
+  let min: u8 = parse_number("--levels", min)?;
+  let max: u8 = parse_number("--levels", max)?;
+  if min == 0 || min > max || max > 12 {
+    return Err(format!("Invalid level range {}", value));
+  }
+  Ok((min, max))
+}
+
+// [NAME=]PROFILE[:WEIGHTS] or [NAME=]engine:PATH
+fn parse_player(spec: &str, engine_time: u64) -> TournamentPlayer {
+  let (name, rest) = spec.split_once('=').unwrap_or(("", spec));
+  if let Some(path) = rest.strip_prefix("engine:") {
+    return TournamentPlayer {
+      name: name.to_string(),
+      profile: None,
+      config: None,
+      weights: None,
+      engine: Some(EngineConfig {
+        path: path.to_string(),
+        args: Vec::new(),
+        timeout_turn_ms: engine_time,
+        max_memory_mb: ENGINE_MEMORY_MB,
+      }),
+    };
+  }
+  let (profile, weights) = match rest.split_once(':') {
+    Some((profile, weights)) => (profile, Some(weights.to_string())),
+    None => (rest, None),
+  };
+  TournamentPlayer {
+    name: name.to_string(),
+    profile: Some(profile.to_string()),
+    config: None,
+    weights,
+    engine: None,
+  }
+}
+
+fn tournament(options: &Options) -> Result<(), String> {
+  let ladder = ladder(options)?;
+  let players = resolve_players(&options.settings.players, &ladder)?;
+  let mut archive = create_archive(options)?;
+
+  let stop_flag = AtomicBool::new(false);
+  let report = run_tournament(&options.settings, &players, &stop_flag, |report, record| {
+    let game = report.games.last().ok_or("Missing game")?;
+    archive.write(record)?;
+    save_report(report, &options.output_dir)?;
+
+    let result = match game.result {
+      GameResult::BWin => "1-0",
+      GameResult::WWin => "0-1",
+      GameResult::Draw => "½-½",
+    };
+    println!(
+      "[{}/{}] {} - {} {} ({} moves)",
+      report.completed_games, report.total_games, record.players.black, record.players.white, result, game.moves
+    );
+    // The full table once per round
+    let pairs = report.total_games / report.games_per_pair;
+    if report.completed_games % pairs == 0 {
+      println!("\n{}", format_crosstable(report));
+    }
+    Ok(())
+  })?;
+
+  save_report(&report, &options.output_dir)?;
+  if report.completed_games % (report.total_games / report.games_per_pair) != 0 {
+    println!("\n{}", format_crosstable(&report));
+  }
+  println!("Seed {} · results in {}", report.seed, options.output_dir.display());
+  Ok(())
+}
+
+fn calibrate(options: &Options) -> Result<(), String> {
+  let ratings_path = options
+    .ratings_path
+    .as_deref()
+    .ok_or("--calibrate needs the ratings file to update: pass --ratings FILE")?;
+  let mut store = if ratings_path.exists() {
+    load_ratings(ratings_path)?
+  } else {
+    println!("Starting {} from the built-in ratings", ratings_path.display());
+    RatingStore::default()
+  };
+  let mut archive = create_archive(options)?;
+  let (min_level, max_level) = options.levels;
+  let report = run_self_play(
+    &mut store,
+    ratings_path,
+    SelfPlaySettings {
+      games_per_pair: options.settings.games_per_pair,
+      parallelism: options.settings.parallelism,
+      min_level,
+      max_level,
+      seed: options.settings.seed,
+      archive: Some(&mut archive),
+    },
+    Arc::new(AtomicBool::new(false)),
+    |completed, total| {
+      if completed > 0 {
+        println!("[{}/{}]", completed, total);
+      }
+    },
+  )?;
+
+  let mut ladder = String::new();
+  let mut profiles: Vec<_> = store.profiles.iter().collect();
+  profiles.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+  for profile in profiles {
+    ladder.push_str(&format!(
+      "{:<10} {:>6.0} {:>6} games  {}-{}-{}\n",
+      profile.name, profile.rating, profile.games, profile.wins, profile.draws, profile.losses
+    ));
+  }
+  println!("\n{}", ladder);
+
+  let data = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+  fs::write(options.output_dir.join("results.json"), data).map_err(|e| e.to_string())?;
+  fs::write(options.output_dir.join("ladder.txt"), ladder).map_err(|e| e.to_string())?;
+  println!(
+    "Seed {} · ratings saved to {} · results in {}",
+    report.seed,
+    ratings_path.display(),
+    options.output_dir.display()
+  );
+  Ok(())
+}
+
+fn sprt(options: &Options) -> Result<(), String> {
+  let settings = options.sprt.as_ref().ok_or("Missing SPRT settings")?;
+  if options.settings.players.len() != 2 {
+    return Err("--sprt needs exactly two players: the candidate and the baseline".to_string());
+  }
+  let ladder = ladder(options)?;
+  let players = resolve_players(&options.settings.players, &ladder)?;
+  let mut archive = create_archive(options)?;
+  let results = options.output_dir.join("results.json");
+
+  let stop_flag = AtomicBool::new(false);
+  let report = run_sprt(settings, &players[0], &players[1], &stop_flag, |report, records| {
+    for record in records {
+      archive.write(record)?;
+    }
+    let data = serde_json::to_string_pretty(report).map_err(|e| e.to_string())?;
+    fs::write(&results, data).map_err(|e| e.to_string())?;
+    println!(
+      "[{}] LLR {:.2} ({:.2}, {:.2}) · Elo {:+.1} ± {:.1} · {}-{}-{} · pairs {:?}",
+      report.pairs,
+      report.llr,
+      report.lower_bound,
+      report.upper_bound,
+      report.elo.unwrap_or(0.0),
+      report.elo_margin.unwrap_or(0.0),
+      report.wins,
+      report.draws,
+      report.losses,
+      report.pentanomial
+    );
+    Ok(())
+  })?;
+
+  let verdict = match report.verdict {
+    Some(SprtVerdict::H1) => format!("H1 accepted: {} gains at least {} Elo", report.candidate, report.elo1),
+    Some(SprtVerdict::H0) => format!("H0 accepted: {} gains no more than {} Elo", report.candidate, report.elo0),
+    None if report.stopped => "Stopped undecided".to_string(),
+    None => format!("Undecided after {} pairs", report.pairs),
+  };
+  println!("\n{}", verdict);
+  println!("Seed {} · results in {}", report.seed, options.output_dir.display());
+  Ok(())
+}
+
+// Profile configs are built in; a ratings file only adds its ratings, and
+// one given explicitly has to be readable
+fn ladder(options: &Options) -> Result<RatingStore, String> {
+  match &options.ratings_path {
+    Some(path) => load_ratings(path),
+    None => Ok(RatingStore::default()),
+  }
+}
+
+fn load_ratings(path: &Path) -> Result<RatingStore, String> {
+  let data = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+  let mut store: RatingStore =
+    serde_json::from_str(&data).map_err(|e| format!("Invalid ratings file {}: {}", path.display(), e))?;
+  store.ensure_profiles();
+  Ok(store)
+}
+
+fn create_archive(options: &Options) -> Result<GameArchive, String> {
+  let path = match options.archive_format {
+    ArchiveFormat::Directory => options.output_dir.join("games"),
+    ArchiveFormat::Jsonl => options.output_dir.join("games.jsonl"),
+  };
+  GameArchive::create(&path, options.archive_format)
+}
@@ -0,0 +1,8 @@
+// Gomocup protocol engine for Piskvork-compatible tournament managers
+use std::io;
+
+use gomoku::gomocup::run_brain;
+
+fn main() -> io::Result<()> {
+  run_brain(io::stdin().lock(), io::stdout().lock())
+}
@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::engine::Board;
+use crate::rules::rules_for;
+use crate::types::{Coord, GameRecord, GameResult, Move, Player, RuleSetKind, Symmetry};
+
+pub const BOOK_VERSION: u32 = 1;
+pub const DEFAULT_BOOK_PLIES: usize = 12;
+
+// Mixed into the key when White is to move, so the same stones with the
+// other side to move (after a swap, say) are a different book position
+const WHITE_TO_MOVE_KEY: u64 = 0x2545_F491_4F6C_DD1D;
+
+/// A move seen in a book position, with how the games went for the side
+/// that played it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookMove {
+  pub x: usize,
+  pub y: usize,
+  pub wins: u32,
+  pub draws: u32,
+  pub losses: u32,
+}
+
+impl BookMove {
+  pub fn games(&self) -> u32 {
+    self.wins + self.draws + self.losses
+  }
+
+  /// Average score for the mover, pulled towards 0.5 while there are few games.
+  pub fn expected_score(&self) -> f64 {
+    (self.wins as f64 + self.draws as f64 / 2.0 + 1.0) / (self.games() as f64 + 2.0)
+  }
+}
+
+/// Moves played from known positions in earlier games. Positions are keyed by
+/// a Zobrist hash normalised over the board's eight symmetries, and moves are
+/// stored in that normalised orientation, so rotated and mirrored games share
+/// their statistics.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpeningBook {
+  pub version: u32,
+  pub board_size: usize,
+  pub rule_set: RuleSetKind,
+  /// Only the first this many moves of each game are recorded.
+  pub max_plies: usize,
+  pub games: u32,
+  pub positions: HashMap<u64, Vec<BookMove>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookSummary {
+  pub board_size: usize,
+  pub rule_set: RuleSetKind,
+  pub games: u32,
+  pub positions: usize,
+  pub moves: usize,
+  /// Files read while building that were not usable game records.
+  pub skipped_files: u32,
+}
+
+impl OpeningBook {
+  pub fn new(board_size: usize, rule_set: RuleSetKind, max_plies: usize) -> Self {
+    Self {
+      version: BOOK_VERSION,
+      board_size,
+      rule_set,
+      max_plies,
+      games: 0,
+      positions: HashMap::new(),
+    }
+  }
+
+  pub fn load(path: &Path) -> Result<Self, String> {
+    let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let book: OpeningBook = serde_json::from_str(&data).map_err(|e| format!("Invalid opening book: {}", e))?;
+    if book.version != BOOK_VERSION {
+      return Err(format!("Unsupported opening book version {}", book.version));
+    }
+    Ok(book)
+  }
+
+  pub fn save(&self, path: &Path) -> Result<(), String> {
+    let data = serde_json::to_string(self).map_err(|e| e.to_string())?;
+    fs::write(path, data).map_err(|e| e.to_string())
+  }
+
+  /// Builds a book from every saved game (`*.json` `GameRecord`) in `dir`
+  /// that was played on the given board size and rule set and has a result.
+  /// Returns the book and the number of files that could not be read.
+  pub fn build(dir: &Path, board_size: usize, rule_set: RuleSetKind, max_plies: usize) -> Result<(Self, u32), String> {
+    let mut book = OpeningBook::new(board_size, rule_set, max_plies);
+    let mut skipped = 0;
+    let entries = fs::read_dir(dir).map_err(|e| e.to_string())?;
+    for entry in entries {
+      let path = entry.map_err(|e| e.to_string())?.path();
+      if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+        continue;
+      }
+      let record = fs::read_to_string(&path)
+        .ok()
+        .and_then(|data| serde_json::from_str::<GameRecord>(&data).ok());
+      match record {
+        Some(record) => {
+          book.add_game(&record);
+        }
+        None => skipped += 1,
+      }
+    }
+    Ok((book, skipped))
+  }
+
+  /// Adds the opening of a finished game. Returns false (and adds nothing)
+  /// if the game does not match the book's board size and rule set.
+  pub fn add_game(&mut self, record: &GameRecord) -> bool {
+    let Some(result) = record.result else {
+      return false;
+    };
+    if record.board_size != self.board_size || record.rule_set != self.rule_set {
+      return false;
+    }
+
+    let mut board = Board::new(self.board_size);
+    for mv in record.moves.iter().take(self.max_plies) {
+      if !board.is_empty(mv.x, mv.y) {
+        break;
+      }
+      let (key, symmetries) = position_key(&board, mv.player);
+      let Coord { x, y } = canonical_move(self.board_size, &symmetries, Coord { x: mv.x, y: mv.y });
+      let moves = self.positions.entry(key).or_default();
+      let index = match moves.iter().position(|book_move| book_move.x == x && book_move.y == y) {
+        Some(index) => index,
+        None => {
+          moves.push(BookMove {
+            x,
+            y,
+            wins: 0,
+            draws: 0,
+            losses: 0,
+          });
+          moves.len() - 1
+        }
+      };
+      let book_move = &mut moves[index];
+      match (result, mv.player) {
+        (GameResult::Draw, _) => book_move.draws += 1,
+        (GameResult::BWin, Player::B) | (GameResult::WWin, Player::W) => book_move.wins += 1,
+        _ => book_move.losses += 1,
+      }
+      board.set(mv.x, mv.y, mv.player);
+    }
+    self.games += 1;
+    true
+  }
+
+  /// Book moves for `player` in this position, in the board's own orientation.
+  pub fn moves(&self, board: &Board, rule_set: RuleSetKind, player: Player) -> Vec<(Coord, &BookMove)> {
+    if board.size() != self.board_size || rule_set != self.rule_set {
+      return Vec::new();
+    }
+    let (key, symmetries) = position_key(board, player);
+    let Some(moves) = self.positions.get(&key) else {
+      return Vec::new();
+    };
+    let rules = rules_for(rule_set);
+    let symmetry = symmetries[0].inverse();
+    moves
+      .iter()
+      .filter_map(|book_move| {
+        let (x, y) = symmetry.apply(self.board_size, book_move.x, book_move.y);
+        let mv = Move {
+          x,
+          y,
+          player,
+          t: None,
+        };
+        rules.is_legal(board, &mv).then_some((Coord { x, y }, book_move))
+      })
+      .collect()
+  }
+
+  /// Picks a book move for `player`, or None when the position is not in
+  /// the book. With `variety` 0 the move with the best record is played;
+  /// higher values choose more evenly between the book moves.
+  pub fn pick(
+    &self,
+    board: &Board,
+    rule_set: RuleSetKind,
+    player: Player,
+    variety: u8,
+    rng: &mut impl Rng,
+  ) -> Option<Coord> {
+    let moves = self.moves(board, rule_set, player);
+    if variety == 0 {
+      return moves
+        .iter()
+        .max_by(|a, b| a.1.expected_score().total_cmp(&b.1.expected_score()))
+        .map(|(coord, _)| *coord);
+    }
+    let sharpness = 10.0 / variety as f64;
+    let weights: Vec<f64> = moves
+      .iter()
+      .map(|(_, book_move)| book_move.expected_score().powf(sharpness))
+      .collect();
+    let total: f64 = weights.iter().sum();
+    if total <= 0.0 {
+      return None;
+    }
+    let mut target = rng.gen_range(0.0..total);
+    for ((coord, _), weight) in moves.iter().zip(weights) {
+      if target < weight {
+        return Some(*coord);
+      }
+      target -= weight;
+    }
+    moves.last().map(|(coord, _)| *coord)
+  }
+
+  pub fn summary(&self, skipped_files: u32) -> BookSummary {
+    BookSummary {
+      board_size: self.board_size,
+      rule_set: self.rule_set,
+      games: self.games,
+      positions: self.positions.len(),
+      moves: self.positions.values().map(|moves| moves.len()).sum(),
+      skipped_files,
+    }
+  }
+}
+
+// Key of the position with `player` to move, and the symmetries that map
+// the board onto the orientation the book stores it in
+fn position_key(board: &Board, player: Player) -> (u64, Vec<Symmetry>) {
+  let (hash, symmetries) = board.canonical_symmetries();
+  let side_key = if player == Player::W { WHITE_TO_MOVE_KEY } else { 0 };
+  (hash ^ side_key, symmetries)
+}
+
+// A move in the stored orientation. When several symmetries normalise the
+// position, equivalent moves are folded together by taking the smallest image.
+fn canonical_move(size: usize, symmetries: &[Symmetry], coord: Coord) -> Coord {
+  symmetries
+    .iter()
+    .map(|&symmetry| coord.transformed(size, symmetry))
+    .min_by_key(|coord| (coord.y, coord.x))
+    .unwrap_or(coord)
+}
@@ -1,21 +1,36 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 use lazy_static::lazy_static;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 use crate::rules::rules_for;
 use crate::types::{
-  Coord, GameMode, GameRecord, GameResult, GameSnapshot, Meta, Move, Player, Players, RuleSetKind,
-  TrainingSample,
+  ClockState, Coord, GameMode, GameRecord, GameResult, GameSnapshot, Meta, Move, OpeningChoice,
+  OpeningDecision, OpeningPhase, OpeningRule, OpeningState, Player, Players, RuleSetKind, Symmetry,
+  Termination, TimeControl, TrainingSample,
 };
 
+pub const MIN_BOARD_SIZE: usize = 9;
+pub const MAX_BOARD_SIZE: usize = 20;
+pub const DEFAULT_BOARD_SIZE: usize = 15;
+
+const ZOBRIST_CELLS: usize = MAX_BOARD_SIZE * MAX_BOARD_SIZE;
+// Fixed so hashes are the same in every run; opening books store them on disk
+const ZOBRIST_SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+
+// The engine plans its time as if this many moves were still to come
+const MOVES_TO_GO: u64 = 25;
+// Held back from every thinking budget so the reply lands before the flag falls
+const CLOCK_SAFETY_MS: u64 = 50;
+
 // Zobrist hashing table for transposition table
-// 225 = 15x15 board, 2 players (Black and White)
+// Sized for the largest supported board, 2 players (Black and White)
 lazy_static! {
-  pub static ref ZOBRIST_TABLE: [[u64; 2]; 225] = {
-    let mut table = [[0u64; 2]; 225];
-    let mut rng = rand::thread_rng();
-    for i in 0..225 {
+  pub static ref ZOBRIST_TABLE: [[u64; 2]; ZOBRIST_CELLS] = {
+    let mut table = [[0u64; 2]; ZOBRIST_CELLS];
+    let mut rng = StdRng::seed_from_u64(ZOBRIST_SEED);
+    for i in 0..ZOBRIST_CELLS {
       table[i][0] = rng.gen(); // Black
       table[i][1] = rng.gen(); // White
     }
@@ -23,6 +38,17 @@ lazy_static! {
   };
 }
 
+pub fn check_board_size(size: usize) -> Result<(), String> {
+  if (MIN_BOARD_SIZE..=MAX_BOARD_SIZE).contains(&size) {
+    Ok(())
+  } else {
+    Err(format!(
+      "Board size must be between {} and {}, got {}",
+      MIN_BOARD_SIZE, MAX_BOARD_SIZE, size
+    ))
+  }
+}
+
 #[derive(Clone, Debug)]
 pub struct Board {
   size: usize,
@@ -62,26 +88,14 @@ impl Board {
     let idx = self.index(x, y);
     self.cells[idx] = Some(player);
     // Incremental hash update: XOR in the new piece
-    if idx < 225 {
-      let player_idx = match player {
-        Player::B => 0,
-        Player::W => 1,
-      };
-      self.hash ^= ZOBRIST_TABLE[idx][player_idx];
-    }
+    self.hash ^= ZOBRIST_TABLE[idx][player_index(player)];
   }
 
   pub fn clear(&mut self, x: usize, y: usize) {
     let idx = self.index(x, y);
     // Incremental hash update: XOR out the removed piece before clearing
-    if idx < 225 {
-      if let Some(player) = self.cells[idx] {
-        let player_idx = match player {
-          Player::B => 0,
-          Player::W => 1,
-        };
-        self.hash ^= ZOBRIST_TABLE[idx][player_idx];
-      }
+    if let Some(player) = self.cells[idx] {
+      self.hash ^= ZOBRIST_TABLE[idx][player_index(player)];
     }
     self.cells[idx] = None;
   }
@@ -117,9 +131,162 @@ impl Board {
 
   // Compute Zobrist hash from scratch (for verification or backward compatibility)
   pub fn zobrist_hash(&self) -> u64 {
-    // Return cached hash - O(1) instead of O(225)
+    // Return cached hash - O(1) instead of O(size^2)
     self.hash
   }
+
+  /// The position with every stone moved by `symmetry`.
+  pub fn transformed(&self, symmetry: Symmetry) -> Board {
+    let mut board = Board::new(self.size);
+    for y in 0..self.size {
+      for x in 0..self.size {
+        if let Some(player) = self.get(x, y) {
+          let (tx, ty) = symmetry.apply(self.size, x, y);
+          board.set(tx, ty, player);
+        }
+      }
+    }
+    board
+  }
+
+  /// Zobrist hash of `transformed(symmetry)`, without building that board.
+  pub fn symmetric_hash(&self, symmetry: Symmetry) -> u64 {
+    let mut hash = 0;
+    for (idx, cell) in self.cells.iter().enumerate() {
+      if let Some(player) = cell {
+        let (tx, ty) = symmetry.apply(self.size, idx % self.size, idx / self.size);
+        hash ^= ZOBRIST_TABLE[self.index(tx, ty)][player_index(*player)];
+      }
+    }
+    hash
+  }
+
+  /// Smallest hash over the eight symmetric images of the position, so
+  /// positions that differ only by rotation or reflection hash the same.
+  pub fn canonical_hash(&self) -> u64 {
+    self.canonical_symmetries().0
+  }
+
+  /// The canonical hash and every symmetry that maps this position onto the
+  /// canonical one. There is more than one when the position is itself
+  /// symmetric, e.g. a lone stone in the centre.
+  pub fn canonical_symmetries(&self) -> (u64, Vec<Symmetry>) {
+    let mut hashes = [0u64; 8];
+    for (idx, cell) in self.cells.iter().enumerate() {
+      if let Some(player) = cell {
+        let (x, y) = (idx % self.size, idx / self.size);
+        for (hash, symmetry) in hashes.iter_mut().zip(Symmetry::ALL) {
+          let (tx, ty) = symmetry.apply(self.size, x, y);
+          *hash ^= ZOBRIST_TABLE[self.index(tx, ty)][player_index(*player)];
+        }
+      }
+    }
+    let canonical = hashes.iter().copied().min().unwrap_or(0);
+    let symmetries = Symmetry::ALL
+      .into_iter()
+      .zip(hashes)
+      .filter(|&(_, hash)| hash == canonical)
+      .map(|(symmetry, _)| symmetry)
+      .collect();
+    (canonical, symmetries)
+  }
+}
+
+// Column of a player's keys in ZOBRIST_TABLE
+fn player_index(player: Player) -> usize {
+  match player {
+    Player::B => 0,
+    Player::W => 1,
+  }
+}
+
+/// Game clock with Fischer increment. Time is charged to the seat that has to
+/// act, which during a Swap/Swap2 opening need not be the colour of the stone.
+#[derive(Clone, Debug)]
+pub struct GameClock {
+  state: ClockState,
+  turn_started: Instant,
+}
+
+impl GameClock {
+  pub fn new(time_control: TimeControl) -> Self {
+    Self::resume(ClockState {
+      time_control,
+      black_ms: time_control.main_ms,
+      white_ms: time_control.main_ms,
+    })
+  }
+
+  /// Continues from saved clock times; the running turn starts now.
+  pub fn resume(state: ClockState) -> Self {
+    Self {
+      state,
+      turn_started: Instant::now(),
+    }
+  }
+
+  /// Clock times with the turn in progress charged to `running`, if any.
+  pub fn state(&self, running: Option<Player>) -> ClockState {
+    let mut state = self.state;
+    match running {
+      Some(Player::B) => state.black_ms = self.remaining_ms(Player::B),
+      Some(Player::W) => state.white_ms = self.remaining_ms(Player::W),
+      None => {}
+    }
+    state
+  }
+
+  /// Time left for `seat` if its clock is the one running.
+  pub fn remaining_ms(&self, seat: Player) -> u64 {
+    self.stored_ms(seat).saturating_sub(self.elapsed_ms())
+  }
+
+  /// How long `seat` may think about its next move: an even share of the
+  /// remaining time plus most of the increment, never the whole clock.
+  pub fn move_budget_ms(&self, seat: Player) -> u64 {
+    let left = self.remaining_ms(seat);
+    let budget = left / MOVES_TO_GO + self.state.time_control.increment_ms * 3 / 4;
+    budget.min(left.saturating_sub(CLOCK_SAFETY_MS)).max(1)
+  }
+
+  // Charges the turn in progress to `seat` and starts the next one.
+  fn end_turn(&mut self, seat: Player, increment: bool) {
+    let left = self.remaining_ms(seat);
+    let bonus = if increment {
+      self.state.time_control.increment_ms
+    } else {
+      0
+    };
+    *self.stored_ms_mut(seat) = left + bonus;
+    self.restart();
+  }
+
+  // Starts a new turn without charging anyone, e.g. after a takeback.
+  fn restart(&mut self) {
+    self.turn_started = Instant::now();
+  }
+
+  fn swap_seats(&mut self) {
+    std::mem::swap(&mut self.state.black_ms, &mut self.state.white_ms);
+  }
+
+  fn elapsed_ms(&self) -> u64 {
+    self.turn_started.elapsed().as_millis() as u64
+  }
+
+  fn stored_ms(&self, seat: Player) -> u64 {
+    match seat {
+      Player::B => self.state.black_ms,
+      Player::W => self.state.white_ms,
+    }
+  }
+
+  fn stored_ms_mut(&mut self, seat: Player) -> &mut u64 {
+    match seat {
+      Player::B => &mut self.state.black_ms,
+      Player::W => &mut self.state.white_ms,
+    }
+  }
 }
 
 #[derive(Clone, Debug)]
@@ -129,11 +296,19 @@ pub struct GameState {
   pub to_move: Player,
   pub moves: Vec<Move>,
   pub result: Option<GameResult>,
+  pub termination: Option<Termination>,
   pub players: Players,
   pub created_at: i64,
   pub updated_at: i64,
   pub game_id: String,
   pub mode: GameMode,
+  pub opening: OpeningState,
+  pub redo_stack: Vec<Move>,
+  // Cleared by any takeback, so a result reached after undoing never counts for Elo
+  pub rated: bool,
+  pub clock: Option<GameClock>,
+  // Seed for the AI's random choices in this game, kept in the record for replays
+  pub seed: u64,
 }
 
 impl GameState {
@@ -145,25 +320,59 @@ impl GameState {
       to_move: Player::B,
       moves: Vec::new(),
       result: None,
+      termination: None,
       players,
       created_at: now,
       updated_at: now,
       game_id: new_game_id(now),
       mode,
+      opening: OpeningState::default(),
+      redo_stack: Vec::new(),
+      rated: true,
+      clock: None,
+      seed: rand::random(),
     }
   }
 
   pub fn snapshot(&self) -> GameSnapshot {
     let can_human_move = self.can_human_move();
+    let forbidden = if self.result.is_none() {
+      rules_for(self.rule_set).forbidden_points(&self.board, self.to_move)
+    } else {
+      Vec::new()
+    };
     GameSnapshot {
       board_size: self.board.size(),
       board: self.board.cells(),
       rule_set: self.rule_set,
       to_move: self.to_move,
       result: self.result,
+      termination: self.termination,
       moves: self.moves.clone(),
       mode: self.mode.clone(),
+      opening: self.opening.clone(),
       can_human_move,
+      forbidden,
+      can_undo: self.can_undo(),
+      can_redo: self.can_redo(),
+      rated: self.rated,
+      clock: self.clock_state(),
+    }
+  }
+
+  fn clock_state(&self) -> Option<ClockState> {
+    let running = self.result.is_none().then(|| self.acting_color());
+    self.clock.as_ref().map(|clock| clock.state(running))
+  }
+
+  /// The seat that has to act next. During a Swap/Swap2 opening this is the
+  /// proposer (Black seat) or responder (White seat), whatever colour the
+  /// stone being placed has; afterwards it is simply the side to move.
+  pub fn acting_color(&self) -> Player {
+    match self.opening.phase {
+      OpeningPhase::ProposerPlace | OpeningPhase::ProposerChoice => Player::B,
+      OpeningPhase::ResponderChoice | OpeningPhase::ResponderPlace => Player::W,
+      OpeningPhase::Done => self.to_move,
     }
   }
 
@@ -172,7 +381,7 @@ impl GameState {
       return false;
     }
     match &self.mode {
-      GameMode::HumanVsAi { human_color } => self.to_move == *human_color,
+      GameMode::HumanVsAi { human_color } => self.acting_color() == *human_color,
       GameMode::HumanVsHuman => true,
       GameMode::AiVsAi { .. } => false,
     }
@@ -183,7 +392,7 @@ impl GameState {
       return false;
     }
     match &self.mode {
-      GameMode::HumanVsAi { human_color } => self.to_move != *human_color,
+      GameMode::HumanVsAi { human_color } => self.acting_color() != *human_color,
       GameMode::HumanVsHuman => false,
       GameMode::AiVsAi { .. } => true,
     }
@@ -193,7 +402,7 @@ impl GameState {
     match &self.mode {
       GameMode::HumanVsAi { .. } => None, // AI profile handled externally
       GameMode::AiVsAi { black_id, white_id } => {
-        if self.to_move == Player::B {
+        if self.acting_color() == Player::B {
           Some(black_id)
         } else {
           Some(white_id)
@@ -204,13 +413,191 @@ impl GameState {
   }
 
   pub fn apply_move(&mut self, x: usize, y: usize) -> Result<(), String> {
+    if self.check_flag() {
+      return Err("Time is up".to_string());
+    }
+    let seat = self.acting_color();
     let mv = Move {
       x,
       y,
       player: self.to_move,
       t: Some(now_ts()),
     };
-    self.apply_existing_move(mv)
+    self.apply_existing_move(mv)?;
+    self.redo_stack.clear();
+    if let Some(clock) = self.clock.as_mut() {
+      clock.end_turn(seat, true);
+    }
+    Ok(())
+  }
+
+  /// Starts the game clocks. Like the opening rule, this has to be settled before the first move.
+  pub fn start_clock(&mut self, time_control: TimeControl) -> Result<(), String> {
+    if !self.moves.is_empty() {
+      return Err("Time control must be chosen before the first move".to_string());
+    }
+    if time_control.main_ms == 0 {
+      return Err("Main time must be positive".to_string());
+    }
+    self.clock = Some(GameClock::new(time_control));
+    Ok(())
+  }
+
+  /// Ends the game if the seat to act has run out of time. Returns true when
+  /// this call decided the game.
+  pub fn check_flag(&mut self) -> bool {
+    if self.result.is_some() {
+      return false;
+    }
+    let seat = self.acting_color();
+    let Some(clock) = self.clock.as_mut() else {
+      return false;
+    };
+    if clock.remaining_ms(seat) > 0 {
+      return false;
+    }
+    clock.end_turn(seat, false);
+    self.result = Some(match seat {
+      Player::B => GameResult::WWin,
+      Player::W => GameResult::BWin,
+    });
+    self.termination = Some(Termination::Timeout);
+    self.updated_at = now_ts();
+    true
+  }
+
+  /// Takes back up to `count` moves, newest first, and returns how many were
+  /// removed. Taken-back moves go onto the redo stack and the game becomes unrated.
+  /// Clocks keep their times; the new turn starts from now.
+  pub fn undo(&mut self, count: usize) -> Result<usize, String> {
+    if self.termination == Some(Termination::Timeout) {
+      return Err("The game was lost on time".to_string());
+    }
+    let floor = self.undo_floor();
+    if self.moves.len() <= floor {
+      return Err("No moves to take back".to_string());
+    }
+    let count = count.min(self.moves.len() - floor);
+    for _ in 0..count {
+      let Some(mv) = self.moves.pop() else {
+        break;
+      };
+      self.board.clear(mv.x, mv.y);
+      self.to_move = mv.player;
+      self.redo_stack.push(mv);
+    }
+    self.result = None;
+    self.termination = None;
+    self.rated = false;
+    if let Some(clock) = self.clock.as_mut() {
+      clock.restart();
+    }
+    self.updated_at = now_ts();
+    Ok(count)
+  }
+
+  /// Replays up to `count` moves from the redo stack and returns how many were replayed.
+  pub fn redo(&mut self, count: usize) -> Result<usize, String> {
+    if !self.can_redo() {
+      return Err("No moves to redo".to_string());
+    }
+    let mut replayed = 0;
+    while replayed < count && self.result.is_none() {
+      let Some(mv) = self.redo_stack.pop() else {
+        break;
+      };
+      if let Err(err) = self.apply_existing_move(mv.clone()) {
+        self.redo_stack.push(mv);
+        return Err(err);
+      }
+      replayed += 1;
+    }
+    if let Some(clock) = self.clock.as_mut() {
+      clock.restart();
+    }
+    Ok(replayed)
+  }
+
+  pub fn can_undo(&self) -> bool {
+    self.termination != Some(Termination::Timeout) && self.moves.len() > self.undo_floor()
+  }
+
+  pub fn can_redo(&self) -> bool {
+    self.result.is_none() && !self.redo_stack.is_empty()
+  }
+
+  // Opening stones placed under Swap/Swap2 are settled by the colour choice
+  // that followed them, so takebacks stop at the last opening decision.
+  fn undo_floor(&self) -> usize {
+    if self.opening.rule == OpeningRule::Free {
+      0
+    } else if self.opening.is_done() {
+      self.opening.decisions.last().map(|d| d.ply).unwrap_or(0)
+    } else {
+      self.moves.len()
+    }
+  }
+
+  pub fn start_opening(&mut self, rule: OpeningRule) -> Result<(), String> {
+    if !self.moves.is_empty() {
+      return Err("Opening protocol must be chosen before the first move".to_string());
+    }
+    self.opening = OpeningState::new(rule);
+    Ok(())
+  }
+
+  pub fn apply_opening_choice(&mut self, choice: OpeningChoice) -> Result<(), String> {
+    if self.check_flag() {
+      return Err("Time is up".to_string());
+    }
+    if self.result.is_some() {
+      return Err("Game is already finished".to_string());
+    }
+    let by = self.acting_color();
+    let (next_phase, swap) = match (self.opening.phase, choice) {
+      (OpeningPhase::ResponderChoice, OpeningChoice::TakeBlack) => (OpeningPhase::Done, true),
+      (OpeningPhase::ResponderChoice, OpeningChoice::TakeWhite) => (OpeningPhase::Done, false),
+      (OpeningPhase::ResponderChoice, OpeningChoice::PlaceTwo)
+        if self.opening.rule == OpeningRule::Swap2 =>
+      {
+        (OpeningPhase::ResponderPlace, false)
+      }
+      (OpeningPhase::ProposerChoice, OpeningChoice::TakeBlack) => (OpeningPhase::Done, false),
+      (OpeningPhase::ProposerChoice, OpeningChoice::TakeWhite) => (OpeningPhase::Done, true),
+      _ if self.opening.awaiting_choice() => {
+        return Err("Choice not allowed at this point of the opening".to_string());
+      }
+      _ => return Err("No opening choice is pending".to_string()),
+    };
+
+    self.opening.decisions.push(OpeningDecision {
+      ply: self.moves.len(),
+      by,
+      choice,
+    });
+    self.opening.phase = next_phase;
+    if let Some(clock) = self.clock.as_mut() {
+      clock.end_turn(by, false);
+    }
+    if swap {
+      self.swap_seats();
+    }
+    self.updated_at = now_ts();
+    Ok(())
+  }
+
+  // The proposer and responder trade colours: names, mode and the AI profiles follow.
+  fn swap_seats(&mut self) {
+    std::mem::swap(&mut self.players.black, &mut self.players.white);
+    match &mut self.mode {
+      GameMode::HumanVsAi { human_color } => *human_color = human_color.other(),
+      GameMode::AiVsAi { black_id, white_id } => std::mem::swap(black_id, white_id),
+      GameMode::HumanVsHuman => {}
+    }
+    if let Some(clock) = self.clock.as_mut() {
+      clock.swap_seats();
+    }
+    self.opening.swapped = !self.opening.swapped;
   }
 
   pub fn to_record(&self) -> GameRecord {
@@ -220,16 +607,27 @@ impl GameState {
       rule_set: self.rule_set,
       players: self.players.clone(),
       result: self.result,
+      termination: self.termination,
       moves: self.moves.clone(),
+      opening: if self.opening.rule == OpeningRule::Free {
+        None
+      } else {
+        Some(self.opening.clone())
+      },
+      clock: self.clock_state(),
+      seed: Some(self.seed),
       meta: Meta {
         created_at: self.created_at,
         updated_at: Some(self.updated_at),
         game_id: Some(self.game_id.clone()),
+        rated: if self.rated { None } else { Some(false) },
+        self_play: None,
       },
     }
   }
 
   pub fn from_record(record: GameRecord) -> Result<Self, String> {
+    check_board_size(record.board_size)?;
     let mut state = GameState::new(
       record.board_size,
       record.rule_set,
@@ -249,14 +647,41 @@ impl GameState {
       .game_id
       .clone()
       .unwrap_or_else(|| new_game_id(created_at));
+    if let Some(seed) = record.seed {
+      state.seed = seed;
+    }
 
-    for mv in record.moves.iter() {
+    let decisions = match record.opening {
+      Some(ref opening) => {
+        state.start_opening(opening.rule)?;
+        opening.decisions.clone()
+      }
+      None => Vec::new(),
+    };
+    let mut pending = decisions.iter().peekable();
+    for (ply, mv) in record.moves.iter().enumerate() {
+      while let Some(decision) = pending.next_if(|d| d.ply == ply) {
+        state.apply_opening_choice(decision.choice)?;
+      }
       state.apply_existing_move(mv.clone())?;
     }
+    for decision in pending {
+      if decision.ply != record.moves.len() {
+        return Err("Opening decision does not match the move list".to_string());
+      }
+      state.apply_opening_choice(decision.choice)?;
+    }
+    // Replaying the choices swaps seats; the record already names the final colours.
+    state.players = record.players.clone();
 
     if let Some(result) = record.result {
       state.result = Some(result);
     }
+    if record.termination.is_some() {
+      state.termination = record.termination;
+    }
+    state.clock = record.clock.map(GameClock::resume);
+    state.rated = record.meta.rated.unwrap_or(true);
     state.updated_at = updated_at;
     Ok(state)
   }
@@ -265,6 +690,9 @@ impl GameState {
     if self.result.is_some() {
       return Err("Game is already finished".to_string());
     }
+    if self.opening.awaiting_choice() {
+      return Err("An opening choice must be made first".to_string());
+    }
     if mv.player != self.to_move {
       return Err("Move order mismatch".to_string());
     }
@@ -276,15 +704,18 @@ impl GameState {
 
     self.board.set(mv.x, mv.y, mv.player);
     self.moves.push(mv.clone());
+    self.advance_opening();
 
     if let Some(result) = rules.check_win(&self.board, &mv) {
       self.result = Some(result);
+      self.termination = Some(Termination::Five);
       self.updated_at = now_ts();
       return Ok(());
     }
 
     if self.board.is_full() {
       self.result = Some(GameResult::Draw);
+      self.termination = Some(Termination::BoardFull);
       self.updated_at = now_ts();
       return Ok(());
     }
@@ -294,6 +725,14 @@ impl GameState {
     Ok(())
   }
 
+  fn advance_opening(&mut self) {
+    self.opening.phase = match (self.opening.phase, self.moves.len()) {
+      (OpeningPhase::ProposerPlace, 3) => OpeningPhase::ResponderChoice,
+      (OpeningPhase::ResponderPlace, 5) => OpeningPhase::ProposerChoice,
+      (phase, _) => phase,
+    };
+  }
+
   pub fn training_samples(&self) -> Vec<TrainingSample> {
     let mut board = Board::new(self.board.size());
     let mut samples = Vec::with_capacity(self.moves.len());
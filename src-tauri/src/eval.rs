@@ -0,0 +1,572 @@
+use std::fs;
+use std::ops::{Add, Deref, Sub};
+use std::path::Path;
+
+use crate::engine::Board;
+use crate::rules::RuleSet;
+use crate::types::{Coord, EvalWeights, Player};
+
+// Row, column, diagonal and anti-diagonal
+const DIRECTIONS: [(i32, i32); 4] = [(1, 0), (0, 1), (1, 1), (-1, 1)];
+// Diagonals shorter than this cannot hold a five and are not scored
+const MIN_LINE_LEN: usize = 5;
+// Limits on loaded weights, so static scores stay far below the search's win
+// scores (1,000,000) and cannot be mistaken for proven wins
+const MAX_FIVE_WEIGHT: i64 = 250_000;
+pub(crate) const MAX_THREAT_SCORE: i64 = 500_000;
+pub(crate) const MAX_DEFENSE: i32 = 300;
+// Largest defense weight a profile uses, in tenths
+pub(crate) const MAX_DEFENSE_WEIGHT: i64 = 15;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct ScoreBreakdown {
+  score: i32,
+  open_threes: i32,
+  open_fours: i32,
+}
+
+impl Add for ScoreBreakdown {
+  type Output = Self;
+
+  fn add(self, other: Self) -> Self {
+    Self {
+      score: self.score + other.score,
+      open_threes: self.open_threes + other.open_threes,
+      open_fours: self.open_fours + other.open_fours,
+    }
+  }
+}
+
+impl Sub for ScoreBreakdown {
+  type Output = Self;
+
+  fn sub(self, other: Self) -> Self {
+    Self {
+      score: self.score - other.score,
+      open_threes: self.open_threes - other.open_threes,
+      open_fours: self.open_fours - other.open_fours,
+    }
+  }
+}
+
+// One row, column or diagonal. The pattern code is a bitmask of each
+// player's stones along the line, bit i being the i-th cell from `start`.
+#[derive(Clone)]
+struct Line {
+  start: (usize, usize),
+  step: (i32, i32),
+  len: usize,
+  stones: [u32; 2],
+  scores: [ScoreBreakdown; 2],
+}
+
+impl Line {
+  fn cell(&self, pos: usize) -> (usize, usize) {
+    (
+      (self.start.0 as i32 + self.step.0 * pos as i32) as usize,
+      (self.start.1 as i32 + self.step.1 * pos as i32) as usize,
+    )
+  }
+}
+
+// How a stone on an empty point would change the pattern scores of the lines
+// through it: `mine` for the player placing it, `theirs` for the opponent.
+#[derive(Clone, Copy, Default)]
+struct MoveDelta {
+  mine: ScoreBreakdown,
+  theirs: ScoreBreakdown,
+}
+
+/// Board wrapper for the search that keeps the evaluation up to date as
+/// stones are placed and removed. Each line's pattern code and score is
+/// refreshed on `set`/`clear`, so evaluating costs only the lines through the
+/// changed point, and the effect of a move on every empty point is cached
+/// until a stone lands on one of its lines.
+#[derive(Clone)]
+pub struct SearchBoard {
+  board: Board,
+  weights: EvalWeights,
+  overline_wins: [bool; 2],
+  lines: Vec<Line>,
+  cell_lines: Vec<[Option<(usize, usize)>; 4]>, // (line, position in line) per direction
+  totals: [ScoreBreakdown; 2],                   // Sum of the line scores per player
+  center: [i32; 2],
+  stones: usize,
+  move_deltas: Vec<[Option<MoveDelta>; 2]>,
+}
+
+impl Deref for SearchBoard {
+  type Target = Board;
+
+  fn deref(&self) -> &Board {
+    &self.board
+  }
+}
+
+impl SearchBoard {
+  pub fn new(board: &Board, rules: &dyn RuleSet, weights: EvalWeights) -> Self {
+    let size = board.size();
+    let mut lines = Vec::new();
+    let mut cell_lines = vec![[None; 4]; size * size];
+    for (dir, &step) in DIRECTIONS.iter().enumerate() {
+      for start in line_starts(size, step) {
+        let mut line = Line {
+          start,
+          step,
+          len: 0,
+          stones: [0; 2],
+          scores: [ScoreBreakdown::default(); 2],
+        };
+        while line.len < size && board.in_bounds(line.cell(line.len).0, line.cell(line.len).1) {
+          line.len += 1;
+        }
+        // Rows and columns are always scored, whatever the board size
+        if dir >= 2 && line.len < MIN_LINE_LEN {
+          continue;
+        }
+        for pos in 0..line.len {
+          let (x, y) = line.cell(pos);
+          cell_lines[board.index(x, y)][dir] = Some((lines.len(), pos));
+        }
+        lines.push(line);
+      }
+    }
+
+    let mut search_board = Self {
+      board: Board::new(size),
+      weights,
+      overline_wins: [rules.overline_wins(Player::B), rules.overline_wins(Player::W)],
+      lines,
+      cell_lines,
+      totals: [ScoreBreakdown::default(); 2],
+      center: [0; 2],
+      stones: 0,
+      move_deltas: vec![[None; 2]; size * size],
+    };
+    for y in 0..size {
+      for x in 0..size {
+        if let Some(player) = board.get(x, y) {
+          search_board.set(x, y, player);
+        }
+      }
+    }
+    search_board
+  }
+
+  pub fn set(&mut self, x: usize, y: usize, player: Player) {
+    self.board.set(x, y, player);
+    self.center[side(player)] += self.center_value(x, y);
+    self.stones += 1;
+    self.refresh_lines(x, y);
+  }
+
+  pub fn clear(&mut self, x: usize, y: usize) {
+    let Some(player) = self.board.get(x, y) else {
+      return;
+    };
+    self.board.clear(x, y);
+    self.center[side(player)] -= self.center_value(x, y);
+    self.stones -= 1;
+    self.refresh_lines(x, y);
+  }
+
+  pub fn is_full(&self) -> bool {
+    self.stones == self.board.size() * self.board.size()
+  }
+
+  /// Static score of the position from `player`'s point of view. The
+  /// opponent's threats count `defense_weight` tenths as much as our own,
+  /// scaled again by the weights' `defense` percentage.
+  pub fn evaluate(&self, player: Player, defense_weight: i32) -> i32 {
+    let opponent = player.other();
+    let mine = self.side_score(self.totals[side(player)], self.center[side(player)]);
+    let theirs = self.side_score(self.totals[side(opponent)], self.center[side(opponent)]);
+    mine - self.defense(theirs, defense_weight)
+  }
+
+  /// What `evaluate(player, defense_weight)` would return after `player`
+  /// plays the empty point `coord`, without placing the stone.
+  pub fn move_value(&mut self, coord: Coord, player: Player, defense_weight: i32) -> i32 {
+    let cell = self.board.index(coord.x, coord.y);
+    let delta = match self.move_deltas[cell][side(player)] {
+      Some(delta) => delta,
+      None => {
+        let delta = self.move_delta(coord, player);
+        self.move_deltas[cell][side(player)] = Some(delta);
+        delta
+      }
+    };
+    let opponent = player.other();
+    let mine = self.side_score(
+      self.totals[side(player)] + delta.mine,
+      self.center[side(player)] + self.center_value(coord.x, coord.y),
+    );
+    let theirs = self.side_score(self.totals[side(opponent)] + delta.theirs, self.center[side(opponent)]);
+    mine - self.defense(theirs, defense_weight)
+  }
+
+  /// Empty points where `player` would complete a winning five.
+  pub fn five_points(&self, player: Player) -> Vec<Coord> {
+    self.threat_points(player, 4)
+  }
+
+  /// Empty points where `player` would make a four, i.e. threaten to complete
+  /// a five next move. Whether the rules allow the move is left to the caller.
+  pub fn four_points(&self, player: Player) -> Vec<Coord> {
+    self.threat_points(player, 3)
+  }
+
+  // Empty points of every five-cell window holding `stones` of `player`'s
+  // stones and none of the opponent's, where filling the window gives a
+  // winning five. Only lines with enough stones are looked at.
+  fn threat_points(&self, player: Player, stones: u32) -> Vec<Coord> {
+    let (me, them) = (side(player), side(player.other()));
+    let mut points = Vec::new();
+    for line in &self.lines {
+      let (mine, theirs) = (line.stones[me], line.stones[them]);
+      if line.len < 5 || mine.count_ones() < stones {
+        continue;
+      }
+      for start in 0..=line.len - 5 {
+        let window = 0b11111u32 << start;
+        if theirs & window != 0 || (mine & window).count_ones() != stones {
+          continue;
+        }
+        let run = run_length(mine | window, start, line.len);
+        if run != 5 && !(run > 5 && self.overline_wins[me]) {
+          continue;
+        }
+        for pos in start..start + 5 {
+          if mine & (1 << pos) == 0 {
+            let (x, y) = line.cell(pos);
+            let coord = Coord { x, y };
+            if !points.contains(&coord) {
+              points.push(coord);
+            }
+          }
+        }
+      }
+    }
+    points
+  }
+
+  fn move_delta(&self, coord: Coord, player: Player) -> MoveDelta {
+    let (me, them) = (side(player), side(player.other()));
+    let mut delta = MoveDelta::default();
+    for &(line_idx, pos) in self.cell_lines[self.board.index(coord.x, coord.y)].iter().flatten() {
+      let line = &self.lines[line_idx];
+      let mut stones = line.stones;
+      stones[me] |= 1 << pos;
+      let mine = score_line(&self.weights, stones[me], stones[them], line.len, self.overline_wins[me]);
+      let theirs = score_line(&self.weights, stones[them], stones[me], line.len, self.overline_wins[them]);
+      delta.mine = delta.mine + (mine - line.scores[me]);
+      delta.theirs = delta.theirs + (theirs - line.scores[them]);
+    }
+    delta
+  }
+
+  // Updates the pattern codes and scores of the lines through (x, y) and
+  // forgets the cached move deltas of every point on them.
+  fn refresh_lines(&mut self, x: usize, y: usize) {
+    let cell = self.board.index(x, y);
+    let stone = self.board.get(x, y);
+    for &(line_idx, pos) in self.cell_lines[cell].iter().flatten() {
+      let line = &mut self.lines[line_idx];
+      let bit = 1u32 << pos;
+      line.stones = [line.stones[0] & !bit, line.stones[1] & !bit];
+      if let Some(player) = stone {
+        line.stones[side(player)] |= bit;
+      }
+      for me in 0..2 {
+        let scores = score_line(&self.weights, line.stones[me], line.stones[1 - me], line.len, self.overline_wins[me]);
+        self.totals[me] = self.totals[me] - line.scores[me] + scores;
+        line.scores[me] = scores;
+      }
+      for pos in 0..line.len {
+        let (cx, cy) = line.cell(pos);
+        self.move_deltas[cy * self.board.size() + cx] = [None; 2];
+      }
+    }
+  }
+
+  fn center_value(&self, x: usize, y: usize) -> i32 {
+    let size = self.board.size() as i32;
+    let center = (size - 1) / 2;
+    let dist = (x as i32 - center).abs() + (y as i32 - center).abs();
+    self.weights.center * (size - dist) / 3
+  }
+
+  fn side_score(&self, total: ScoreBreakdown, center: i32) -> i32 {
+    let mut score = total.score + center;
+    if total.open_fours > 0 {
+      score += self.weights.open_four_bonus;
+    }
+    if total.open_threes >= 2 {
+      score += self.weights.double_three_bonus;
+    }
+    score
+  }
+
+  fn defense(&self, theirs: i32, defense_weight: i32) -> i32 {
+    (theirs * defense_weight) / 10 * self.weights.defense / 100
+  }
+}
+
+/// Reads evaluation weights from a JSON file, rejecting sets that `check_weights` refuses.
+pub fn load_weights(path: &Path) -> Result<EvalWeights, String> {
+  let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
+  let weights = serde_json::from_str(&data).map_err(|e| format!("Invalid weights file: {}", e))?;
+  check_weights(&weights).map_err(|e| format!("Invalid weights file: {}", e))?;
+  Ok(weights)
+}
+
+/// Checks that weights are non-negative and small enough that a busy
+/// position, two of every threat for the opponent weighed with the strongest
+/// defense, still scores well short of a win.
+pub fn check_weights(weights: &EvalWeights) -> Result<(), String> {
+  let threats = [
+    ("openFour", weights.open_four),
+    ("semiFour", weights.semi_four),
+    ("brokenFour", weights.broken_four),
+    ("openThree", weights.open_three),
+    ("brokenThree", weights.broken_three),
+    ("semiThree", weights.semi_three),
+    ("openTwo", weights.open_two),
+    ("semiTwo", weights.semi_two),
+    ("openOne", weights.open_one),
+    ("openFourBonus", weights.open_four_bonus),
+    ("doubleThreeBonus", weights.double_three_bonus),
+  ];
+  let others = [("five", weights.five), ("center", weights.center), ("defense", weights.defense)];
+  if let Some((name, _)) = threats.iter().chain(others.iter()).find(|(_, value)| *value < 0) {
+    return Err(format!("{} cannot be negative", name));
+  }
+  if weights.five as i64 > MAX_FIVE_WEIGHT {
+    return Err(format!("five cannot exceed {}", MAX_FIVE_WEIGHT));
+  }
+  if weights.defense > MAX_DEFENSE {
+    return Err(format!("defense must be between 0 and {}", MAX_DEFENSE));
+  }
+  let bonuses = weights.open_four_bonus as i64 + weights.double_three_bonus as i64;
+  let patterns: i64 = threats.iter().map(|(_, value)| *value as i64).sum::<i64>() - bonuses;
+  let busiest = (2 * patterns + bonuses) * MAX_DEFENSE_WEIGHT / 10 * weights.defense.max(100) as i64 / 100;
+  if busiest > MAX_THREAT_SCORE {
+    return Err(format!(
+      "threat weights are too large: a busy position would score {}, the limit is {}",
+      busiest, MAX_THREAT_SCORE
+    ));
+  }
+  Ok(())
+}
+
+// First cell of every line running in direction `step`
+fn line_starts(size: usize, step: (i32, i32)) -> Vec<(usize, usize)> {
+  match step {
+    (1, 0) => (0..size).map(|y| (0, y)).collect(),
+    (0, 1) => (0..size).map(|x| (x, 0)).collect(),
+    (1, 1) => (0..size).map(|x| (x, 0)).chain((1..size).map(|y| (0, y))).collect(),
+    _ => (0..size).map(|x| (x, 0)).chain((1..size).map(|y| (size - 1, y))).collect(),
+  }
+}
+
+fn side(player: Player) -> usize {
+  match player {
+    Player::B => 0,
+    Player::W => 1,
+  }
+}
+
+/// 直接在行的棋型编码上评估一条线，避免分配Vec
+fn score_line(weights: &EvalWeights, mine: u32, theirs: u32, len: usize, overline_wins: bool) -> ScoreBreakdown {
+  let mut out = ScoreBreakdown::default();
+
+  // 使用固定大小数组作为滑动窗口 (最大支持6格窗口用于模式匹配)
+  let mut window: [i8; 6] = [0; 6];
+  let mut window_pos = 0usize;
+
+  // 连续棋子序列追踪
+  let mut run_start_idx: Option<usize> = None;
+  let mut prev_val: i8 = -1; // 用于追踪左侧是否开放
+
+  for i in 0..len {
+    let val = cell_value(mine, theirs, i);
+
+    // 处理连续序列
+    if val == 1 {
+      if run_start_idx.is_none() {
+        run_start_idx = Some(i);
+      }
+    } else if let Some(start) = run_start_idx {
+      // 连续序列结束
+      let run_len = (i - start) as i32;
+      let left_open = start > 0 && prev_val == 0;
+      let right_open = val == 0;
+      let open_ends = left_open as i32 + right_open as i32;
+
+      out.score += run_score(weights, run_len, open_ends, overline_wins);
+      if run_len == 4 && open_ends == 2 {
+        out.open_fours += 1;
+      }
+      if run_len == 3 && open_ends == 2 {
+        out.open_threes += 1;
+      }
+      run_start_idx = None;
+    }
+
+    // 更新前一个值 (用于下一个序列的左侧开放判断)
+    if val != 1 {
+      prev_val = val;
+    }
+
+    // 滑动窗口模式匹配
+    window[window_pos % 6] = val;
+    window_pos += 1;
+
+    if window_pos >= 6 {
+      // 重构窗口为正确顺序
+      let w = [
+        window[window_pos % 6],
+        window[(window_pos + 1) % 6],
+        window[(window_pos + 2) % 6],
+        window[(window_pos + 3) % 6],
+        window[(window_pos + 4) % 6],
+        window[(window_pos + 5) % 6],
+      ];
+      // 6格模式
+      if w == [0, 1, 1, 0, 1, 0] || w == [0, 1, 0, 1, 1, 0] {
+        out.score += weights.broken_three;
+        out.open_threes += 1;
+      }
+      if w == [0, 1, 1, 1, 0, 1] || w == [1, 0, 1, 1, 1, 0] {
+        out.score += weights.broken_four;
+      }
+    }
+
+    if window_pos >= 5 {
+      // 5格模式
+      let w5 = [
+        window[(window_pos + 1) % 6],
+        window[(window_pos + 2) % 6],
+        window[(window_pos + 3) % 6],
+        window[(window_pos + 4) % 6],
+        window[(window_pos + 5) % 6],
+      ];
+      if w5 == [0, 1, 0, 1, 0] {
+        out.score += weights.open_two;
+      }
+      if w5 == [1, 1, 1, 0, 1] || w5 == [1, 0, 1, 1, 1] {
+        out.score += weights.broken_four;
+      }
+    }
+  }
+
+  // 处理末尾的连续序列
+  if let Some(start) = run_start_idx {
+    let run_len = (len - start) as i32;
+    let left_open = start > 0 && prev_val == 0;
+    let open_ends = left_open as i32; // 右侧到达边界
+
+    out.score += run_score(weights, run_len, open_ends, overline_wins);
+    if run_len == 4 && open_ends == 2 {
+      out.open_fours += 1;
+    }
+    if run_len == 3 && open_ends == 2 {
+      out.open_threes += 1;
+    }
+  }
+
+  out
+}
+
+// Length of the run of set bits covering the five cells from `start`
+fn run_length(bits: u32, start: usize, len: usize) -> usize {
+  let mut first = start;
+  while first > 0 && bits & (1 << (first - 1)) != 0 {
+    first -= 1;
+  }
+  let mut end = start + 5;
+  while end < len && bits & (1 << end) != 0 {
+    end += 1;
+  }
+  end - first
+}
+
+fn cell_value(mine: u32, theirs: u32, pos: usize) -> i8 {
+  if mine & (1 << pos) != 0 {
+    1
+  } else if theirs & (1 << pos) != 0 {
+    2
+  } else {
+    0
+  }
+}
+
+fn run_score(weights: &EvalWeights, len: i32, open_ends: i32, overline_wins: bool) -> i32 {
+  match (len, open_ends) {
+    (5, _) => weights.five,
+    // An overline is worthless when the rules only count an exact five
+    (6..=i32::MAX, _) if !overline_wins => 0,
+    (6..=i32::MAX, _) => weights.five,
+    (4, 2) => weights.open_four,
+    (4, 1) => weights.semi_four,
+    (3, 2) => weights.open_three,
+    (3, 1) => weights.semi_three,
+    (2, 2) => weights.open_two,
+    (2, 1) => weights.semi_two,
+    (1, 2) => weights.open_one,
+    _ => 0,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use rand::rngs::StdRng;
+  use rand::{Rng, SeedableRng};
+
+  use super::*;
+  use crate::rules::rules_for;
+  use crate::types::RuleSetKind;
+
+  #[test]
+  fn incremental_evaluation_matches_a_fresh_scan() {
+    let rule_sets = [
+      RuleSetKind::Standard,
+      RuleSetKind::Renju,
+      RuleSetKind::ExactFive,
+      RuleSetKind::ExactFiveBlack,
+    ];
+    for size in [9, 15, 19] {
+      for rule_set in rule_sets {
+        let rules = rules_for(rule_set);
+        let mut rng = StdRng::seed_from_u64(size as u64);
+        let mut board = Board::new(size);
+        let mut search_board = SearchBoard::new(&board, rules.as_ref(), EvalWeights::default());
+        for step in 0..400 {
+          let (x, y) = (rng.gen_range(0..size), rng.gen_range(0..size));
+          // Clearing a third of the time keeps the board from filling up
+          if board.get(x, y).is_some() && rng.gen_range(0..3) == 0 {
+            board.clear(x, y);
+            search_board.clear(x, y);
+          } else if board.is_empty(x, y) {
+            let player = if rng.gen() { Player::B } else { Player::W };
+            board.set(x, y, player);
+            search_board.set(x, y, player);
+          } else {
+            continue;
+          }
+
+          let fresh = SearchBoard::new(&board, rules.as_ref(), EvalWeights::default());
+          for player in [Player::B, Player::W] {
+            for defense in [0, 10, 15] {
+              assert_eq!(
+                search_board.evaluate(player, defense),
+                fresh.evaluate(player, defense),
+                "{size}x{size} {rule_set:?}, step {step}: ({x}, {y})"
+              );
+            }
+          }
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,459 @@
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::ai;
+use crate::engine::{check_board_size, Board, DEFAULT_BOARD_SIZE};
+use crate::tt::{TranspositionTable, MAX_HASH_MB, MIN_HASH_MB};
+use crate::types::{AiConfig, Coord, EngineConfig, EvalWeights, Move, Player, RuleSetKind};
+
+// INFO `rule` bits
+const RULE_EXACT_FIVE: u32 = 1;
+const RULE_RENJU: u32 = 4;
+const RULE_CARO: u32 = 8;
+
+// Time per move until the manager sends INFO timeout_turn
+const DEFAULT_TURN_MS: u64 = 5_000;
+// Share of the remaining match time one move may use
+const MATCH_TIME_DIVISOR: u64 = 25;
+// Never think for less than this, however little time is left
+const MIN_MOVE_MS: u64 = 20;
+// Table size when the manager sets no memory limit
+const DEFAULT_HASH_MB: usize = 64;
+// The clock, not the depth, ends the search
+const ENGINE_DEPTH: u8 = 32;
+
+// How long an external engine may take to start and answer START
+const ENGINE_START_MS: u64 = 15_000;
+// Allowed on top of the turn limit before an external engine counts as timed out
+const ENGINE_GRACE_MS: u64 = 1_000;
+// How long an external engine gets to quit after END before it is killed
+const ENGINE_EXIT_MS: u64 = 500;
+
+/// Rule set for an INFO `rule` value: 1 plays exactly five, 4 renju, and
+/// anything else freestyle (continuous games, bit 2, play like single ones).
+/// Caro (8) is not supported.
+pub fn rule_set_for(rule: u32) -> Option<RuleSetKind> {
+  if rule & RULE_CARO != 0 {
+    None
+  } else if rule & RULE_RENJU != 0 {
+    Some(RuleSetKind::Renju)
+  } else if rule & RULE_EXACT_FIVE != 0 {
+    Some(RuleSetKind::ExactFive)
+  } else {
+    Some(RuleSetKind::Standard)
+  }
+}
+
+/// The INFO `rule` value for a rule set. Exact five for Black only has no
+/// protocol equivalent and is sent as plain exact five.
+pub fn rule_value(rule_set: RuleSetKind) -> u32 {
+  match rule_set {
+    RuleSetKind::Standard => 0,
+    RuleSetKind::ExactFive | RuleSetKind::ExactFiveBlack => RULE_EXACT_FIVE,
+    RuleSetKind::Renju => RULE_RENJU,
+  }
+}
+
+/// Engine side of the Gomocup protocol: keeps the position the manager
+/// describes and answers with moves from `ai::choose_move`.
+pub struct Brain {
+  size: usize,
+  rule_set: RuleSetKind,
+  // Stones in the order played, true for our own
+  stones: Vec<(Coord, bool)>,
+  timeout_turn: u64,
+  timeout_match: u64, // 0 means no match limit
+  time_left: u64,
+  hash_mb: usize,
+  // Kept for the whole game; dropped on a new game or memory limit
+  tt: Option<TranspositionTable>,
+}
+
+impl Default for Brain {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Brain {
+  pub fn new() -> Self {
+    Self {
+      size: DEFAULT_BOARD_SIZE,
+      rule_set: RuleSetKind::Standard,
+      stones: Vec::new(),
+      timeout_turn: DEFAULT_TURN_MS,
+      timeout_match: 0,
+      time_left: 0,
+      hash_mb: DEFAULT_HASH_MB,
+      tt: None,
+    }
+  }
+
+  /// Handles one command, reading the position lines that follow BOARD from
+  /// `lines`, and returns the reply, if the command has one.
+  pub fn command(
+    &mut self,
+    line: &str,
+    lines: &mut impl Iterator<Item = io::Result<String>>,
+  ) -> io::Result<Option<String>> {
+    let (name, args) = match line.split_once(char::is_whitespace) {
+      Some((name, args)) => (name, args.trim()),
+      None => (line, ""),
+    };
+    let reply = match name.to_ascii_uppercase().as_str() {
+      "START" => Some(self.start(args)),
+      "RESTART" => {
+        self.new_game();
+        Some("OK".to_string())
+      }
+      "INFO" => self.info(args),
+      "BEGIN" => {
+        self.stones.clear();
+        Some(self.think())
+      }
+      "TURN" => Some(match self.place(args, false) {
+        Ok(()) => self.think(),
+        Err(err) => format!("ERROR {}", err),
+      }),
+      "BOARD" => Some(self.board(lines)?),
+      "TAKEBACK" => Some(self.take_back(args)),
+      "ABOUT" => Some(format!(
+        "name=\"{}\", version=\"{}\"",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+      )),
+      "" => None,
+      _ => Some(format!("UNKNOWN {}", name)),
+    };
+    Ok(reply)
+  }
+
+  fn start(&mut self, args: &str) -> String {
+    let size = match args.parse::<usize>() {
+      Ok(size) => size,
+      Err(_) => return format!("ERROR invalid board size {}", args),
+    };
+    if let Err(err) = check_board_size(size) {
+      return format!("ERROR {}", err);
+    }
+    self.size = size;
+    self.new_game();
+    "OK".to_string()
+  }
+
+  fn new_game(&mut self) {
+    self.stones.clear();
+    self.tt = None;
+  }
+
+  fn info(&mut self, args: &str) -> Option<String> {
+    let (key, value) = args.split_once(char::is_whitespace)?;
+    let value = value.trim();
+    // Keys this engine has no use for (folder, game_type, ...) are ignored
+    let number = value.parse::<i64>().ok();
+    match (key.to_ascii_lowercase().as_str(), number) {
+      ("timeout_turn", Some(ms)) => self.timeout_turn = ms.max(0) as u64,
+      ("timeout_match", Some(ms)) => {
+        // Until the first time_left arrives
+        self.timeout_match = ms.max(0) as u64;
+        self.time_left = self.timeout_match;
+      }
+      ("time_left", Some(ms)) => self.time_left = ms.max(0) as u64,
+      ("max_memory", Some(bytes)) => {
+        let hash_mb = if bytes > 0 {
+          // Leave half the limit for everything besides the table
+          (bytes as usize / (1024 * 1024) / 2).clamp(MIN_HASH_MB, MAX_HASH_MB)
+        } else {
+          DEFAULT_HASH_MB
+        };
+        if hash_mb != self.hash_mb {
+          self.hash_mb = hash_mb;
+          self.tt = None;
+        }
+      }
+      ("rule", Some(rule)) => match rule_set_for(rule.max(0) as u32) {
+        Some(rule_set) => self.rule_set = rule_set,
+        None => return Some(format!("ERROR unsupported rule {}", rule)),
+      },
+      _ => {}
+    }
+    None
+  }
+
+  // Reads "x,y,field" lines up to DONE (field 1 is our stone, 2 the
+  // opponent's) as the whole position, then moves
+  fn board(&mut self, lines: &mut impl Iterator<Item = io::Result<String>>) -> io::Result<String> {
+    self.stones.clear();
+    let mut error = None;
+    for line in lines.by_ref() {
+      let line = line?;
+      let line = line.trim();
+      if line.eq_ignore_ascii_case("DONE") {
+        break;
+      }
+      if error.is_some() {
+        continue;
+      }
+      let (coord, field) = match line.rsplit_once(',') {
+        Some((coord, field)) => (coord, field.trim()),
+        None => (line, ""),
+      };
+      let result = match field {
+        "1" => self.place(coord, true),
+        "2" => self.place(coord, false),
+        _ => Err(format!("invalid board line {}", line)),
+      };
+      error = result.err();
+    }
+    Ok(match error {
+      Some(err) => format!("ERROR {}", err),
+      None => self.think(),
+    })
+  }
+
+  fn take_back(&mut self, args: &str) -> String {
+    let coord = match self.parse_coord(args) {
+      Ok(coord) => coord,
+      Err(err) => return format!("ERROR {}", err),
+    };
+    match self.stones.iter().rposition(|(stone, _)| *stone == coord) {
+      Some(index) => {
+        self.stones.remove(index);
+        "OK".to_string()
+      }
+      None => format!("ERROR no stone at {},{}", coord.x, coord.y),
+    }
+  }
+
+  fn place(&mut self, args: &str, own: bool) -> Result<(), String> {
+    let coord = self.parse_coord(args)?;
+    if self.stones.iter().any(|(stone, _)| *stone == coord) {
+      return Err(format!("{},{} is already taken", coord.x, coord.y));
+    }
+    self.stones.push((coord, own));
+    Ok(())
+  }
+
+  fn parse_coord(&self, args: &str) -> Result<Coord, String> {
+    let parsed = args
+      .split_once(',')
+      .and_then(|(x, y)| Some((x.trim().parse::<usize>().ok()?, y.trim().parse::<usize>().ok()?)));
+    match parsed {
+      Some((x, y)) if x < self.size && y < self.size => Ok(Coord { x, y }),
+      _ => Err(format!("invalid coordinates {}", args)),
+    }
+  }
+
+  // Chooses, records and announces our move
+  fn think(&mut self) -> String {
+    // Black moves whenever both sides have as many stones
+    let own_count = self.stones.iter().filter(|(_, own)| *own).count();
+    let me = if own_count * 2 == self.stones.len() { Player::B } else { Player::W };
+    let mut board = Board::new(self.size);
+    for (coord, own) in &self.stones {
+      board.set(coord.x, coord.y, if *own { me } else { me.other() });
+    }
+
+    let config = engine_config(self.move_time_ms(), self.hash_mb);
+    let tt = self.tt.get_or_insert_with(|| TranspositionTable::new(config.hash_mb));
+    match ai::choose_move(&board, self.rule_set, me, config, tt) {
+      Some(coord) => {
+        self.stones.push((coord, true));
+        format!("{},{}", coord.x, coord.y)
+      }
+      None => "ERROR no legal move".to_string(),
+    }
+  }
+
+  // The turn limit, cut to a share of what is left of the match clock, less
+  // a margin for reading and answering
+  fn move_time_ms(&self) -> u64 {
+    let mut ms = self.timeout_turn;
+    if self.timeout_match > 0 {
+      ms = ms.min(self.time_left / MATCH_TIME_DIVISOR);
+    }
+    ms.saturating_sub(ms / 10 + 30).max(MIN_MOVE_MS)
+  }
+}
+
+// As strong as the top ladder level, but bounded by the clock alone
+fn engine_config(move_time_ms: u64, hash_mb: usize) -> AiConfig {
+  AiConfig {
+    depth: ENGINE_DEPTH,
+    max_candidates: 20,
+    randomness: 0,
+    max_nodes: u32::MAX,
+    defense_weight: 14,
+    threat_nodes: 20_000,
+    quiescence_nodes: 1024,
+    move_time_ms,
+    hash_mb,
+    threads: 1,
+    weights: EvalWeights::default(),
+    use_book: false,
+    book_variety: 0,
+    seed: None,
+  }
+}
+
+/// Runs the engine over a manager's commands until END or end of input.
+pub fn run_brain(input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+  let mut brain = Brain::new();
+  let mut lines = input.lines();
+  while let Some(line) = lines.next() {
+    let line = line?;
+    let line = line.trim();
+    if line.eq_ignore_ascii_case("END") {
+      break;
+    }
+    if let Some(reply) = brain.command(line, &mut lines)? {
+      writeln!(output, "{}", reply)?;
+      output.flush()?;
+    }
+  }
+  Ok(())
+}
+
+/// An external engine running as a subprocess and driven over the Gomocup
+/// protocol. Every move request sends the whole game with BOARD, so undos and
+/// openings need no special handling. After an error the engine may be in any
+/// state and should be dropped, which stops the process.
+pub struct EngineProcess {
+  child: Child,
+  stdin: ChildStdin,
+  // Lines the engine prints, read on a separate thread so waits can time out
+  replies: Receiver<String>,
+  timeout_turn_ms: u64,
+}
+
+impl EngineProcess {
+  /// Starts the engine and sets up a game of the given size and rules.
+  pub fn start(config: &EngineConfig, board_size: usize, rule_set: RuleSetKind) -> Result<Self, String> {
+    let path = Path::new(&config.path);
+    let mut command = Command::new(path);
+    command
+      .args(&config.args)
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .stderr(Stdio::null());
+    // Engines tend to load their data files from the working directory
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+      command.current_dir(dir);
+    }
+    #[cfg(windows)]
+    {
+      use std::os::windows::process::CommandExt;
+      const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+      command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let mut child = command
+      .spawn()
+      .map_err(|e| format!("Cannot start engine {}: {}", config.path, e))?;
+    let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+      let _ = child.kill();
+      return Err("Engine pipes unavailable".to_string());
+    };
+
+    let (sender, replies) = mpsc::channel();
+    thread::spawn(move || {
+      for line in BufReader::new(stdout).lines() {
+        let Ok(line) = line else {
+          break;
+        };
+        if sender.send(line).is_err() {
+          break;
+        }
+      }
+    });
+
+    let mut engine = Self {
+      child,
+      stdin,
+      replies,
+      timeout_turn_ms: config.timeout_turn_ms,
+    };
+    engine.send(&format!("START {}", board_size))?;
+    let reply = engine.reply(Duration::from_millis(ENGINE_START_MS))?;
+    if !reply.eq_ignore_ascii_case("OK") {
+      return Err(format!("Engine did not accept START: {}", reply));
+    }
+    engine.send(&format!("INFO timeout_turn {}", config.timeout_turn_ms))?;
+    engine.send("INFO timeout_match 0")?;
+    engine.send(&format!("INFO max_memory {}", config.max_memory_mb * 1024 * 1024))?;
+    engine.send(&format!("INFO rule {}", rule_value(rule_set)))?;
+    Ok(engine)
+  }
+
+  /// Asks for `to_move`'s reply to the game so far, with `timeout_turn_ms` to
+  /// think. An engine that has not answered shortly after that has timed out.
+  pub fn choose_move(&mut self, moves: &[Move], to_move: Player, timeout_turn_ms: u64) -> Result<Coord, String> {
+    if timeout_turn_ms != self.timeout_turn_ms {
+      self.send(&format!("INFO timeout_turn {}", timeout_turn_ms))?;
+      self.timeout_turn_ms = timeout_turn_ms;
+    }
+    let mut board = String::from("BOARD\n");
+    for mv in moves {
+      let field = if mv.player == to_move { 1 } else { 2 };
+      let _ = writeln!(board, "{},{},{}", mv.x, mv.y, field);
+    }
+    board.push_str("DONE");
+    self.send(&board)?;
+
+    let reply = self.reply(Duration::from_millis(timeout_turn_ms + ENGINE_GRACE_MS))?;
+    reply
+      .split_once(',')
+      .and_then(|(x, y)| {
+        Some(Coord {
+          x: x.trim().parse().ok()?,
+          y: y.trim().parse().ok()?,
+        })
+      })
+      .ok_or_else(|| format!("Engine answered {}", reply))
+  }
+
+  fn send(&mut self, text: &str) -> Result<(), String> {
+    writeln!(self.stdin, "{}", text)
+      .and_then(|_| self.stdin.flush())
+      .map_err(|_| "Engine has exited".to_string())
+  }
+
+  // The next answer, skipping the MESSAGE and DEBUG lines engines print
+  // while thinking
+  fn reply(&self, timeout: Duration) -> Result<String, String> {
+    let deadline = Instant::now() + timeout;
+    loop {
+      let line = match self.replies.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+        Ok(line) => line,
+        Err(RecvTimeoutError::Timeout) => return Err("Engine timed out".to_string()),
+        Err(RecvTimeoutError::Disconnected) => return Err("Engine has exited".to_string()),
+      };
+      let line = line.trim();
+      let word = line.split_whitespace().next().unwrap_or("").to_ascii_uppercase();
+      match word.as_str() {
+        "" | "MESSAGE" | "DEBUG" | "SUGGEST" => continue,
+        "ERROR" | "UNKNOWN" => return Err(format!("Engine error: {}", line)),
+        _ => return Ok(line.to_string()),
+      }
+    }
+  }
+}
+
+impl Drop for EngineProcess {
+  fn drop(&mut self) {
+    let _ = self.send("END");
+    let deadline = Instant::now() + Duration::from_millis(ENGINE_EXIT_MS);
+    while Instant::now() < deadline {
+      if !matches!(self.child.try_wait(), Ok(None)) {
+        return;
+      }
+      thread::sleep(Duration::from_millis(10));
+    }
+    let _ = self.child.kill();
+    let _ = self.child.wait();
+  }
+}
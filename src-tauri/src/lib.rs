@@ -0,0 +1,18 @@
+pub mod ai;
+pub mod archive;
+pub mod book;
+pub mod engine;
+pub mod eval;
+pub mod gomocup;
+pub mod llm;
+pub mod proof;
+pub mod rating;
+pub mod review;
+pub mod rules;
+pub mod sprt;
+pub mod threat;
+pub mod tournament;
+pub mod tt;
+pub mod tune;
+pub mod types;
+pub mod users;
@@ -7,9 +7,10 @@ use tokio::time::timeout;
 
 use crate::ai;
 use crate::engine::Board;
-use crate::types::{Coord, LlmConfig, Move, Player};
+use crate::types::{Coord, LlmConfig, Move, Player, RuleSetKind};
 
-const COLS: &str = "ABCDEFGHIJKLMNO";
+// Column labels for the largest supported board (20x20)
+const COLS: &str = "ABCDEFGHIJKLMNOPQRST";
 const MAX_RETRIES: u32 = 3;
 const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1/chat/completions";
 
@@ -62,6 +63,7 @@ lazy_static::lazy_static! {
 
 pub fn choose_move(
   board: &Board,
+  rule_set: RuleSetKind,
   player: Player,
   config: &LlmConfig,
   api_key: &str,
@@ -73,11 +75,12 @@ pub fn choose_move(
     .build()
     .map_err(|e| format!("Failed to create async runtime: {e}"))?;
 
-  rt.block_on(choose_move_async(board, player, config, api_key, moves))
+  rt.block_on(choose_move_async(board, rule_set, player, config, api_key, moves))
 }
 
 pub async fn choose_move_async(
   board: &Board,
+  rule_set: RuleSetKind,
   player: Player,
   config: &LlmConfig,
   api_key: &str,
@@ -87,7 +90,7 @@ pub async fn choose_move_async(
     return Err("Missing API key for LLM profile".to_string());
   }
 
-  let candidates = ai::candidate_moves_for_llm(board, player, config.candidate_limit);
+  let candidates = ai::candidate_moves_for_llm(board, rule_set, player, config.candidate_limit);
   if candidates.is_empty() {
     return Err("No valid moves".to_string());
   }
@@ -99,7 +102,7 @@ pub async fn choose_move_async(
   let mut last_error = String::new();
 
   for attempt in 1..=MAX_RETRIES {
-    match try_llm_call_async(config, api_key, &system, &user, &candidate_set).await {
+    match try_llm_call_async(config, api_key, &system, &user, &candidate_set, board.size()).await {
       Ok(coord) => return Ok(coord),
       Err(e) => {
         last_error = e;
@@ -119,9 +122,10 @@ async fn try_llm_call_async(
   system: &str,
   user: &str,
   candidate_set: &HashSet<String>,
+  board_size: usize,
 ) -> Result<Coord, String> {
   let response = call_llm_api(config, api_key, system, user).await?;
-  let coord = parse_response(&response)?;
+  let coord = parse_response(&response, board_size)?;
   let coord_label = coord_to_label(coord);
   if !candidate_set.contains(&coord_label) {
     return Err(format!("LLM returned move {} outside candidate list", coord_label));
@@ -213,13 +217,16 @@ fn build_prompt(
   moves: &[Move],
   candidates: &[String],
 ) -> (String, String) {
-  let system = "You are a Gomoku player. Board size 15x15.\n\
-Use coordinates A–O (columns) and 1–15 (rows).\n\
+  let size = board.size();
+  let last_col = COLS.chars().nth(size - 1).unwrap_or('O');
+  let system = format!(
+    "You are a Gomoku player. Board size {size}x{size}.\n\
+Use coordinates A–{last_col} (columns) and 1–{size} (rows).\n\
 You must choose a move from the provided candidates list.\n\
 Priority: (1) if you can win immediately, choose that move; (2) if the opponent can win immediately, block it; (3) otherwise choose the strongest candidate.\n\
-Respond only with JSON: {\"move\":\"H8\"} where move is in candidates.\n\
-If no move possible, respond {\"move\":\"pass\"}."
-    .to_string();
+Respond only with JSON: {{\"move\":\"H8\"}} where move is in candidates.\n\
+If no move possible, respond {{\"move\":\"pass\"}}."
+  );
 
   let to_move = match player {
     Player::B => "Black",
@@ -229,7 +236,7 @@ If no move possible, respond {\"move\":\"pass\"}."
   let history = format_move_history(moves);
   let board_str = render_board(board);
   let user = format!(
-    "To move: {to_move}\nBlack stones: {black_stones}\nWhite stones: {white_stones}\nMove history: {history}\nCandidates: {candidates}\nBoard (row 15 at top):\n{board_str}",
+    "To move: {to_move}\nBlack stones: {black_stones}\nWhite stones: {white_stones}\nMove history: {history}\nCandidates: {candidates}\nBoard (row {size} at top):\n{board_str}",
     candidates = candidates.join(", ")
   );
   (system, user)
@@ -329,7 +336,7 @@ fn parse_label(label: &str) -> Option<Coord> {
   })
 }
 
-fn parse_response(raw: &str) -> Result<Coord, String> {
+fn parse_response(raw: &str, board_size: usize) -> Result<Coord, String> {
   // Try multiple parsing strategies
   if let Some(coord) = try_parse_json(raw) {
     return Ok(coord);
@@ -337,7 +344,7 @@ fn parse_response(raw: &str) -> Result<Coord, String> {
   if let Some(coord) = try_extract_json_from_text(raw) {
     return Ok(coord);
   }
-  if let Some(coord) = try_extract_move_directly(raw) {
+  if let Some(coord) = try_extract_move_directly(raw, board_size) {
     return Ok(coord);
   }
   Err(format!("Failed to parse LLM response: {}", truncate_for_error(raw)))
@@ -377,18 +384,19 @@ fn try_extract_json_from_text(raw: &str) -> Option<Coord> {
   None
 }
 
-fn try_extract_move_directly(raw: &str) -> Option<Coord> {
+fn try_extract_move_directly(raw: &str, board_size: usize) -> Option<Coord> {
   // Try to find a coordinate pattern directly (e.g., "H8", "A15")
-  // Pattern: letter A-O followed by 1-15
+  // Pattern: a column letter on the board followed by a row number on the board
+  let cols = &COLS[..board_size.min(COLS.len())];
   let raw_upper = raw.to_uppercase();
   for word in raw_upper.split(|c: char| !c.is_alphanumeric()) {
     let word = word.trim();
     if word.len() >= 2 && word.len() <= 3 {
       if let Some(first_char) = word.chars().next() {
-        if ('A'..='O').contains(&first_char) {
+        if cols.contains(first_char) {
           let rest: String = word.chars().skip(1).collect();
           if let Ok(num) = rest.parse::<usize>() {
-            if (1..=15).contains(&num) {
+            if (1..=board_size).contains(&num) {
               return parse_label(word);
             }
           }
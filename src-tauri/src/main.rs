@@ -1,27 +1,43 @@
 use std::fs;
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use tauri::{State, Window};
 use serde::Serialize;
 
-mod ai;
-mod engine;
-mod llm;
-mod rating;
-mod rules;
-mod types;
-mod users;
-
-use engine::GameState;
-use rating::{ratings_base_path, run_self_play, run_self_play_mixed, ProfileRating, RatingStore, RatingsSnapshot, SelfPlayReport};
-use types::{GameMode, GameRecord, GameSnapshot, LlmConfig, Player, ProfileKind, RuleSetKind};
-use users::{
-  ensure_data_dirs, ensure_user_dir, llm_keys_path, new_user_id, now_timestamp, ratings_user_path,
+use gomoku::{ai, llm, proof, review, threat, types};
+use gomoku::archive::{ArchiveFormat, GameArchive};
+use gomoku::book::{BookSummary, OpeningBook, DEFAULT_BOOK_PLIES};
+use gomoku::eval::load_weights;
+use gomoku::gomocup::EngineProcess;
+use gomoku::engine::{check_board_size, GameState, DEFAULT_BOARD_SIZE};
+use gomoku::sprt::{run_sprt, SprtSettings};
+use gomoku::tournament::{resolve_players, TournamentPlayer};
+use gomoku::tt::{TranspositionTable, MAX_HASH_MB, MIN_HASH_MB};
+use gomoku::tune::{run_spsa, save_results, TuneSettings};
+use gomoku::rating::{
+  ratings_base_path, run_self_play, run_self_play_mixed, MixedProfiles, ProfileRating, RatingStore, RatingsSnapshot,
+  SelfPlayReport, SelfPlaySettings,
+};
+use gomoku::types::{
+  Coord, EngineConfig, GameMode, GameRecord, GameSnapshot, LlmConfig, MoveAnalysis, OpeningChoice, OpeningRule,
+  Player, PositionAnalysis, ProfileKind, RuleSetKind, TimeControl,
+};
+use gomoku::users::{
+  ensure_data_dirs, ensure_user_dir, llm_keys_path, new_user_id, now_timestamp, opening_book_path,
+  ratings_user_path,
   snapshot_from_store, user_dir, user_settings_path, users_path, LlmKeyStore, UserProfile,
   UserSettings, UserStore, UsersSnapshot,
 };
 
+// Profile whose search settings drive hints and analysis by default
+const ANALYSIS_PROFILE: &str = "l12";
+const DEFAULT_ANALYSIS_LINES: usize = 3;
+const DEFAULT_SOLVER_NODES: u32 = 50_000;
+const DEFAULT_PROOF_NODES: u32 = 500_000;
+
 struct AppState {
   game: Mutex<GameState>,
   rating_base: Arc<Mutex<RatingStore>>,
@@ -34,6 +50,19 @@ struct AppState {
   rating_applied: Mutex<bool>,
   self_play_running: Arc<Mutex<bool>>,
   self_play_stop: Arc<AtomicBool>,
+  analysis_running: Arc<Mutex<bool>>,
+  analysis_stop: Arc<AtomicBool>,
+  review_running: Arc<Mutex<bool>>,
+  tuning_running: Arc<Mutex<bool>>,
+  tuning_stop: Arc<AtomicBool>,
+  sprt_running: Arc<Mutex<bool>>,
+  sprt_stop: Arc<AtomicBool>,
+  // Transposition tables of the AI seats (Black, White), kept until the next game
+  search_tables: Mutex<[Option<Arc<TranspositionTable>>; 2]>,
+  // Opening book used by profiles with `use_book`, if one has been built or loaded
+  book: Mutex<Option<Arc<OpeningBook>>>,
+  // External engines of the AI seats (Black, White) with their profile ids, kept running until the next game
+  engines: Mutex<[Option<(String, EngineProcess)>; 2]>,
 }
 
 #[tauri::command]
@@ -41,7 +70,13 @@ fn new_game(
   state: State<'_, AppState>,
   rule_set: RuleSetKind,
   mode: Option<GameMode>,
+  opening: Option<OpeningRule>,
+  board_size: Option<usize>,
+  time_control: Option<TimeControl>,
+  seed: Option<u64>,
 ) -> Result<GameSnapshot, String> {
+  let board_size = board_size.unwrap_or(DEFAULT_BOARD_SIZE);
+  check_board_size(board_size)?;
   let mut game = state.game.lock().map_err(|_| "Game state lock poisoned".to_string())?;
   let mode = mode.unwrap_or_default();
 
@@ -92,7 +127,17 @@ fn new_game(
     *current = profile_id.clone();
   }
 
-  *game = GameState::new(15, rule_set, players, mode);
+  let mut fresh = GameState::new(board_size, rule_set, players, mode);
+  if let Some(seed) = seed {
+    fresh.seed = seed;
+  }
+  fresh.start_opening(opening.unwrap_or_default())?;
+  if let Some(time_control) = time_control {
+    fresh.start_clock(time_control)?;
+  }
+  *game = fresh;
+  reset_search_tables(&state)?;
+  stop_engines(&state)?;
   let mut applied = state
     .rating_applied
     .lock()
@@ -103,13 +148,17 @@ fn new_game(
 
 #[tauri::command]
 fn get_state(state: State<'_, AppState>) -> Result<GameSnapshot, String> {
-  let game = state.game.lock().map_err(|_| "Game state lock poisoned".to_string())?;
+  let mut game = state.game.lock().map_err(|_| "Game state lock poisoned".to_string())?;
+  settle_flag(&state, &mut game)?;
   Ok(game.snapshot())
 }
 
 #[tauri::command]
 fn make_move(state: State<'_, AppState>, x: usize, y: usize) -> Result<GameSnapshot, String> {
   let mut game = state.game.lock().map_err(|_| "Game state lock poisoned".to_string())?;
+  if settle_flag(&state, &mut game)? {
+    return Ok(game.snapshot());
+  }
 
   if !game.can_human_move() {
     return Err("It's not your turn".to_string());
@@ -126,9 +175,52 @@ fn make_move(state: State<'_, AppState>, x: usize, y: usize) -> Result<GameSnaps
   Ok(game.snapshot())
 }
 
+#[tauri::command]
+fn undo_move(state: State<'_, AppState>, count: Option<usize>) -> Result<GameSnapshot, String> {
+  let mut game = state.game.lock().map_err(|_| "Game state lock poisoned".to_string())?;
+  game.undo(count.unwrap_or(1).max(1))?;
+  // Against the AI a takeback also removes the AI reply, so the human is to move again
+  if let GameMode::HumanVsAi { human_color } = game.mode {
+    if game.acting_color() != human_color && game.can_undo() {
+      game.undo(1)?;
+    }
+  }
+  Ok(game.snapshot())
+}
+
+#[tauri::command]
+fn redo_move(state: State<'_, AppState>, count: Option<usize>) -> Result<GameSnapshot, String> {
+  let mut game = state.game.lock().map_err(|_| "Game state lock poisoned".to_string())?;
+  game.redo(count.unwrap_or(1).max(1))?;
+  if let GameMode::HumanVsAi { human_color } = game.mode {
+    if game.acting_color() != human_color && game.can_redo() {
+      game.redo(1)?;
+    }
+  }
+  Ok(game.snapshot())
+}
+
+#[tauri::command]
+fn choose_opening(state: State<'_, AppState>, choice: OpeningChoice) -> Result<GameSnapshot, String> {
+  let mut game = state.game.lock().map_err(|_| "Game state lock poisoned".to_string())?;
+  if settle_flag(&state, &mut game)? {
+    return Ok(game.snapshot());
+  }
+
+  if !game.can_human_move() {
+    return Err("It's not your turn".to_string());
+  }
+
+  game.apply_opening_choice(choice)?;
+  Ok(game.snapshot())
+}
+
 #[tauri::command]
 fn ai_move(state: State<'_, AppState>) -> Result<GameSnapshot, String> {
   let mut game = state.game.lock().map_err(|_| "Game state lock poisoned".to_string())?;
+  if settle_flag(&state, &mut game)? {
+    return Ok(game.snapshot());
+  }
   if game.result.is_some() {
     return Err("Game is already finished".to_string());
   }
@@ -145,7 +237,7 @@ fn ai_move(state: State<'_, AppState>) -> Result<GameSnapshot, String> {
     }
     GameMode::AiVsAi { black_id, white_id } => {
       // Use the appropriate profile based on whose turn it is
-      if game.to_move == Player::B {
+      if game.acting_color() == Player::B {
         black_id.clone()
       } else {
         white_id.clone()
@@ -165,13 +257,50 @@ fn ai_move(state: State<'_, AppState>) -> Result<GameSnapshot, String> {
     .lock()
     .map_err(|_| "Rating lock poisoned".to_string())?;
   let selection = select_profile(&base, &user, &profile_id)?;
+  let opening_config = if game.opening.is_done() {
+    None
+  } else {
+    Some(opening_config(&base, &selection)?)
+  };
   drop(user);
   drop(base);
 
+  // Swap/Swap2: the AI either places an opening stone or makes the colour choice
+  if let Some(config) = opening_config {
+    let config = types::AiConfig {
+      seed: Some(game.seed),
+      ..config
+    };
+    if game.opening.awaiting_choice() {
+      let choice = ai::opening_choice(&game.board, game.rule_set, &game.opening, config);
+      game.apply_opening_choice(choice)?;
+    } else {
+      let coord = ai::opening_placement(&game.board, game.rule_set, game.to_move, config)
+        .ok_or_else(|| "No valid moves".to_string())?;
+      game.apply_move(coord.x, coord.y)?;
+    }
+    return Ok(game.snapshot());
+  }
+
   let choice = match selection {
     SelectedProfile::Heuristic { config } => {
-      ai::choose_move(&game.board, game.rule_set, game.to_move, config)
-        .ok_or_else(|| "No valid moves".to_string())?
+      let from_book = if config.use_book {
+        book_move(&state, &game, config.book_variety)?
+      } else {
+        None
+      };
+      match from_book {
+        Some(coord) => coord,
+        None => {
+          let config = types::AiConfig {
+            seed: Some(game.seed),
+            ..clocked_config(config, &game)
+          };
+          let tt = search_table(&state, game.to_move, config.hash_mb)?;
+          ai::choose_move(&game.board, game.rule_set, game.to_move, config, &tt)
+            .ok_or_else(|| "No valid moves".to_string())?
+        }
+      }
     }
     SelectedProfile::Llm { id, config } => {
       if let Some(tactical) = ai::tactical_move(&game.board, game.rule_set, game.to_move) {
@@ -184,9 +313,10 @@ fn ai_move(state: State<'_, AppState>) -> Result<GameSnapshot, String> {
           .get(&id)
           .ok_or_else(|| "Missing API key for LLM profile".to_string())?
           .clone();
-        llm::choose_move(&game.board, game.to_move, &config, &api_key, &game.moves)?
+        llm::choose_move(&game.board, game.rule_set, game.to_move, &config, &api_key, &game.moves)?
       }
     }
+    SelectedProfile::Engine { id, config } => engine_move(&state, &game, &id, &config)?,
   };
 
   // Determine player color for rating purposes
@@ -195,6 +325,10 @@ fn ai_move(state: State<'_, AppState>) -> Result<GameSnapshot, String> {
     _ => Player::B, // Default, won't actually be used for rating in AI vs AI mode
   };
 
+  // The AI may have thought past its own flag (an LLM call, say)
+  if settle_flag(&state, &mut game)? {
+    return Ok(game.snapshot());
+  }
   game.apply_move(choice.x, choice.y)?;
 
   // Only apply rating changes for human vs AI mode
@@ -205,6 +339,44 @@ fn ai_move(state: State<'_, AppState>) -> Result<GameSnapshot, String> {
   Ok(game.snapshot())
 }
 
+#[tauri::command]
+fn analyze_position(
+  state: State<'_, AppState>,
+  top_n: Option<usize>,
+  profile_id: Option<String>,
+) -> Result<PositionAnalysis, String> {
+  let (board, rule_set, to_move) = {
+    let game = state.game.lock().map_err(|_| "Game state lock poisoned".to_string())?;
+    if game.result.is_some() {
+      return Err("Game is already finished".to_string());
+    }
+    if !game.opening.is_done() {
+      return Err("Analysis is available once the opening is settled".to_string());
+    }
+    (game.board.clone(), game.rule_set, game.to_move)
+  };
+
+  let config = {
+    let base = state
+      .rating_base
+      .lock()
+      .map_err(|_| "Rating lock poisoned".to_string())?;
+    analysis_config(&base, profile_id.as_deref())?
+  };
+
+  Ok(PositionAnalysis {
+    to_move,
+    moves: ai::analyze(
+      &board,
+      rule_set,
+      to_move,
+      config,
+      top_n.unwrap_or(DEFAULT_ANALYSIS_LINES),
+      &TranspositionTable::new(config.hash_mb),
+    ),
+  })
+}
+
 #[tauri::command]
 fn save_game(state: State<'_, AppState>, path: String) -> Result<(), String> {
   let game = state.game.lock().map_err(|_| "Game state lock poisoned".to_string())?;
@@ -220,6 +392,8 @@ fn load_game(state: State<'_, AppState>, path: String) -> Result<GameSnapshot, S
   let game = GameState::from_record(record)?;
   let mut guard = state.game.lock().map_err(|_| "Game state lock poisoned".to_string())?;
   *guard = game;
+  reset_search_tables(&state)?;
+  stop_engines(&state)?;
   if guard.moves.is_empty() || guard.result.is_some() {
     let active_profile = resolve_active_profile(&state)?;
     let mut current = state
@@ -496,6 +670,7 @@ fn create_llm_profile(
     kind: ProfileKind::Llm,
     config: None,
     llm: Some(llm_config),
+    engine: None,
   };
   user.extras.push(profile);
   user.save(&ratings_user_path(&user_id))?;
@@ -562,6 +737,189 @@ fn update_llm_profile(
   get_ratings(state)
 }
 
+/// Adds an AI personality: a built-in level's search settings playing with
+/// evaluation weights read from a JSON file.
+#[tauri::command]
+fn create_heuristic_profile(
+  state: State<'_, AppState>,
+  name: String,
+  base_profile: String,
+  weights_path: String,
+) -> Result<RatingsSnapshot, String> {
+  if name.trim().is_empty() {
+    return Err("Profile name cannot be empty".to_string());
+  }
+  {
+    let game = state.game.lock().map_err(|_| "Game state lock poisoned".to_string())?;
+    if !game.moves.is_empty() && game.result.is_none() {
+      return Err("Finish the current game before adding profiles".to_string());
+    }
+  }
+  let weights = load_weights(Path::new(&weights_path))?;
+
+  let (rating, config) = {
+    let base = state
+      .rating_base
+      .lock()
+      .map_err(|_| "Rating lock poisoned".to_string())?;
+    let profile = base
+      .get_profile(&base_profile)
+      .ok_or_else(|| "Unknown profile".to_string())?;
+    let config = profile
+      .config
+      .ok_or_else(|| "Missing heuristic config".to_string())?;
+    (profile.rating, config)
+  };
+
+  let user_id = active_user_id(&state)?;
+  let mut user = state
+    .rating_user
+    .lock()
+    .map_err(|_| "Rating lock poisoned".to_string())?;
+  let id = new_heuristic_profile_id();
+  if user.get_profile_any(&id).is_some() {
+    return Err("Profile id collision".to_string());
+  }
+  user.extras.push(ProfileRating {
+    id,
+    name: name.trim().to_string(),
+    rating,
+    games: 0,
+    wins: 0,
+    draws: 0,
+    losses: 0,
+    kind: ProfileKind::Heuristic,
+    config: Some(types::AiConfig { weights, ..config }),
+    llm: None,
+    engine: None,
+  });
+  user.save(&ratings_user_path(&user_id))?;
+  drop(user);
+
+  get_ratings(state)
+}
+
+/// Renames an AI personality and, given a path, reloads its weights file.
+#[tauri::command]
+fn update_heuristic_profile(
+  state: State<'_, AppState>,
+  id: String,
+  name: String,
+  weights_path: Option<String>,
+) -> Result<RatingsSnapshot, String> {
+  if name.trim().is_empty() {
+    return Err("Profile name cannot be empty".to_string());
+  }
+  let weights = weights_path
+    .map(|path| load_weights(Path::new(&path)))
+    .transpose()?;
+
+  let user_id = active_user_id(&state)?;
+  let mut user = state
+    .rating_user
+    .lock()
+    .map_err(|_| "Rating lock poisoned".to_string())?;
+  let profile = user
+    .extras
+    .iter_mut()
+    .find(|p| p.id == id)
+    .ok_or_else(|| "Unknown profile".to_string())?;
+  if profile.kind != ProfileKind::Heuristic {
+    return Err("Profile is not a heuristic AI".to_string());
+  }
+
+  profile.name = name.trim().to_string();
+  if let (Some(weights), Some(config)) = (weights, profile.config.as_mut()) {
+    config.weights = weights;
+  }
+  user.save(&ratings_user_path(&user_id))?;
+  drop(user);
+
+  get_ratings(state)
+}
+
+/// Adds an external Gomocup-protocol engine as an opponent with its own rating.
+#[tauri::command]
+fn create_engine_profile(
+  state: State<'_, AppState>,
+  name: String,
+  config: EngineConfig,
+) -> Result<RatingsSnapshot, String> {
+  if name.trim().is_empty() {
+    return Err("Profile name cannot be empty".to_string());
+  }
+  {
+    let game = state.game.lock().map_err(|_| "Game state lock poisoned".to_string())?;
+    if !game.moves.is_empty() && game.result.is_none() {
+      return Err("Finish the current game before adding profiles".to_string());
+    }
+  }
+  let config = normalize_engine_config(config)?;
+
+  let user_id = active_user_id(&state)?;
+  let mut user = state
+    .rating_user
+    .lock()
+    .map_err(|_| "Rating lock poisoned".to_string())?;
+  let id = new_engine_profile_id();
+  if user.get_profile_any(&id).is_some() {
+    return Err("Profile id collision".to_string());
+  }
+  user.extras.push(ProfileRating {
+    id,
+    name: name.trim().to_string(),
+    rating: 1000.0,
+    games: 0,
+    wins: 0,
+    draws: 0,
+    losses: 0,
+    kind: ProfileKind::Engine,
+    config: None,
+    llm: None,
+    engine: Some(config),
+  });
+  user.save(&ratings_user_path(&user_id))?;
+  drop(user);
+
+  get_ratings(state)
+}
+
+#[tauri::command]
+fn update_engine_profile(
+  state: State<'_, AppState>,
+  id: String,
+  name: String,
+  config: EngineConfig,
+) -> Result<RatingsSnapshot, String> {
+  if name.trim().is_empty() {
+    return Err("Profile name cannot be empty".to_string());
+  }
+  let config = normalize_engine_config(config)?;
+
+  let user_id = active_user_id(&state)?;
+  let mut user = state
+    .rating_user
+    .lock()
+    .map_err(|_| "Rating lock poisoned".to_string())?;
+  let profile = user
+    .extras
+    .iter_mut()
+    .find(|p| p.id == id)
+    .ok_or_else(|| "Unknown profile".to_string())?;
+  if profile.kind != ProfileKind::Engine {
+    return Err("Profile is not an external engine".to_string());
+  }
+
+  profile.name = name.trim().to_string();
+  profile.engine = Some(config);
+  user.save(&ratings_user_path(&user_id))?;
+  drop(user);
+  // A running copy would keep playing with the old settings
+  stop_engines(&state)?;
+
+  get_ratings(state)
+}
+
 #[tauri::command]
 fn delete_llm_profile(
   state: State<'_, AppState>,
@@ -733,21 +1091,12 @@ fn start_self_play(
   parallelism: u32,
   include_llm: bool,
   llm_ids: Vec<String>,
+  engine_ids: Option<Vec<String>>,
   min_level: Option<u8>,
   max_level: Option<u8>,
+  seed: Option<u64>,
+  save_games: Option<ArchiveFormat>,
 ) -> Result<bool, String> {
-  {
-    let mut running = state
-      .self_play_running
-      .lock()
-      .map_err(|_| "Rating lock poisoned".to_string())?;
-    if *running {
-      return Err("Self play already running".to_string());
-    }
-    *running = true;
-  }
-  state.self_play_stop.store(false, Ordering::Relaxed);
-
   if include_llm && llm_ids.is_empty() {
     return Err("Select at least one LLM profile".to_string());
   }
@@ -758,13 +1107,10 @@ fn start_self_play(
     return Err("Invalid level range".to_string());
   }
 
-  let rating_base = state.rating_base.clone();
-  let rating_user = state.rating_user.clone();
-  let running_flag = state.self_play_running.clone();
-  let stop_flag = state.self_play_stop.clone();
-  let progress_window = window.clone();
-  let save_path = ratings_base_path();
-  let user_id = if include_llm {
+  let engine_ids = engine_ids.unwrap_or_default();
+  // LLM and engine profiles belong to the user, so their games go through the user's ratings
+  let mixed = include_llm || !engine_ids.is_empty();
+  let user_id = if mixed {
     Some(active_user_id(&state)?)
   } else {
     None
@@ -776,10 +1122,53 @@ fn start_self_play(
     None
   };
   let llm_ids = if include_llm { llm_ids } else { Vec::new() };
+  // Each run's games go to their own directory or file in the user's folder
+  let games_path = match save_games {
+    Some(format) => {
+      let run = format!("self_play_{}", now_timestamp());
+      let dir = user_dir(&active_user_id(&state)?).join("self_play");
+      Some(match format {
+        ArchiveFormat::Directory => (dir.join(run), format),
+        ArchiveFormat::Jsonl => (dir.join(format!("{}.jsonl", run)), format),
+      })
+    }
+    None => None,
+  };
+
+  {
+    let mut running = state
+      .self_play_running
+      .lock()
+      .map_err(|_| "Rating lock poisoned".to_string())?;
+    if *running {
+      return Err("Self play already running".to_string());
+    }
+    *running = true;
+  }
+  state.self_play_stop.store(false, Ordering::Relaxed);
+
+  let rating_base = state.rating_base.clone();
+  let rating_user = state.rating_user.clone();
+  let running_flag = state.self_play_running.clone();
+  let stop_flag = state.self_play_stop.clone();
+  let progress_window = window.clone();
+  let save_path = ratings_base_path();
 
   tauri::async_runtime::spawn_blocking(move || {
     let result = (|| -> Result<SelfPlayReport, String> {
-      if include_llm {
+      let mut archive = match games_path {
+        Some((path, format)) => Some(GameArchive::create(&path, format)?),
+        None => None,
+      };
+      let settings = SelfPlaySettings {
+        games_per_pair,
+        parallelism: usize::max(1, parallelism as usize),
+        min_level,
+        max_level,
+        seed,
+        archive: archive.as_mut(),
+      };
+      if mixed {
         let base_store = {
           let rating = rating_base
             .lock()
@@ -797,10 +1186,12 @@ fn start_self_play(
         let report = run_self_play_mixed(
           &base_store,
           &mut user_store,
-          &key_store.keys,
-          games_per_pair,
-          usize::max(1, parallelism as usize),
-          &llm_ids,
+          MixedProfiles {
+            llm_ids: &llm_ids,
+            llm_keys: &key_store.keys,
+            engine_ids: &engine_ids,
+          },
+          settings,
           stop_flag,
           |completed, total| {
             let percent = if total == 0 {
@@ -818,8 +1209,6 @@ fn start_self_play(
             );
           },
           &save_path,
-          min_level,
-          max_level,
         );
         if let Ok(mut rating) = rating_user.lock() {
           *rating = user_store;
@@ -835,8 +1224,7 @@ fn start_self_play(
         let report = run_self_play(
           &mut local_store,
           &save_path,
-          games_per_pair,
-          usize::max(1, parallelism as usize),
+          settings,
           stop_flag,
           |completed, total| {
             let percent = if total == 0 {
@@ -853,8 +1241,6 @@ fn start_self_play(
               },
             );
           },
-          min_level,
-          max_level,
         );
         if let Ok(mut rating) = rating_base.lock() {
           *rating = local_store;
@@ -892,12 +1278,530 @@ fn stop_self_play(state: State<'_, AppState>) -> Result<(), String> {
   Ok(())
 }
 
+/// Tunes evaluation weights by self-play in the background, starting from a
+/// built-in level and either its own weights or those in `weights_path`.
+/// Progress is reported through `tuning_progress` events; the tuned weights
+/// and a report are written to `output_path` once done or stopped.
+#[tauri::command]
+fn start_tuning(
+  state: State<'_, AppState>,
+  window: Window,
+  profile_id: String,
+  weights_path: Option<String>,
+  output_path: String,
+  settings: TuneSettings,
+) -> Result<bool, String> {
+  let mut config = {
+    let base = state
+      .rating_base
+      .lock()
+      .map_err(|_| "Rating lock poisoned".to_string())?;
+    base
+      .get_profile(&profile_id)
+      .and_then(|profile| profile.config)
+      .ok_or_else(|| "Tuning needs a heuristic profile".to_string())?
+  };
+  if let Some(path) = weights_path {
+    config.weights = load_weights(Path::new(&path))?;
+  }
+  {
+    let mut running = state
+      .tuning_running
+      .lock()
+      .map_err(|_| "Tuning lock poisoned".to_string())?;
+    if *running {
+      return Err("Tuning already running".to_string());
+    }
+    *running = true;
+  }
+  state.tuning_stop.store(false, Ordering::Relaxed);
+
+  let running_flag = state.tuning_running.clone();
+  let stop_flag = state.tuning_stop.clone();
+  tauri::async_runtime::spawn_blocking(move || {
+    let result = run_spsa(config, &settings, &stop_flag, |progress| {
+      let _ = window.emit("tuning_progress", progress);
+    })
+    .and_then(|report| {
+      save_results(&report, Path::new(&output_path))?;
+      Ok(report)
+    });
+
+    match result {
+      Ok(report) => {
+        let _ = window.emit("tuning_done", report);
+      }
+      Err(err) => {
+        let _ = window.emit("tuning_error", err);
+      }
+    }
+
+    if let Ok(mut running) = running_flag.lock() {
+      *running = false;
+    }
+  });
+
+  Ok(true)
+}
+
+#[tauri::command]
+fn stop_tuning(state: State<'_, AppState>) -> Result<(), String> {
+  let running = state
+    .tuning_running
+    .lock()
+    .map_err(|_| "Tuning lock poisoned".to_string())?;
+  if *running {
+    state.tuning_stop.store(true, Ordering::Relaxed);
+  }
+  Ok(())
+}
+
+/// Runs an SPRT of `candidate` against `baseline` in the background. Each
+/// finished pair is reported through a `sprt_progress` event with the
+/// statistics so far, and the final report through `sprt_done`.
+#[tauri::command]
+fn start_sprt(
+  state: State<'_, AppState>,
+  window: Window,
+  candidate: TournamentPlayer,
+  baseline: TournamentPlayer,
+  settings: SprtSettings,
+) -> Result<bool, String> {
+  let players = {
+    let base = state
+      .rating_base
+      .lock()
+      .map_err(|_| "Rating lock poisoned".to_string())?;
+    resolve_players(&[candidate, baseline], &base)?
+  };
+  {
+    let mut running = state
+      .sprt_running
+      .lock()
+      .map_err(|_| "SPRT lock poisoned".to_string())?;
+    if *running {
+      return Err("SPRT already running".to_string());
+    }
+    *running = true;
+  }
+  state.sprt_stop.store(false, Ordering::Relaxed);
+
+  let running_flag = state.sprt_running.clone();
+  let stop_flag = state.sprt_stop.clone();
+  tauri::async_runtime::spawn_blocking(move || {
+    let result = run_sprt(&settings, &players[0], &players[1], &stop_flag, |report, _| {
+      let _ = window.emit("sprt_progress", report);
+      Ok(())
+    });
+
+    match result {
+      Ok(report) => {
+        let _ = window.emit("sprt_done", report);
+      }
+      Err(err) => {
+        let _ = window.emit("sprt_error", err);
+      }
+    }
+
+    if let Ok(mut running) = running_flag.lock() {
+      *running = false;
+    }
+  });
+
+  Ok(true)
+}
+
+#[tauri::command]
+fn stop_sprt(state: State<'_, AppState>) -> Result<(), String> {
+  let running = state
+    .sprt_running
+    .lock()
+    .map_err(|_| "SPRT lock poisoned".to_string())?;
+  if *running {
+    state.sprt_stop.store(true, Ordering::Relaxed);
+  }
+  Ok(())
+}
+
+/// Builds an opening book from the saved games in `records_dir` and makes it
+/// the active book. Board size and rules default to those of the current game.
+#[tauri::command]
+fn build_opening_book(
+  state: State<'_, AppState>,
+  records_dir: String,
+  board_size: Option<usize>,
+  rule_set: Option<RuleSetKind>,
+  max_plies: Option<usize>,
+) -> Result<BookSummary, String> {
+  let (current_size, current_rules) = {
+    let game = state.game.lock().map_err(|_| "Game state lock poisoned".to_string())?;
+    (game.board.size(), game.rule_set)
+  };
+  let board_size = board_size.unwrap_or(current_size);
+  check_board_size(board_size)?;
+  let (book, skipped) = OpeningBook::build(
+    Path::new(&records_dir),
+    board_size,
+    rule_set.unwrap_or(current_rules),
+    max_plies.unwrap_or(DEFAULT_BOOK_PLIES),
+  )?;
+  if book.games == 0 {
+    return Err("No finished games for this board size and rule set".to_string());
+  }
+  book.save(&opening_book_path())?;
+  let summary = book.summary(skipped);
+  set_opening_book(&state, Some(book))?;
+  Ok(summary)
+}
+
+/// Replaces the active opening book with the book file at `path`.
+#[tauri::command]
+fn load_opening_book(state: State<'_, AppState>, path: String) -> Result<BookSummary, String> {
+  let book = OpeningBook::load(Path::new(&path))?;
+  check_board_size(book.board_size)?;
+  book.save(&opening_book_path())?;
+  let summary = book.summary(0);
+  set_opening_book(&state, Some(book))?;
+  Ok(summary)
+}
+
+#[tauri::command]
+fn get_opening_book(state: State<'_, AppState>) -> Result<Option<BookSummary>, String> {
+  let book = state.book.lock().map_err(|_| "Opening book lock poisoned".to_string())?;
+  Ok(book.as_ref().map(|book| book.summary(0)))
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AnalysisUpdate {
+  to_move: Player,
+  depth: u8,
+  score: i32,
+  pv: Vec<Coord>,
+  nodes: u64,
+  nodes_per_second: u64,
+  lines: Vec<MoveAnalysis>,
+}
+
+/// Searches for a VCF/VCT win for the side to move; `None` if none was found within the budget.
+#[tauri::command]
+fn find_forced_win(
+  state: State<'_, AppState>,
+  max_nodes: Option<u32>,
+) -> Result<Option<threat::ForcedWin>, String> {
+  let (board, rule_set, to_move) = {
+    let game = state.game.lock().map_err(|_| "Game state lock poisoned".to_string())?;
+    if game.result.is_some() {
+      return Err("Game is already finished".to_string());
+    }
+    (game.board.clone(), game.rule_set, game.to_move)
+  };
+  Ok(threat::find_forced_win(
+    &board,
+    rule_set,
+    to_move,
+    max_nodes.unwrap_or(DEFAULT_SOLVER_NODES),
+  ))
+}
+
+/// Proves a win or loss for the side to move by proof-number search, within
+/// `max_nodes` positions; the outcome is `unknown` if the budget runs out.
+#[tauri::command]
+fn solve_position(state: State<'_, AppState>, max_nodes: Option<u32>) -> Result<proof::SolveResult, String> {
+  let (board, rule_set, to_move) = {
+    let game = state.game.lock().map_err(|_| "Game state lock poisoned".to_string())?;
+    if game.result.is_some() {
+      return Err("Game is already finished".to_string());
+    }
+    (game.board.clone(), game.rule_set, game.to_move)
+  };
+  Ok(proof::solve(&board, rule_set, to_move, max_nodes.unwrap_or(DEFAULT_PROOF_NODES)))
+}
+
+#[tauri::command]
+fn start_analysis(
+  state: State<'_, AppState>,
+  window: Window,
+  top_n: Option<usize>,
+  profile_id: Option<String>,
+) -> Result<bool, String> {
+  let (board, rule_set, to_move) = {
+    let game = state.game.lock().map_err(|_| "Game state lock poisoned".to_string())?;
+    if game.result.is_some() {
+      return Err("Game is already finished".to_string());
+    }
+    if !game.opening.is_done() {
+      return Err("Analysis is available once the opening is settled".to_string());
+    }
+    (game.board.clone(), game.rule_set, game.to_move)
+  };
+  let config = {
+    let base = state
+      .rating_base
+      .lock()
+      .map_err(|_| "Rating lock poisoned".to_string())?;
+    analysis_config(&base, profile_id.as_deref())?
+  };
+
+  {
+    let mut running = state
+      .analysis_running
+      .lock()
+      .map_err(|_| "Analysis lock poisoned".to_string())?;
+    if *running {
+      return Err("Analysis already running".to_string());
+    }
+    *running = true;
+  }
+  state.analysis_stop.store(false, Ordering::Relaxed);
+
+  let running_flag = state.analysis_running.clone();
+  let stop_flag = state.analysis_stop.clone();
+  let top_n = top_n.unwrap_or(DEFAULT_ANALYSIS_LINES);
+
+  tauri::async_runtime::spawn_blocking(move || {
+    let started = Instant::now();
+    let lines = ai::analyze_until_stopped(
+      &board,
+      rule_set,
+      to_move,
+      config,
+      top_n,
+      stop_flag,
+      |lines, nodes| {
+        let Some(best) = lines.first() else {
+          return;
+        };
+        let elapsed = started.elapsed().as_secs_f64().max(0.001);
+        let _ = window.emit(
+          "analysis_update",
+          AnalysisUpdate {
+            to_move,
+            depth: best.depth,
+            score: best.score,
+            pv: best.pv.clone(),
+            nodes,
+            nodes_per_second: (nodes as f64 / elapsed) as u64,
+            lines: lines.to_vec(),
+          },
+        );
+      },
+    );
+    let _ = window.emit("analysis_done", PositionAnalysis { to_move, moves: lines });
+
+    if let Ok(mut running) = running_flag.lock() {
+      *running = false;
+    }
+  });
+
+  Ok(true)
+}
+
+#[tauri::command]
+fn stop_analysis(state: State<'_, AppState>) -> Result<(), String> {
+  let running = state
+    .analysis_running
+    .lock()
+    .map_err(|_| "Analysis lock poisoned".to_string())?;
+  if *running {
+    state.analysis_stop.store(true, Ordering::Relaxed);
+  }
+  Ok(())
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReviewProgress {
+  completed: usize,
+  total: usize,
+  percent: f32,
+}
+
+/// Reviews the saved game at `path`, or the current game when no path is given.
+#[tauri::command]
+fn start_review(
+  state: State<'_, AppState>,
+  window: Window,
+  path: Option<String>,
+  profile_id: Option<String>,
+) -> Result<bool, String> {
+  let record = match path {
+    Some(path) => {
+      let data = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+      serde_json::from_str::<GameRecord>(&data).map_err(|e| e.to_string())?
+    }
+    None => state
+      .game
+      .lock()
+      .map_err(|_| "Game state lock poisoned".to_string())?
+      .to_record(),
+  };
+  let config = {
+    let base = state
+      .rating_base
+      .lock()
+      .map_err(|_| "Rating lock poisoned".to_string())?;
+    analysis_config(&base, profile_id.as_deref())?
+  };
+
+  {
+    let mut running = state
+      .review_running
+      .lock()
+      .map_err(|_| "Review lock poisoned".to_string())?;
+    if *running {
+      return Err("Review already running".to_string());
+    }
+    *running = true;
+  }
+  let running_flag = state.review_running.clone();
+
+  tauri::async_runtime::spawn_blocking(move || {
+    let result = review::review_game(&record, config, |completed, total| {
+      let percent = if total == 0 {
+        100.0
+      } else {
+        (completed as f32 / total as f32) * 100.0
+      };
+      let _ = window.emit(
+        "review_progress",
+        ReviewProgress {
+          completed,
+          total,
+          percent,
+        },
+      );
+    });
+
+    match result {
+      Ok(report) => {
+        let _ = window.emit("review_done", report);
+      }
+      Err(err) => {
+        let _ = window.emit("review_error", err);
+      }
+    }
+
+    if let Ok(mut running) = running_flag.lock() {
+      *running = false;
+    }
+  });
+
+  Ok(true)
+}
+
+// The table `seat`'s AI searches with, created on its first move of the game
+// or again if the profile asks for a different size.
+fn search_table(
+  state: &State<'_, AppState>,
+  seat: Player,
+  size_mb: usize,
+) -> Result<Arc<TranspositionTable>, String> {
+  let mut tables = state
+    .search_tables
+    .lock()
+    .map_err(|_| "Search table lock poisoned".to_string())?;
+  let slot = &mut tables[if seat == Player::B { 0 } else { 1 }];
+  match slot {
+    Some(tt) if tt.size_mb() == size_mb.clamp(MIN_HASH_MB, MAX_HASH_MB) => Ok(tt.clone()),
+    _ => {
+      let tt = Arc::new(TranspositionTable::new(size_mb));
+      *slot = Some(tt.clone());
+      Ok(tt)
+    }
+  }
+}
+
+fn set_opening_book(state: &State<'_, AppState>, book: Option<OpeningBook>) -> Result<(), String> {
+  let mut active = state.book.lock().map_err(|_| "Opening book lock poisoned".to_string())?;
+  *active = book.map(Arc::new);
+  Ok(())
+}
+
+// A move from the active opening book, if the position is in it
+fn book_move(state: &State<'_, AppState>, game: &GameState, variety: u8) -> Result<Option<Coord>, String> {
+  let book = state
+    .book
+    .lock()
+    .map_err(|_| "Opening book lock poisoned".to_string())?
+    .clone();
+  let mut rng = ai::move_rng(Some(game.seed), &game.board);
+  Ok(book.and_then(|book| book.pick(&game.board, game.rule_set, game.to_move, variety, &mut rng)))
+}
+
+// A move from the seat's external engine, which is started on first use.
+// An engine that fails is stopped, so the next request starts it afresh.
+fn engine_move(state: &State<'_, AppState>, game: &GameState, id: &str, config: &EngineConfig) -> Result<Coord, String> {
+  let mut engines = state.engines.lock().map_err(|_| "Engine lock poisoned".to_string())?;
+  let slot = &mut engines[if game.to_move == Player::B { 0 } else { 1 }];
+  let engine = match slot {
+    Some((running, engine)) if running == id => engine,
+    _ => {
+      // The old engine has to quit before the new one starts
+      *slot = None;
+      let engine = EngineProcess::start(config, game.board.size(), game.rule_set)?;
+      &mut slot.insert((id.to_string(), engine)).1
+    }
+  };
+  let timeout_turn_ms = match game.clock.as_ref() {
+    Some(clock) => config.timeout_turn_ms.min(clock.move_budget_ms(game.acting_color())),
+    None => config.timeout_turn_ms,
+  };
+  let result = engine.choose_move(&game.moves, game.to_move, timeout_turn_ms);
+  if result.is_err() {
+    *slot = None;
+  }
+  result
+}
+
+fn stop_engines(state: &State<'_, AppState>) -> Result<(), String> {
+  let mut engines = state.engines.lock().map_err(|_| "Engine lock poisoned".to_string())?;
+  *engines = [None, None];
+  Ok(())
+}
+
+fn reset_search_tables(state: &State<'_, AppState>) -> Result<(), String> {
+  let mut tables = state
+    .search_tables
+    .lock()
+    .map_err(|_| "Search table lock poisoned".to_string())?;
+  *tables = [None, None];
+  Ok(())
+}
+
+// Ends the game if the seat to act has run out of time, rating it like any
+// other result. Returns true if the flag fell just now.
+fn settle_flag(state: &State<'_, AppState>, game: &mut GameState) -> Result<bool, String> {
+  if !game.check_flag() {
+    return Ok(false);
+  }
+  if let GameMode::HumanVsAi { human_color } = game.mode {
+    maybe_apply_rating(state, game, human_color)?;
+  }
+  Ok(true)
+}
+
+// Under a game clock the AI thinks no longer than its share of the remaining
+// time, or the profile's own per-move limit if that is shorter.
+fn clocked_config(config: types::AiConfig, game: &GameState) -> types::AiConfig {
+  let Some(clock) = game.clock.as_ref() else {
+    return config;
+  };
+  let budget = clock.move_budget_ms(game.acting_color());
+  let move_time_ms = if config.move_time_ms > 0 {
+    config.move_time_ms.min(budget)
+  } else {
+    budget
+  };
+  types::AiConfig { move_time_ms, ..config }
+}
+
 fn maybe_apply_rating(
   state: &State<'_, AppState>,
   game: &GameState,
   player_color: Player,
 ) -> Result<(), String> {
-  if game.result.is_none() {
+  if game.result.is_none() || !game.rated {
     return Ok(());
   }
 
@@ -1016,10 +1920,12 @@ fn profile_label_for(state: &State<'_, AppState>, id: &str) -> Result<String, St
     .rating_user
     .lock()
     .map_err(|_| "Rating lock poisoned".to_string())?;
-  if user.extras.iter().any(|p| p.id == id && p.kind == ProfileKind::Llm) {
-    return Ok("LLM".to_string());
-  }
-  Ok("AI".to_string())
+  let label = match user.extras.iter().find(|p| p.id == id).map(|p| p.kind) {
+    Some(ProfileKind::Llm) => "LLM",
+    Some(ProfileKind::Engine) => "Engine",
+    _ => "AI",
+  };
+  Ok(label.to_string())
 }
 
 fn profile_exists(base: &RatingStore, user: &RatingStore, id: &str) -> bool {
@@ -1029,6 +1935,7 @@ fn profile_exists(base: &RatingStore, user: &RatingStore, id: &str) -> bool {
 enum SelectedProfile {
   Heuristic { config: types::AiConfig },
   Llm { id: String, config: LlmConfig },
+  Engine { id: String, config: EngineConfig },
 }
 
 fn select_profile(
@@ -1043,8 +1950,21 @@ fn select_profile(
     return Ok(SelectedProfile::Heuristic { config });
   }
   if let Some(profile) = user.extras.iter().find(|p| p.id == id) {
-    if profile.kind != ProfileKind::Llm {
-      return Err("Unsupported profile kind".to_string());
+    if profile.kind == ProfileKind::Heuristic {
+      let config = profile
+        .config
+        .ok_or_else(|| "Missing heuristic config".to_string())?;
+      return Ok(SelectedProfile::Heuristic { config });
+    }
+    if profile.kind == ProfileKind::Engine {
+      let config = profile
+        .engine
+        .clone()
+        .ok_or_else(|| "Missing engine config".to_string())?;
+      return Ok(SelectedProfile::Engine {
+        id: id.to_string(),
+        config,
+      });
     }
     let config = profile
       .llm
@@ -1058,6 +1978,34 @@ fn select_profile(
   Err("Unknown profile".to_string())
 }
 
+// LLM and engine profiles have no search settings of their own, so they
+// handle the opening protocol like the mid-ladder heuristic level.
+fn opening_config(base: &RatingStore, selection: &SelectedProfile) -> Result<types::AiConfig, String> {
+  match selection {
+    SelectedProfile::Heuristic { config } => Ok(*config),
+    SelectedProfile::Llm { .. } | SelectedProfile::Engine { .. } => base
+      .get_profile("l05")
+      .and_then(|profile| profile.config)
+      .ok_or_else(|| "Missing heuristic config".to_string()),
+  }
+}
+
+// Hints and analysis use the strongest built-in level unless a heuristic profile is given
+fn analysis_config(base: &RatingStore, profile_id: Option<&str>) -> Result<types::AiConfig, String> {
+  let id = profile_id.unwrap_or(ANALYSIS_PROFILE);
+  let config = base
+    .get_profile(id)
+    .and_then(|profile| profile.config)
+    .ok_or_else(|| "Analysis needs a heuristic profile".to_string())?;
+  // Deterministic: the hint should always be the engine's top choice. Nothing
+  // is rated here, so the search may use every core.
+  Ok(types::AiConfig {
+    randomness: 0,
+    threads: 0,
+    ..config
+  })
+}
+
 fn effective_profiles(
   base: &RatingStore,
   user: &RatingStore,
@@ -1082,6 +2030,7 @@ fn effective_profiles(
         kind: ProfileKind::Heuristic,
         config: profile.config,
         llm: None,
+        engine: None,
       }
     })
     .collect();
@@ -1144,6 +2093,30 @@ fn new_llm_profile_id() -> String {
   format!("llm-{}-{:08x}", now_timestamp(), rand_part)
 }
 
+fn new_heuristic_profile_id() -> String {
+  let rand_part: u32 = rand::random();
+  format!("ai-{}-{:08x}", now_timestamp(), rand_part)
+}
+
+fn new_engine_profile_id() -> String {
+  let rand_part: u32 = rand::random();
+  format!("engine-{}-{:08x}", now_timestamp(), rand_part)
+}
+
+fn normalize_engine_config(mut config: EngineConfig) -> Result<EngineConfig, String> {
+  config.path = config.path.trim().to_string();
+  if config.path.is_empty() {
+    return Err("Engine path cannot be empty".to_string());
+  }
+  if !Path::new(&config.path).is_file() {
+    return Err(format!("Engine not found: {}", config.path));
+  }
+  if config.timeout_turn_ms < 100 {
+    config.timeout_turn_ms = 100;
+  }
+  Ok(config)
+}
+
 fn normalize_llm_config(mut config: LlmConfig) -> Result<LlmConfig, String> {
   if config.model.trim().is_empty() {
     return Err("Model name cannot be empty".to_string());
@@ -1266,7 +2239,7 @@ fn main() {
     black: "Human".to_string(),
     white: "AI".to_string(),
   };
-  let game = GameState::new(15, RuleSetKind::Standard, players, GameMode::default());
+  let game = GameState::new(DEFAULT_BOARD_SIZE, RuleSetKind::Standard, players, GameMode::default());
   let _ = ensure_data_dirs();
 
   let users_path = users_path();
@@ -1343,6 +2316,13 @@ fn main() {
     }
   };
 
+  let book_path = opening_book_path();
+  let book = if book_path.exists() {
+    OpeningBook::load(&book_path).ok().map(Arc::new)
+  } else {
+    None
+  };
+
   tauri::Builder::default()
     .manage(AppState {
       game: Mutex::new(game),
@@ -1356,12 +2336,31 @@ fn main() {
       rating_applied: Mutex::new(false),
       self_play_running: Arc::new(Mutex::new(false)),
       self_play_stop: Arc::new(AtomicBool::new(false)),
+      analysis_running: Arc::new(Mutex::new(false)),
+      analysis_stop: Arc::new(AtomicBool::new(false)),
+      review_running: Arc::new(Mutex::new(false)),
+      tuning_running: Arc::new(Mutex::new(false)),
+      tuning_stop: Arc::new(AtomicBool::new(false)),
+      sprt_running: Arc::new(Mutex::new(false)),
+      sprt_stop: Arc::new(AtomicBool::new(false)),
+      search_tables: Mutex::new([None, None]),
+      book: Mutex::new(book),
+      engines: Mutex::new([None, None]),
     })
     .invoke_handler(tauri::generate_handler![
       new_game,
       get_state,
       make_move,
+      choose_opening,
+      undo_move,
+      redo_move,
       ai_move,
+      analyze_position,
+      find_forced_win,
+      solve_position,
+      start_analysis,
+      stop_analysis,
+      start_review,
       save_game,
       load_game,
       export_training,
@@ -1372,12 +2371,23 @@ fn main() {
       create_llm_profile,
       update_llm_profile,
       delete_llm_profile,
+      create_engine_profile,
+      update_engine_profile,
+      create_heuristic_profile,
+      update_heuristic_profile,
       create_user,
       update_user,
       set_active_user,
       delete_user,
       start_self_play,
       stop_self_play,
+      start_tuning,
+      stop_tuning,
+      start_sprt,
+      stop_sprt,
+      build_opening_book,
+      load_opening_book,
+      get_opening_book,
     ])
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
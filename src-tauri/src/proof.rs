@@ -0,0 +1,391 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::Board;
+use crate::rules::{rules_for, RuleSet};
+use crate::threat::{five_points, five_points_through, threat_moves};
+use crate::types::{Coord, Move, Player, RuleSetKind};
+
+const INFINITY: u32 = u32::MAX;
+// Starting proof (or disproof) number of a node whose side to move is free to
+// play anywhere, against 1 for a node where it has to answer a four
+const QUIET_COST: u32 = 4;
+// Moves are generated within this distance of the stones on the board
+const MOVE_RADIUS: usize = 2;
+// The budget is handed to the two searches in this many slices each
+const BUDGET_ROUNDS: u32 = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SolveOutcome {
+  /// The side to move can force a win.
+  Win,
+  /// The opponent can force a win whatever the side to move plays.
+  Loss,
+  /// Neither was proven within the node budget.
+  Unknown,
+}
+
+/// A move in a proof tree. Below a move of the winning side are all the
+/// loser's replies; below a move of the losing side is the one reply that
+/// keeps the win. The tree ends with the winning five.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofNode {
+  pub coord: Coord,
+  pub children: Vec<ProofNode>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SolveResult {
+  pub outcome: SolveOutcome,
+  /// Side to move in the solved position.
+  pub to_move: Player,
+  pub nodes: u32,
+  /// Main line of the proof, starting with the side to move. The loser's
+  /// moves are the replies that hold out longest.
+  pub line: Vec<Coord>,
+  /// The proof below the current position: the winning move for a win, or
+  /// every move of the side to move for a loss.
+  pub tree: Vec<ProofNode>,
+}
+
+/// Decides the position by proof-number search, looking for a win for
+/// `to_move` and for the opponent side by side until one is proven. The two
+/// searches share `max_nodes`, which bounds the positions in their trees.
+///
+/// The side trying to win only plays fours and threes (and blocks the other
+/// side's fours), so a quiet winning move is never found. The defending side
+/// may play any point within two of a stone, so what is proven holds for any
+/// defence near the stones, which is where tactical fights are decided.
+pub fn solve(board: &Board, rule_set: RuleSetKind, to_move: Player, max_nodes: u32) -> SolveResult {
+  let rules = rules_for(rule_set);
+  let mut searches = [
+    (ProofSearch::new(board, rules.as_ref(), to_move, to_move), SolveOutcome::Win),
+    (ProofSearch::new(board, rules.as_ref(), to_move, to_move.other()), SolveOutcome::Loss),
+  ];
+  let step = (max_nodes / BUDGET_ROUNDS).max(1);
+  let total = |searches: &[(ProofSearch, SolveOutcome)]| searches.iter().map(|(search, _)| search.size()).sum::<u32>();
+
+  while total(&searches) < max_nodes && searches.iter().any(|(search, _)| !search.resolved()) {
+    for index in 0..searches.len() {
+      let remaining = max_nodes.saturating_sub(total(&searches));
+      let (search, outcome) = &mut searches[index];
+      if search.resolved() {
+        continue;
+      }
+      search.run(search.size() + step.min(remaining));
+      if search.proven() {
+        let (tree, line, _) = search.proof(0);
+        return SolveResult {
+          outcome: *outcome,
+          to_move,
+          nodes: total(&searches),
+          line,
+          tree,
+        };
+      }
+    }
+  }
+  SolveResult {
+    outcome: SolveOutcome::Unknown,
+    to_move,
+    nodes: total(&searches),
+    line: Vec::new(),
+    tree: Vec::new(),
+  }
+}
+
+struct Node {
+  coord: Coord, // Move that led here; unused at the root
+  parent: usize,
+  to_move: Player,
+  // Children are stored next to each other once the node is expanded
+  first_child: usize,
+  child_count: usize,
+  expanded: bool,
+  proof: u32,
+  disproof: u32,
+  // Earlier node, reached by other moves, that already settled this position
+  transposition: Option<usize>,
+}
+
+// Proof-number search for a win by `winner`. Nodes where `winner` is to move
+// are OR nodes (one winning move is enough), the others are AND nodes.
+struct ProofSearch<'a> {
+  board: Board,
+  rules: &'a dyn RuleSet,
+  winner: Player,
+  nodes: Vec<Node>,
+  // Settled positions by hash and side to move
+  settled: HashMap<(u64, Player), usize>,
+}
+
+impl<'a> ProofSearch<'a> {
+  fn new(board: &Board, rules: &'a dyn RuleSet, to_move: Player, winner: Player) -> Self {
+    let root = Node {
+      coord: Coord { x: 0, y: 0 },
+      parent: 0,
+      to_move,
+      first_child: 0,
+      child_count: 0,
+      expanded: false,
+      proof: 1,
+      disproof: 1,
+      transposition: None,
+    };
+    Self {
+      board: board.clone(),
+      rules,
+      winner,
+      nodes: vec![root],
+      settled: HashMap::new(),
+    }
+  }
+
+  // Grows the tree until the win is proven or disproven or it holds `max_nodes` positions.
+  fn run(&mut self, max_nodes: u32) {
+    while !self.resolved() && self.size() < max_nodes {
+      let leaf = self.select();
+      self.expand(leaf);
+      self.backup(leaf);
+    }
+  }
+
+  fn size(&self) -> u32 {
+    self.nodes.len() as u32
+  }
+
+  fn proven(&self) -> bool {
+    self.nodes[0].proof == 0
+  }
+
+  fn resolved(&self) -> bool {
+    self.nodes[0].proof == 0 || self.nodes[0].disproof == 0
+  }
+
+  // Descends to the most-proving leaf, playing its moves on the board.
+  fn select(&mut self) -> usize {
+    let mut current = 0;
+    while self.nodes[current].expanded {
+      let node = &self.nodes[current];
+      let children = node.first_child..node.first_child + node.child_count;
+      let best = if node.to_move == self.winner {
+        children.min_by_key(|&child| self.nodes[child].proof)
+      } else {
+        children.min_by_key(|&child| self.nodes[child].disproof)
+      };
+      let Some(best) = best else {
+        break;
+      };
+      let coord = self.nodes[best].coord;
+      self.board.set(coord.x, coord.y, node.to_move);
+      current = best;
+    }
+    current
+  }
+
+  fn expand(&mut self, index: usize) {
+    let to_move = self.nodes[index].to_move;
+    let moves = self.moves(to_move);
+    self.nodes[index].first_child = self.nodes.len();
+    self.nodes[index].child_count = moves.len();
+    self.nodes[index].expanded = true;
+    for coord in moves {
+      let (proof, disproof, transposition) = self.initial_numbers(coord, to_move);
+      self.nodes.push(Node {
+        coord,
+        parent: index,
+        to_move: to_move.other(),
+        first_child: 0,
+        child_count: 0,
+        expanded: false,
+        proof,
+        disproof,
+        transposition,
+      });
+    }
+  }
+
+  // Updates the numbers from `index` up to the root, taking the moves back off the board.
+  fn backup(&mut self, mut index: usize) {
+    loop {
+      let node = &self.nodes[index];
+      let children = &self.nodes[node.first_child..node.first_child + node.child_count];
+      let (proof, disproof) = if children.is_empty() {
+        // No moves: the winner has run out of threats, or the other side
+        // cannot block a four
+        if node.to_move == self.winner {
+          (INFINITY, 0)
+        } else {
+          (0, INFINITY)
+        }
+      } else if node.to_move == self.winner {
+        (
+          children.iter().map(|child| child.proof).min().unwrap_or(INFINITY),
+          children.iter().fold(0, |sum: u32, child| sum.saturating_add(child.disproof)),
+        )
+      } else {
+        (
+          children.iter().fold(0, |sum: u32, child| sum.saturating_add(child.proof)),
+          children.iter().map(|child| child.disproof).min().unwrap_or(INFINITY),
+        )
+      };
+      let node = &mut self.nodes[index];
+      node.proof = proof;
+      node.disproof = disproof;
+      if index == 0 {
+        break;
+      }
+      if proof == 0 || disproof == 0 {
+        self.settled.insert((self.board.hash(), node.to_move), index);
+      }
+      self.board.clear(node.coord.x, node.coord.y);
+      index = node.parent;
+    }
+  }
+
+  // Proof and disproof numbers of the position after `player` plays `coord`,
+  // and the node it transposes to if that position was already settled.
+  fn initial_numbers(&mut self, coord: Coord, player: Player) -> (u32, u32, Option<usize>) {
+    let mv = Move {
+      x: coord.x,
+      y: coord.y,
+      player,
+      t: None,
+    };
+    self.board.set(coord.x, coord.y, player);
+    if let Some(&settled) = self.settled.get(&(self.board.hash(), player.other())) {
+      self.board.clear(coord.x, coord.y);
+      let node = &self.nodes[settled];
+      return (node.proof, node.disproof, Some(settled));
+    }
+    let (proof, disproof) = if self.rules.check_win(&self.board, &mv).is_some() {
+      if player == self.winner {
+        (0, INFINITY)
+      } else {
+        (INFINITY, 0)
+      }
+    } else if self.board.is_full() {
+      // A draw is no win for either side
+      (INFINITY, 0)
+    } else {
+      let forced = !five_points_through(&mut self.board, self.rules, player, coord).is_empty();
+      let cost = if forced { 1 } else { QUIET_COST };
+      if player == self.winner {
+        (cost, 1)
+      } else {
+        (1, cost)
+      }
+    };
+    self.board.clear(coord.x, coord.y);
+    (proof, disproof, None)
+  }
+
+  // Completing a five ends the game, and facing a four only the blocks
+  // matter. Otherwise the winner makes a four or a three and the other side
+  // may play any legal point near the stones.
+  fn moves(&mut self, player: Player) -> Vec<Coord> {
+    if let Some(&five) = five_points(&mut self.board, self.rules, player).first() {
+      return vec![five];
+    }
+    let blocks = five_points(&mut self.board, self.rules, player.other());
+    let points = if !blocks.is_empty() {
+      blocks
+    } else if player == self.winner {
+      return threat_moves(&mut self.board, self.rules, player, true);
+    } else {
+      self.nearby_points()
+    };
+    points
+      .into_iter()
+      .filter(|point| {
+        self.rules.is_legal(
+          &self.board,
+          &Move {
+            x: point.x,
+            y: point.y,
+            player,
+            t: None,
+          },
+        )
+      })
+      .collect()
+  }
+
+  fn nearby_points(&self) -> Vec<Coord> {
+    let size = self.board.size();
+    let mut near = vec![false; size * size];
+    let mut has_stones = false;
+    for y in 0..size {
+      for x in 0..size {
+        if self.board.get(x, y).is_none() {
+          continue;
+        }
+        has_stones = true;
+        for ny in y.saturating_sub(MOVE_RADIUS)..(y + MOVE_RADIUS + 1).min(size) {
+          for nx in x.saturating_sub(MOVE_RADIUS)..(x + MOVE_RADIUS + 1).min(size) {
+            near[ny * size + nx] = true;
+          }
+        }
+      }
+    }
+    if !has_stones {
+      return vec![Coord {
+        x: size / 2,
+        y: size / 2,
+      }];
+    }
+    self
+      .board
+      .empty_coords()
+      .into_iter()
+      .filter(|point| near[point.y * size + point.x])
+      .collect()
+  }
+
+  // Proof trees below a proven node, its main line and the number of moves
+  // in the trees. Only called on proven nodes, so the winner always has a
+  // child with proof number 0. The main line follows the loser's reply with
+  // the largest proof, i.e. the one that takes the most work to beat.
+  fn proof(&self, index: usize) -> (Vec<ProofNode>, Vec<Coord>, usize) {
+    let node = &self.nodes[index];
+    if let Some(settled) = node.transposition {
+      return self.proof(settled);
+    }
+    let children = node.first_child..node.first_child + node.child_count;
+    if node.to_move == self.winner {
+      let Some(child) = children.clone().find(|&child| self.nodes[child].proof == 0) else {
+        return (Vec::new(), Vec::new(), 0);
+      };
+      let (tree, line, size) = self.proof(child);
+      let coord = self.nodes[child].coord;
+      (vec![ProofNode { coord, children: tree }], prepend(coord, line), size + 1)
+    } else {
+      let mut trees = Vec::with_capacity(node.child_count);
+      let mut line = Vec::new();
+      let mut total = 0;
+      let mut largest = 0;
+      for child in children {
+        let (tree, child_line, size) = self.proof(child);
+        let coord = self.nodes[child].coord;
+        if size + 1 > largest {
+          largest = size + 1;
+          line = prepend(coord, child_line);
+        }
+        total += size + 1;
+        trees.push(ProofNode { coord, children: tree });
+      }
+      (trees, line, total)
+    }
+  }
+}
+
+fn prepend(first: Coord, rest: Vec<Coord>) -> Vec<Coord> {
+  let mut moves = Vec::with_capacity(rest.len() + 1);
+  moves.push(first);
+  moves.extend(rest);
+  moves
+}
@@ -9,9 +9,15 @@ use rand::SeedableRng;
 use rand::rngs::StdRng;
 
 use crate::ai;
-use crate::engine::GameState;
+use crate::archive::GameArchive;
+use crate::engine::{GameState, DEFAULT_BOARD_SIZE};
+use crate::gomocup::EngineProcess;
 use crate::llm;
-use crate::types::{AiConfig, GameMode, GameResult, LlmConfig, Player, Players, ProfileKind, RuleSetKind};
+use crate::tt::TranspositionTable;
+use crate::types::{
+  AiConfig, EngineConfig, EvalWeights, GameMode, GameRecord, GameResult, LlmConfig, Player, Players, ProfileKind, RuleSetKind,
+  Coord, SelfPlayMeta, Termination,
+};
 
 const RATINGS_VERSION: u32 = 1;
 const DEFAULT_PLAYER_RATING: f64 = 1000.0;
@@ -50,6 +56,8 @@ pub struct ProfileRating {
   pub config: Option<AiConfig>,
   #[serde(default)]
   pub llm: Option<LlmConfig>,
+  #[serde(default)]
+  pub engine: Option<EngineConfig>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -72,6 +80,29 @@ pub struct RatingsSnapshot {
   pub match_offset: i32,
 }
 
+/// How a self-play calibration run is played.
+pub struct SelfPlaySettings<'a> {
+  /// Games each pair of profiles plays, colours alternating.
+  pub games_per_pair: u32,
+  /// Games played at once.
+  pub parallelism: usize,
+  /// Built-in levels taking part, `min_level..=max_level` out of 1 to 12.
+  pub min_level: u8,
+  pub max_level: u8,
+  /// Seed of the series; unset picks one at random.
+  pub seed: Option<u64>,
+  /// Where each finished game is written, if the games are kept.
+  pub archive: Option<&'a mut GameArchive>,
+}
+
+/// The user's LLM and engine profiles joining a mixed self-play run.
+pub struct MixedProfiles<'a> {
+  pub llm_ids: &'a [String],
+  /// API keys of the LLM profiles, by profile id.
+  pub llm_keys: &'a std::collections::HashMap<String, String>,
+  pub engine_ids: &'a [String],
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SelfPlayReport {
@@ -79,6 +110,11 @@ pub struct SelfPlayReport {
   pub total_games: u32,
   pub completed_games: u32,
   pub stopped: bool,
+  /// Seed of the series; game `i` was played with `game_seed(seed, i)`.
+  pub seed: u64,
+  /// Directory or JSONL file the games were written to, if they were kept.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub games_path: Option<String>,
 }
 
 impl Default for RatingStore {
@@ -134,6 +170,7 @@ impl RatingStore {
       profile.losses = 0;
       profile.kind = ProfileKind::Heuristic;
       profile.llm = None;
+      profile.engine = None;
     }
     store
   }
@@ -160,6 +197,7 @@ impl RatingStore {
           kind: ProfileKind::Heuristic,
           config: def.config,
           llm: None,
+          engine: None,
         });
       } else {
         merged.push(def);
@@ -363,13 +401,19 @@ pub fn ratings_base_path() -> PathBuf {
 pub fn run_self_play(
   store: &mut RatingStore,
   save_path: &Path,
-  games_per_pair: u32,
-  parallelism: usize,
+  settings: SelfPlaySettings,
   stop_flag: Arc<AtomicBool>,
   mut on_progress: impl FnMut(u32, u32),
-  min_level: u8,
-  max_level: u8,
 ) -> Result<SelfPlayReport, String> {
+  let SelfPlaySettings {
+    games_per_pair,
+    parallelism,
+    min_level,
+    max_level,
+    seed,
+    mut archive,
+  } = settings;
+  let seed = seed.unwrap_or_else(rand::random);
   // Filter profiles by level range
   let filtered_indices: Vec<usize> = store
     .profiles
@@ -393,6 +437,8 @@ pub fn run_self_play(
       total_games: 0,
       completed_games: 0,
       stopped: false,
+      seed,
+      games_path: None,
     });
   }
 
@@ -446,12 +492,19 @@ pub fn run_self_play(
       let (black_idx, white_idx) = if game_idx % 2 == 0 { (a, b) } else { (b, a) };
       let black = config_list[black_idx];
       let white = config_list[white_idx];
-      let result = play_ai_game(black, white).map(|result| JobResult {
+      let game = play_ai_game_on(
+        DEFAULT_BOARD_SIZE,
+        RuleSetKind::Standard,
+        black,
+        white,
+        game_seed(seed, idx as u64),
+      );
+      let _ = tx.send(game.map(|game| JobResult {
         black_idx,
         white_idx,
-        result,
-      });
-      let _ = tx.send(result);
+        index: idx as u64,
+        game,
+      }));
     }));
   }
   drop(tx);
@@ -460,7 +513,28 @@ pub fn run_self_play(
   let mut pending_saves = 0u32;
   for msg in rx {
     let result = msg?;
-    let score_black = score_for_result(result.result, Player::B);
+    if let Some(archive) = archive.as_deref_mut() {
+      let black = &store.profiles[result.black_idx];
+      let white = &store.profiles[result.white_idx];
+      let record = self_play_record(
+        &result.game,
+        SelfPlaySeat {
+          id: &black.id,
+          name: &black.name,
+          config: black.config,
+        },
+        SelfPlaySeat {
+          id: &white.id,
+          name: &white.name,
+          config: white.config,
+        },
+        seed,
+        result.index,
+      );
+      archive.write(&record)?;
+    }
+    let game_result = result.game.result.unwrap_or(GameResult::Draw);
+    let score_black = score_for_result(game_result, Player::B);
     store.update_profile_by_index(result.black_idx, result.white_idx, score_black)?;
     completed += 1;
     pending_saves += 1;
@@ -489,6 +563,8 @@ pub fn run_self_play(
     total_games,
     completed_games: completed,
     stopped,
+    seed,
+    games_path: archive.map(|archive| archive.path().display().to_string()),
   })
 }
 
@@ -496,37 +572,52 @@ pub fn run_self_play(
 enum MixedSide {
   Heuristic(usize),
   Llm(String),
+  Engine(String),
 }
 
 #[derive(Clone, Debug)]
 struct MixedEntry {
   side: MixedSide,
+  id: String,
+  name: String,
   rating: f64,
   games: u32,
   config: Option<AiConfig>,
   llm: Option<LlmConfig>,
+  engine: Option<EngineConfig>,
 }
 
 #[derive(Clone, Debug)]
 struct MixedJobResult {
   black_idx: usize,
   white_idx: usize,
-  result: GameResult,
+  index: u64,
+  game: GameState,
 }
 
 pub fn run_self_play_mixed(
   base: &RatingStore,
   user: &mut RatingStore,
-  llm_keys: &std::collections::HashMap<String, String>,
-  games_per_pair: u32,
-  parallelism: usize,
-  llm_ids: &[String],
+  profiles: MixedProfiles,
+  settings: SelfPlaySettings,
   stop_flag: Arc<AtomicBool>,
   mut on_progress: impl FnMut(u32, u32),
   save_path: &Path,
-  min_level: u8,
-  max_level: u8,
 ) -> Result<SelfPlayReport, String> {
+  let MixedProfiles {
+    llm_ids,
+    llm_keys,
+    engine_ids,
+  } = profiles;
+  let SelfPlaySettings {
+    games_per_pair,
+    parallelism,
+    min_level,
+    max_level,
+    seed,
+    mut archive,
+  } = settings;
+  let seed = seed.unwrap_or_else(rand::random);
   let mut entries = Vec::new();
   for (idx, profile) in base.profiles.iter().enumerate() {
     // Filter heuristic profiles by level range
@@ -544,10 +635,13 @@ pub fn run_self_play_mixed(
     let delta_games = user_profile.map(|p| p.games).unwrap_or(0);
     entries.push(MixedEntry {
       side: MixedSide::Heuristic(idx),
+      id: profile.id.clone(),
+      name: profile.name.clone(),
       rating: profile.rating + delta_rating,
       games: profile.games + delta_games,
       config: profile.config,
       llm: None,
+      engine: None,
     });
   }
 
@@ -564,10 +658,29 @@ pub fn run_self_play_mixed(
     }
     entries.push(MixedEntry {
       side: MixedSide::Llm(profile.id.clone()),
+      id: profile.id.clone(),
+      name: profile.name.clone(),
       rating: profile.rating,
       games: profile.games,
       config: None,
       llm: profile.llm.clone(),
+      engine: None,
+    });
+  }
+
+  for profile in user.extras.iter() {
+    if profile.kind != ProfileKind::Engine || !engine_ids.contains(&profile.id) {
+      continue;
+    }
+    entries.push(MixedEntry {
+      side: MixedSide::Engine(profile.id.clone()),
+      id: profile.id.clone(),
+      name: profile.name.clone(),
+      rating: profile.rating,
+      games: profile.games,
+      config: None,
+      llm: None,
+      engine: profile.engine.clone(),
     });
   }
 
@@ -579,6 +692,8 @@ pub fn run_self_play_mixed(
       total_games: 0,
       completed_games: 0,
       stopped: false,
+      seed,
+      games_path: None,
     });
   }
 
@@ -631,11 +746,13 @@ pub fn run_self_play_mixed(
         white_idx,
         &key_map,
         &fallback_map,
+        game_seed(seed, idx as u64),
       );
-      let _ = tx.send(result.map(|result| MixedJobResult {
+      let _ = tx.send(result.map(|game| MixedJobResult {
         black_idx,
         white_idx,
-        result,
+        index: idx as u64,
+        game,
       }));
     }));
   }
@@ -645,13 +762,33 @@ pub fn run_self_play_mixed(
   let mut pending_saves = 0u32;
   for msg in rx {
     let result = msg?;
+    if let Some(archive) = archive.as_deref_mut() {
+      let black = &entry_list[result.black_idx];
+      let white = &entry_list[result.white_idx];
+      let record = self_play_record(
+        &result.game,
+        SelfPlaySeat {
+          id: &black.id,
+          name: &black.name,
+          config: black.config,
+        },
+        SelfPlaySeat {
+          id: &white.id,
+          name: &white.name,
+          config: white.config,
+        },
+        seed,
+        result.index,
+      );
+      archive.write(&record)?;
+    }
     apply_mixed_result(
       base,
       user,
       entry_list.as_ref(),
       result.black_idx,
       result.white_idx,
-      result.result,
+      result.game.result.unwrap_or(GameResult::Draw),
     )?;
     completed += 1;
     pending_saves += 1;
@@ -680,10 +817,71 @@ pub fn run_self_play_mixed(
     total_games,
     completed_games: completed,
     stopped,
+    seed,
+    games_path: archive.map(|archive| archive.path().display().to_string()),
   })
 }
 
-fn play_ai_game(black: AiConfig, white: AiConfig) -> Result<GameResult, String> {
+/// Seed of game `game` in a series started from `series_seed`, spread out
+/// (SplitMix64) so neighbouring games get unrelated seeds.
+pub fn game_seed(series_seed: u64, game: u64) -> u64 {
+  let mut z = series_seed.wrapping_add(game.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+  z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+  z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+  z ^ (z >> 31)
+}
+
+/// Plays one game between two heuristic configs. Both sides take their
+/// random choices from `seed`, so the same seed replays the same game as
+/// long as the searches themselves are deterministic (one thread, no time limit).
+pub fn play_ai_game(black: AiConfig, white: AiConfig, seed: u64) -> Result<GameResult, String> {
+  let game = play_ai_game_on(DEFAULT_BOARD_SIZE, RuleSetKind::Standard, black, white, seed)?;
+  Ok(game.result.unwrap_or(GameResult::Draw))
+}
+
+/// `play_ai_game` on any board size and rule set, returning the finished
+/// game so its moves can be kept. A game that fills the board is a draw.
+pub fn play_ai_game_on(
+  board_size: usize,
+  rule_set: RuleSetKind,
+  black: AiConfig,
+  white: AiConfig,
+  seed: u64,
+) -> Result<GameState, String> {
+  play_match_game(board_size, rule_set, &[], &MatchPlayer::Ai(black), &MatchPlayer::Ai(white), seed)
+}
+
+/// A side in a headless match: the heuristic AI with a config, or an
+/// external program speaking the Gomocup protocol (another build of this
+/// engine, say).
+#[derive(Clone, Debug)]
+pub enum MatchPlayer {
+  Ai(AiConfig),
+  Engine(EngineConfig),
+}
+
+impl MatchPlayer {
+  /// The search config this side plays a game seeded with `seed` with, as
+  /// saved alongside the game to replay it.
+  pub fn config(&self, seed: u64) -> Option<AiConfig> {
+    match self {
+      MatchPlayer::Ai(config) => Some(match_config(*config, seed)),
+      MatchPlayer::Engine(_) => None,
+    }
+  }
+}
+
+/// Plays a game between two match players after the `opening` moves,
+/// which alternate from Black. An engine that fails during the game loses
+/// it by forfeit; one that cannot be started is an error.
+pub fn play_match_game(
+  board_size: usize,
+  rule_set: RuleSetKind,
+  opening: &[Coord],
+  black: &MatchPlayer,
+  white: &MatchPlayer,
+  seed: u64,
+) -> Result<GameState, String> {
   let players = Players {
     black: "AI".to_string(),
     white: "AI".to_string(),
@@ -692,18 +890,56 @@ fn play_ai_game(black: AiConfig, white: AiConfig) -> Result<GameResult, String>
     black_id: "self_play_black".to_string(),
     white_id: "self_play_white".to_string(),
   };
-  let mut game = GameState::new(15, RuleSetKind::Standard, players, mode);
+  let mut game = GameState::new(board_size, rule_set, players, mode);
+  game.seed = seed;
+  for coord in opening {
+    game.apply_move(coord.x, coord.y)?;
+  }
+  let mut black_tt = None;
+  let mut white_tt = None;
+  let mut black_engine = None;
+  let mut white_engine = None;
 
   while game.result.is_none() {
-    let config = if game.to_move == Player::B { black } else { white };
-    let coord = ai::choose_move(&game.board, RuleSetKind::Standard, game.to_move, config);
+    let (player, tt, engine) = if game.to_move == Player::B {
+      (black, &mut black_tt, &mut black_engine)
+    } else {
+      (white, &mut white_tt, &mut white_engine)
+    };
+    let coord = match player {
+      MatchPlayer::Ai(config) => {
+        let config = match_config(*config, seed);
+        let tt = tt.get_or_insert_with(|| TranspositionTable::new(config.hash_mb));
+        ai::choose_move(&game.board, rule_set, game.to_move, config, tt)
+      }
+      MatchPlayer::Engine(config) => {
+        let engine: &mut EngineProcess = match engine {
+          Some(engine) => engine,
+          None => engine.insert(EngineProcess::start(config, board_size, rule_set)?),
+        };
+        match engine.choose_move(&game.moves, game.to_move, config.timeout_turn_ms) {
+          Ok(coord) => Some(coord),
+          Err(_) => {
+            forfeit(&mut game);
+            break;
+          }
+        }
+      }
+    };
     let Some(coord) = coord else {
+      game.result = Some(GameResult::Draw);
       break;
     };
-    game.apply_move(coord.x, coord.y)?;
+    if let Err(err) = game.apply_move(coord.x, coord.y) {
+      if matches!(player, MatchPlayer::Engine(_)) {
+        forfeit(&mut game);
+        break;
+      }
+      return Err(err);
+    }
   }
 
-  Ok(game.result.unwrap_or(GameResult::Draw))
+  Ok(game)
 }
 
 fn play_mixed_game(
@@ -712,7 +948,8 @@ fn play_mixed_game(
   white_idx: usize,
   llm_keys: &std::collections::HashMap<String, String>,
   fallback_map: &std::collections::HashMap<String, AiConfig>,
-) -> Result<GameResult, String> {
+  seed: u64,
+) -> Result<GameState, String> {
   let players = Players {
     black: "Self-play".to_string(),
     white: "Self-play".to_string(),
@@ -721,16 +958,28 @@ fn play_mixed_game(
     black_id: "self_play_black".to_string(),
     white_id: "self_play_white".to_string(),
   };
-  let mut game = GameState::new(15, RuleSetKind::Standard, players, mode);
+  let mut game = GameState::new(DEFAULT_BOARD_SIZE, RuleSetKind::Standard, players, mode);
+  game.seed = seed;
   let black_entry = entries.get(black_idx).ok_or_else(|| "Invalid black index".to_string())?;
   let white_entry = entries.get(white_idx).ok_or_else(|| "Invalid white index".to_string())?;
+  // Created on first use: an LLM side only needs one if it falls back to the heuristic AI
+  let mut black_tt = None;
+  let mut white_tt = None;
+  // External engines run for the length of the game
+  let mut black_engine = None;
+  let mut white_engine = None;
 
   while game.result.is_none() {
-    let entry = if game.to_move == Player::B { black_entry } else { white_entry };
+    let (entry, tt, engine) = if game.to_move == Player::B {
+      (black_entry, &mut black_tt, &mut black_engine)
+    } else {
+      (white_entry, &mut white_tt, &mut white_engine)
+    };
     let coord = match &entry.side {
       MixedSide::Heuristic(_) => {
-        let config = entry.config.ok_or_else(|| "Missing AI config".to_string())?;
-        ai::choose_move(&game.board, RuleSetKind::Standard, game.to_move, config)
+        let config = match_config(entry.config.ok_or_else(|| "Missing AI config".to_string())?, seed);
+        let tt = tt.get_or_insert_with(|| TranspositionTable::new(config.hash_mb));
+        ai::choose_move(&game.board, RuleSetKind::Standard, game.to_move, config, tt)
       }
       MixedSide::Llm(id) => {
         if let Some(tactical) = ai::tactical_move(&game.board, RuleSetKind::Standard, game.to_move) {
@@ -740,11 +989,28 @@ fn play_mixed_game(
             .get(id)
             .ok_or_else(|| "Missing API key for LLM profile".to_string())?;
           let config = entry.llm.clone().ok_or_else(|| "Missing LLM config".to_string())?;
-          match llm::choose_move(&game.board, game.to_move, &config, api_key, &game.moves) {
+          match llm::choose_move(&game.board, RuleSetKind::Standard, game.to_move, &config, api_key, &game.moves) {
             Ok(coord) => Some(coord),
-            Err(_) => fallback_map
-              .get(id)
-              .and_then(|fallback| ai::choose_move(&game.board, RuleSetKind::Standard, game.to_move, *fallback)),
+            Err(_) => fallback_map.get(id).and_then(|fallback| {
+              let fallback = match_config(*fallback, seed);
+              let tt = tt.get_or_insert_with(|| TranspositionTable::new(fallback.hash_mb));
+              ai::choose_move(&game.board, RuleSetKind::Standard, game.to_move, fallback, tt)
+            }),
+          }
+        }
+      }
+      MixedSide::Engine(_) => {
+        let config = entry.engine.as_ref().ok_or_else(|| "Missing engine config".to_string())?;
+        let engine: &mut EngineProcess = match engine {
+          Some(engine) => engine,
+          None => engine.insert(EngineProcess::start(config, DEFAULT_BOARD_SIZE, RuleSetKind::Standard)?),
+        };
+        match engine.choose_move(&game.moves, game.to_move, config.timeout_turn_ms) {
+          Ok(coord) => Some(coord),
+          // Crashing, timing out or answering nonsense loses the game, as in Gomocup
+          Err(_) => {
+            forfeit(&mut game);
+            break;
           }
         }
       }
@@ -754,11 +1020,70 @@ fn play_mixed_game(
       break;
     };
     if let Err(_) = game.apply_move(coord.x, coord.y) {
+      if matches!(entry.side, MixedSide::Engine(_)) {
+        forfeit(&mut game);
+      }
       break;
     }
   }
 
-  Ok(game.result.unwrap_or(GameResult::Draw))
+  if game.result.is_none() {
+    game.result = Some(GameResult::Draw);
+  }
+  Ok(game)
+}
+
+// A profile's config as it plays in self-play and tournament games. Those run
+// several games at once, so each search keeps to a single thread, and the game
+// must replay move for move from its seed, so no wall-clock limit applies:
+// strength comes from the depth and node limits alone.
+fn match_config(config: AiConfig, seed: u64) -> AiConfig {
+  AiConfig {
+    seed: Some(seed),
+    threads: 1,
+    move_time_ms: 0,
+    ..config
+  }
+}
+
+// Ends the game as lost for the player to move
+fn forfeit(game: &mut GameState) {
+  game.result = Some(match game.to_move {
+    Player::B => GameResult::WWin,
+    Player::W => GameResult::BWin,
+  });
+  game.termination = Some(Termination::Forfeit);
+}
+
+// One side of a self-play game as it is recorded
+struct SelfPlaySeat<'a> {
+  id: &'a str,
+  name: &'a str,
+  config: Option<AiConfig>,
+}
+
+// Saved-game form of a self-play game, naming the profiles that played it
+fn self_play_record(
+  game: &GameState,
+  black: SelfPlaySeat,
+  white: SelfPlaySeat,
+  series_seed: u64,
+  index: u64,
+) -> GameRecord {
+  let mut record = game.to_record();
+  record.players = Players {
+    black: black.name.to_string(),
+    white: white.name.to_string(),
+  };
+  record.meta.self_play = Some(SelfPlayMeta {
+    black_id: black.id.to_string(),
+    white_id: white.id.to_string(),
+    black_config: black.config.map(|config| match_config(config, game.seed)),
+    white_config: white.config.map(|config| match_config(config, game.seed)),
+    series_seed,
+    game: index,
+  });
+  record
 }
 
 fn expected_score(rating_a: f64, rating_b: f64) -> f64 {
@@ -779,7 +1104,7 @@ fn k_factor(games: u32) -> f64 {
   }
 }
 
-fn score_for_result(result: GameResult, player: Player) -> f64 {
+pub fn score_for_result(result: GameResult, player: Player) -> f64 {
   match (result, player) {
     (GameResult::BWin, Player::B) => 1.0,
     (GameResult::WWin, Player::W) => 1.0,
@@ -877,23 +1202,29 @@ fn apply_mixed_result(
     (MixedSide::Heuristic(idx_a), MixedSide::Heuristic(idx_b)) => {
       update_user_profiles_with_base(base, user, *idx_a, *idx_b, new_black, new_white, result)?;
     }
-    (MixedSide::Heuristic(idx), MixedSide::Llm(id)) => {
-      update_user_profile_with_base(base, user, *idx, new_black, result, Player::B)?;
-      update_llm_profile(user, id, new_white, result, Player::W)?;
-    }
-    (MixedSide::Llm(id), MixedSide::Heuristic(idx)) => {
-      update_llm_profile(user, id, new_black, result, Player::B)?;
-      update_user_profile_with_base(base, user, *idx, new_white, result, Player::W)?;
-    }
-    (MixedSide::Llm(id_a), MixedSide::Llm(id_b)) => {
-      update_llm_profile(user, id_a, new_black, result, Player::B)?;
-      update_llm_profile(user, id_b, new_white, result, Player::W)?;
+    (black_side, white_side) => {
+      update_side_profile(base, user, black_side, new_black, result, Player::B)?;
+      update_side_profile(base, user, white_side, new_white, result, Player::W)?;
     }
   }
 
   Ok(())
 }
 
+fn update_side_profile(
+  base: &RatingStore,
+  user: &mut RatingStore,
+  side: &MixedSide,
+  new_rating: f64,
+  result: GameResult,
+  player: Player,
+) -> Result<(), String> {
+  match side {
+    MixedSide::Heuristic(idx) => update_user_profile_with_base(base, user, *idx, new_rating, result, player),
+    MixedSide::Llm(id) | MixedSide::Engine(id) => update_extra_profile(user, id, new_rating, result, player),
+  }
+}
+
 fn effective_for_side(
   base: &RatingStore,
   user: &RatingStore,
@@ -908,12 +1239,12 @@ fn effective_for_side(
         base_profile.games + user_profile.games,
       ))
     }
-    MixedSide::Llm(id) => {
+    MixedSide::Llm(id) | MixedSide::Engine(id) => {
       let profile = user
         .extras
         .iter()
         .find(|p| p.id == *id)
-        .ok_or_else(|| "LLM profile missing".to_string())?;
+        .ok_or_else(|| "Profile missing".to_string())?;
       Ok((profile.rating, profile.games))
     }
   }
@@ -973,7 +1304,8 @@ fn update_user_profile_with_base(
   Ok(())
 }
 
-fn update_llm_profile(
+// Rating update for an LLM or engine profile, which have no base rating
+fn update_extra_profile(
   user: &mut RatingStore,
   id: &str,
   new_rating: f64,
@@ -984,7 +1316,7 @@ fn update_llm_profile(
     .extras
     .iter_mut()
     .find(|p| p.id == id)
-    .ok_or_else(|| "LLM profile missing".to_string())?;
+    .ok_or_else(|| "Profile missing".to_string())?;
   profile.rating = new_rating;
   profile.games += 1;
   apply_result_to_profile(profile, result, player);
@@ -995,7 +1327,8 @@ fn update_llm_profile(
 struct JobResult {
   black_idx: usize,
   white_idx: usize,
-  result: GameResult,
+  index: u64,
+  game: GameState,
 }
 
 fn build_pairs(count: usize) -> Vec<(usize, usize)> {
@@ -1025,8 +1358,18 @@ fn default_profiles() -> Vec<ProfileRating> {
         randomness: 5,
         max_nodes: 800,
         defense_weight: 9,
+        threat_nodes: 0,
+        quiescence_nodes: 0,
+        move_time_ms: 0,
+        hash_mb: 1,
+        threads: 1,
+        weights: EvalWeights::default(),
+        use_book: false,
+        book_variety: 0,
+        seed: None,
       }),
       llm: None,
+      engine: None,
     },
     ProfileRating {
       id: "l02".to_string(),
@@ -1043,8 +1386,18 @@ fn default_profiles() -> Vec<ProfileRating> {
         randomness: 4,
         max_nodes: 1500,
         defense_weight: 10,
+        threat_nodes: 0,
+        quiescence_nodes: 0,
+        move_time_ms: 0,
+        hash_mb: 1,
+        threads: 1,
+        weights: EvalWeights::default(),
+        use_book: false,
+        book_variety: 0,
+        seed: None,
       }),
       llm: None,
+      engine: None,
     },
     ProfileRating {
       id: "l03".to_string(),
@@ -1061,8 +1414,18 @@ fn default_profiles() -> Vec<ProfileRating> {
         randomness: 3,
         max_nodes: 2500,
         defense_weight: 11,
+        threat_nodes: 0,
+        quiescence_nodes: 0,
+        move_time_ms: 0,
+        hash_mb: 1,
+        threads: 1,
+        weights: EvalWeights::default(),
+        use_book: false,
+        book_variety: 0,
+        seed: None,
       }),
       llm: None,
+      engine: None,
     },
     ProfileRating {
       id: "l04".to_string(),
@@ -1079,8 +1442,18 @@ fn default_profiles() -> Vec<ProfileRating> {
         randomness: 2,
         max_nodes: 4000,
         defense_weight: 11,
+        threat_nodes: 0,
+        quiescence_nodes: 0,
+        move_time_ms: 0,
+        hash_mb: 1,
+        threads: 1,
+        weights: EvalWeights::default(),
+        use_book: false,
+        book_variety: 0,
+        seed: None,
       }),
       llm: None,
+      engine: None,
     },
     ProfileRating {
       id: "l05".to_string(),
@@ -1097,8 +1470,18 @@ fn default_profiles() -> Vec<ProfileRating> {
         randomness: 2,
         max_nodes: 6500,
         defense_weight: 12,
+        threat_nodes: 500,
+        quiescence_nodes: 0,
+        move_time_ms: 0,
+        hash_mb: 4,
+        threads: 1,
+        weights: EvalWeights::default(),
+        use_book: false,
+        book_variety: 0,
+        seed: None,
       }),
       llm: None,
+      engine: None,
     },
     ProfileRating {
       id: "l06".to_string(),
@@ -1115,8 +1498,18 @@ fn default_profiles() -> Vec<ProfileRating> {
         randomness: 1,
         max_nodes: 9000,
         defense_weight: 12,
+        threat_nodes: 1000,
+        quiescence_nodes: 0,
+        move_time_ms: 0,
+        hash_mb: 4,
+        threads: 1,
+        weights: EvalWeights::default(),
+        use_book: false,
+        book_variety: 0,
+        seed: None,
       }),
       llm: None,
+      engine: None,
     },
     ProfileRating {
       id: "l07".to_string(),
@@ -1133,8 +1526,18 @@ fn default_profiles() -> Vec<ProfileRating> {
         randomness: 1,
         max_nodes: 12000,
         defense_weight: 12,
+        threat_nodes: 2000,
+        quiescence_nodes: 0,
+        move_time_ms: 0,
+        hash_mb: 8,
+        threads: 1,
+        weights: EvalWeights::default(),
+        use_book: false,
+        book_variety: 0,
+        seed: None,
       }),
       llm: None,
+      engine: None,
     },
     ProfileRating {
       id: "l08".to_string(),
@@ -1151,8 +1554,18 @@ fn default_profiles() -> Vec<ProfileRating> {
         randomness: 1,
         max_nodes: 18000,
         defense_weight: 13,
+        threat_nodes: 3000,
+        quiescence_nodes: 0,
+        move_time_ms: 0,
+        hash_mb: 8,
+        threads: 1,
+        weights: EvalWeights::default(),
+        use_book: false,
+        book_variety: 0,
+        seed: None,
       }),
       llm: None,
+      engine: None,
     },
     ProfileRating {
       id: "l09".to_string(),
@@ -1169,8 +1582,18 @@ fn default_profiles() -> Vec<ProfileRating> {
         randomness: 0,
         max_nodes: 26000,
         defense_weight: 13,
+        threat_nodes: 4000,
+        quiescence_nodes: 0,
+        move_time_ms: 0,
+        hash_mb: 16,
+        threads: 1,
+        weights: EvalWeights::default(),
+        use_book: false,
+        book_variety: 0,
+        seed: None,
       }),
       llm: None,
+      engine: None,
     },
     ProfileRating {
       id: "l10".to_string(),
@@ -1187,8 +1610,18 @@ fn default_profiles() -> Vec<ProfileRating> {
         randomness: 0,
         max_nodes: 35000,
         defense_weight: 13,
+        threat_nodes: 6000,
+        quiescence_nodes: 0,
+        move_time_ms: 0,
+        hash_mb: 16,
+        threads: 1,
+        weights: EvalWeights::default(),
+        use_book: false,
+        book_variety: 0,
+        seed: None,
       }),
       llm: None,
+      engine: None,
     },
     ProfileRating {
       id: "l11".to_string(),
@@ -1205,8 +1638,18 @@ fn default_profiles() -> Vec<ProfileRating> {
         randomness: 0,
         max_nodes: 45000,
         defense_weight: 14,
+        threat_nodes: 8000,
+        quiescence_nodes: 0,
+        move_time_ms: 0,
+        hash_mb: 32,
+        threads: 1,
+        weights: EvalWeights::default(),
+        use_book: false,
+        book_variety: 0,
+        seed: None,
       }),
       llm: None,
+      engine: None,
     },
     ProfileRating {
       id: "l12".to_string(),
@@ -1223,8 +1666,67 @@ fn default_profiles() -> Vec<ProfileRating> {
         randomness: 0,
         max_nodes: 60000,
         defense_weight: 14,
+        threat_nodes: 10000,
+        quiescence_nodes: 0,
+        move_time_ms: 0,
+        hash_mb: 32,
+        threads: 1,
+        weights: EvalWeights::default(),
+        use_book: false,
+        book_variety: 0,
+        seed: None,
       }),
       llm: None,
+      engine: None,
     },
   ]
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn ladder_config(id: &str) -> AiConfig {
+    default_profiles()
+      .into_iter()
+      .find(|profile| profile.id == id)
+      .and_then(|profile| profile.config)
+      .expect("ladder level")
+  }
+
+  fn moves(game: &GameState) -> Vec<(usize, usize, Player)> {
+    game.moves.iter().map(|mv| (mv.x, mv.y, mv.player)).collect()
+  }
+
+  #[test]
+  fn seeded_games_replay_move_for_move() {
+    // Levels with randomness, so the seed decides between moves
+    let (black, white) = (ladder_config("l02"), ladder_config("l06"));
+    let first = play_ai_game_on(DEFAULT_BOARD_SIZE, RuleSetKind::Standard, black, white, 42).unwrap();
+    let second = play_ai_game_on(DEFAULT_BOARD_SIZE, RuleSetKind::Standard, black, white, 42).unwrap();
+    assert_eq!(moves(&first), moves(&second));
+    assert_eq!(first.result, second.result);
+  }
+
+  #[test]
+  fn seeded_games_ignore_threads_and_move_time() {
+    let black = AiConfig {
+      threads: 0,
+      move_time_ms: 1,
+      ..ladder_config("l03")
+    };
+    let white = ladder_config("l04");
+    let timed = play_ai_game_on(DEFAULT_BOARD_SIZE, RuleSetKind::Standard, black, white, 7).unwrap();
+    let plain = play_ai_game_on(DEFAULT_BOARD_SIZE, RuleSetKind::Standard, ladder_config("l03"), white, 7).unwrap();
+    assert_eq!(moves(&timed), moves(&plain));
+  }
+
+  #[test]
+  fn ladder_levels_leave_the_book_off() {
+    // Rated games never consult the book, so a level that did would play differently in the app
+    for profile in default_profiles() {
+      let config = profile.config.expect("ladder level");
+      assert!(!config.use_book && config.book_variety == 0, "{}", profile.id);
+    }
+  }
+}
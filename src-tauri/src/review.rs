@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+
+use crate::ai;
+use crate::engine::{Board, GameState};
+use crate::tt::TranspositionTable;
+use crate::types::{AiConfig, Coord, GameRecord, GameResult, OpeningRule, Player, RuleSetKind};
+
+// Node budget per reviewed position, so reviews take the same effort on any profile
+pub const REVIEW_NODE_BUDGET: u32 = 20_000;
+
+// Upper bounds of the score drop (in evaluation points) for each label
+const GOOD_MAX_DROP: i32 = 500;
+const INACCURACY_MAX_DROP: i32 = 2_000;
+const MISTAKE_MAX_DROP: i32 = 8_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MoveLabel {
+  Best,
+  Good,
+  Inaccuracy,
+  Mistake,
+  Blunder,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveReview {
+  pub ply: usize,
+  pub player: Player,
+  pub played: Coord,
+  pub best: Coord,
+  // Both scores are from the mover's point of view
+  pub played_score: i32,
+  pub best_score: i32,
+  pub score_drop: i32,
+  pub label: MoveLabel,
+  pub missed_win: bool,
+  pub best_line: Vec<Coord>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewSummary {
+  pub best: u32,
+  pub good: u32,
+  pub inaccuracies: u32,
+  pub mistakes: u32,
+  pub blunders: u32,
+  pub missed_wins: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameReview {
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub game_id: Option<String>,
+  pub board_size: usize,
+  pub rule_set: RuleSetKind,
+  pub result: Option<GameResult>,
+  pub node_budget: u32,
+  pub moves: Vec<MoveReview>,
+  pub black: ReviewSummary,
+  pub white: ReviewSummary,
+}
+
+impl ReviewSummary {
+  fn add(&mut self, review: &MoveReview) {
+    match review.label {
+      MoveLabel::Best => self.best += 1,
+      MoveLabel::Good => self.good += 1,
+      MoveLabel::Inaccuracy => self.inaccuracies += 1,
+      MoveLabel::Mistake => self.mistakes += 1,
+      MoveLabel::Blunder => self.blunders += 1,
+    }
+    if review.missed_win {
+      self.missed_wins += 1;
+    }
+  }
+}
+
+/// Replays the record and grades every move against the engine's choice.
+/// Stones placed during a Swap/Swap2 opening are not graded, since the colour
+/// choice that followed them was the real decision. `on_progress` is called
+/// with (reviewed, total) after each position.
+pub fn review_game<F>(record: &GameRecord, config: AiConfig, mut on_progress: F) -> Result<GameReview, String>
+where
+  F: FnMut(usize, usize),
+{
+  // Rejects records whose moves are not a legal game under their rule set
+  GameState::from_record(record.clone())?;
+
+  let config = AiConfig {
+    randomness: 0,
+    max_nodes: REVIEW_NODE_BUDGET,
+    ..config
+  };
+  let first_ply = match &record.opening {
+    Some(opening) if opening.rule != OpeningRule::Free => {
+      opening.decisions.last().map(|d| d.ply).unwrap_or(0)
+    }
+    _ => 0,
+  };
+
+  let mut board = Board::new(record.board_size);
+  let mut moves = Vec::new();
+  let mut black = ReviewSummary::default();
+  let mut white = ReviewSummary::default();
+  let total = record.moves.len().saturating_sub(first_ply);
+  // Positions of one game share a lot, so the table is kept for the whole review
+  let tt = TranspositionTable::new(config.hash_mb);
+
+  for (ply, mv) in record.moves.iter().enumerate() {
+    if ply >= first_ply {
+      let played = Coord { x: mv.x, y: mv.y };
+      let review = review_move(&board, record.rule_set, mv.player, config, ply, played, &tt)?;
+      match mv.player {
+        Player::B => black.add(&review),
+        Player::W => white.add(&review),
+      }
+      moves.push(review);
+      on_progress(ply + 1 - first_ply, total);
+    }
+    board.set(mv.x, mv.y, mv.player);
+  }
+
+  Ok(GameReview {
+    game_id: record.meta.game_id.clone(),
+    board_size: record.board_size,
+    rule_set: record.rule_set,
+    result: record.result,
+    node_budget: REVIEW_NODE_BUDGET,
+    moves,
+    black,
+    white,
+  })
+}
+
+fn review_move(
+  board: &Board,
+  rule_set: RuleSetKind,
+  player: Player,
+  config: AiConfig,
+  ply: usize,
+  played: Coord,
+  tt: &TranspositionTable,
+) -> Result<MoveReview, String> {
+  let lines = ai::analyze_with_move(board, rule_set, player, config, played, tt);
+  let best = lines
+    .first()
+    .ok_or_else(|| format!("No legal moves at ply {}", ply + 1))?;
+  let played_score = lines
+    .iter()
+    .find(|line| line.coord == played)
+    .map(|line| line.score)
+    .ok_or_else(|| format!("Move at ply {} is not legal", ply + 1))?;
+
+  // Once the game is lost by force every reply is as bad as any other
+  let score_drop = if ai::is_forced_loss(best.score) {
+    0
+  } else {
+    (best.score - played_score).max(0)
+  };
+  let missed_win = ai::is_forced_win(best.score) && !ai::is_forced_win(played_score);
+  let threw_game = !ai::is_forced_loss(best.score) && ai::is_forced_loss(played_score);
+  let label = if played == best.coord || score_drop == 0 {
+    MoveLabel::Best
+  } else if missed_win || threw_game {
+    MoveLabel::Blunder
+  } else if score_drop <= GOOD_MAX_DROP {
+    MoveLabel::Good
+  } else if score_drop <= INACCURACY_MAX_DROP {
+    MoveLabel::Inaccuracy
+  } else if score_drop <= MISTAKE_MAX_DROP {
+    MoveLabel::Mistake
+  } else {
+    MoveLabel::Blunder
+  };
+
+  Ok(MoveReview {
+    ply,
+    player,
+    played,
+    best: best.coord,
+    played_score,
+    best_score: best.score,
+    score_drop,
+    label,
+    missed_win,
+    best_line: best.pv.clone(),
+  })
+}
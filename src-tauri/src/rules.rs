@@ -1,9 +1,25 @@
 use crate::engine::Board;
-use crate::types::{GameResult, Move, Player, RuleSetKind};
+use crate::types::{Coord, GameResult, Move, Player, RuleSetKind};
+
+const DIRECTIONS: [(i32, i32); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+// Upper bound on the "is this three real?" recursion. Each level adds a stone,
+// so in practice the chain resolves long before this.
+const RENJU_MAX_DEPTH: u8 = 6;
 
 pub trait RuleSet {
   fn is_legal(&self, board: &Board, mv: &Move) -> bool;
   fn check_win(&self, board: &Board, mv: &Move) -> Option<GameResult>;
+
+  /// Whether six or more in a row counts as a win for the given player.
+  fn overline_wins(&self, _player: Player) -> bool {
+    true
+  }
+
+  /// Empty points the given player may not play on. Only rule sets with
+  /// forbidden shapes (Renju) return anything here.
+  fn forbidden_points(&self, _board: &Board, _player: Player) -> Vec<Coord> {
+    Vec::new()
+  }
 }
 
 pub struct StandardRuleSet;
@@ -14,29 +30,93 @@ impl RuleSet for StandardRuleSet {
   }
 
   fn check_win(&self, board: &Board, mv: &Move) -> Option<GameResult> {
-    let player = mv.player;
-    let directions = [(1, 0), (0, 1), (1, 1), (1, -1)];
+    five_in_row(board, mv, true)
+  }
+}
 
-    for (dx, dy) in directions {
-      let mut count = 1;
-      count += count_dir(board, mv.x, mv.y, dx, dy, player);
-      count += count_dir(board, mv.x, mv.y, -dx, -dy, player);
+/// Standard gomoku: only an exact five wins and overlines do not count.
+/// With `black_only`, the restriction applies to Black alone and White still
+/// wins with six or more.
+pub struct ExactFiveRuleSet {
+  pub black_only: bool,
+}
+
+impl RuleSet for ExactFiveRuleSet {
+  fn is_legal(&self, board: &Board, mv: &Move) -> bool {
+    board.in_bounds(mv.x, mv.y) && board.is_empty(mv.x, mv.y)
+  }
 
-      if count >= 5 {
-        return Some(match player {
-          Player::B => GameResult::BWin,
-          Player::W => GameResult::WWin,
-        });
-      }
+  fn check_win(&self, board: &Board, mv: &Move) -> Option<GameResult> {
+    five_in_row(board, mv, self.overline_wins(mv.player))
+  }
+
+  fn overline_wins(&self, player: Player) -> bool {
+    self.black_only && player == Player::W
+  }
+}
+
+/// Renju: Black may not make a double-three, a double-four or an overline,
+/// and only an exact five wins for Black. White plays as in freestyle.
+/// A five always takes precedence over a forbidden shape made by the same move.
+pub struct RenjuRuleSet;
+
+impl RuleSet for RenjuRuleSet {
+  fn is_legal(&self, board: &Board, mv: &Move) -> bool {
+    if !board.in_bounds(mv.x, mv.y) || !board.is_empty(mv.x, mv.y) {
+      return false;
+    }
+    if mv.player == Player::W {
+      return true;
     }
+    let mut work = board.clone();
+    !is_forbidden(&mut work, mv.x, mv.y, 0)
+  }
 
-    None
+  fn check_win(&self, board: &Board, mv: &Move) -> Option<GameResult> {
+    five_in_row(board, mv, self.overline_wins(mv.player))
+  }
+
+  fn overline_wins(&self, player: Player) -> bool {
+    player == Player::W
+  }
+
+  fn forbidden_points(&self, board: &Board, player: Player) -> Vec<Coord> {
+    if player != Player::B {
+      return Vec::new();
+    }
+    let mut work = board.clone();
+    board
+      .empty_coords()
+      .into_iter()
+      .filter(|c| has_black_neighbour(board, c.x, c.y) && is_forbidden(&mut work, c.x, c.y, 0))
+      .collect()
   }
 }
 
 pub fn rules_for(kind: RuleSetKind) -> Box<dyn RuleSet> {
   match kind {
     RuleSetKind::Standard => Box::new(StandardRuleSet),
+    RuleSetKind::Renju => Box::new(RenjuRuleSet),
+    RuleSetKind::ExactFive => Box::new(ExactFiveRuleSet { black_only: false }),
+    RuleSetKind::ExactFiveBlack => Box::new(ExactFiveRuleSet { black_only: true }),
+  }
+}
+
+fn five_in_row(board: &Board, mv: &Move, overline_wins: bool) -> Option<GameResult> {
+  for (dx, dy) in DIRECTIONS {
+    let count = line_length(board, mv.x, mv.y, dx, dy, mv.player);
+    if count == 5 || (overline_wins && count > 5) {
+      return Some(win_for(mv.player));
+    }
+  }
+
+  None
+}
+
+fn win_for(player: Player) -> GameResult {
+  match player {
+    Player::B => GameResult::BWin,
+    Player::W => GameResult::WWin,
   }
 }
 
@@ -61,3 +141,231 @@ fn count_dir(board: &Board, x: usize, y: usize, dx: i32, dy: i32, player: Player
 
   count
 }
+
+/// Length of the unbroken run through (x, y), counting (x, y) itself.
+fn line_length(board: &Board, x: usize, y: usize, dx: i32, dy: i32, player: Player) -> usize {
+  1 + count_dir(board, x, y, dx, dy, player) + count_dir(board, x, y, -dx, -dy, player)
+}
+
+fn offset(board: &Board, x: usize, y: usize, dx: i32, dy: i32, k: i32) -> Option<(usize, usize)> {
+  let nx = x as i32 + dx * k;
+  let ny = y as i32 + dy * k;
+  if nx < 0 || ny < 0 {
+    return None;
+  }
+  let (ux, uy) = (nx as usize, ny as usize);
+  if board.in_bounds(ux, uy) {
+    Some((ux, uy))
+  } else {
+    None
+  }
+}
+
+// A point with no black stone within reach on any line cannot be forbidden.
+fn has_black_neighbour(board: &Board, x: usize, y: usize) -> bool {
+  DIRECTIONS.iter().any(|&(dx, dy)| {
+    (-4..=4).any(|k| {
+      k != 0
+        && offset(board, x, y, dx, dy, k)
+          .map(|(nx, ny)| board.get(nx, ny) == Some(Player::B))
+          .unwrap_or(false)
+    })
+  })
+}
+
+/// Whether Black playing the empty point (x, y) would be forbidden.
+/// The board is used as scratch space and is restored before returning.
+fn is_forbidden(board: &mut Board, x: usize, y: usize, depth: u8) -> bool {
+  board.set(x, y, Player::B);
+  let forbidden = forbidden_after_placing(board, x, y, depth);
+  board.clear(x, y);
+  forbidden
+}
+
+fn forbidden_after_placing(board: &mut Board, x: usize, y: usize, depth: u8) -> bool {
+  // An exact five wins outright, whatever else the move creates.
+  if DIRECTIONS
+    .iter()
+    .any(|&(dx, dy)| line_length(board, x, y, dx, dy, Player::B) == 5)
+  {
+    return false;
+  }
+
+  let mut fours = 0;
+  let mut threes = 0;
+  for (dx, dy) in DIRECTIONS {
+    if line_length(board, x, y, dx, dy, Player::B) > 5 {
+      return true;
+    }
+    let line_fours = fours_in_direction(board, x, y, dx, dy);
+    if line_fours > 0 {
+      fours += line_fours;
+    } else if depth < RENJU_MAX_DEPTH && is_real_three(board, x, y, dx, dy, depth) {
+      threes += 1;
+    }
+  }
+
+  fours >= 2 || threes >= 2
+}
+
+/// Offsets along (dx, dy) of the empty points that would complete an exact
+/// five through the black stone at (x, y).
+fn five_points(board: &mut Board, x: usize, y: usize, dx: i32, dy: i32) -> Vec<i32> {
+  let mut points = Vec::new();
+  for k in -4..=4 {
+    if k == 0 {
+      continue;
+    }
+    let Some((qx, qy)) = offset(board, x, y, dx, dy, k) else {
+      continue;
+    };
+    if !board.is_empty(qx, qy) {
+      continue;
+    }
+    board.set(qx, qy, Player::B);
+    let back = count_dir(board, qx, qy, -dx, -dy, Player::B) as i32;
+    let forward = count_dir(board, qx, qy, dx, dy, Player::B) as i32;
+    // The five must run through (x, y), i.e. cover offset 0.
+    if back + forward + 1 == 5 && k - back <= 0 && 0 <= k + forward {
+      points.push(k);
+    }
+    board.clear(qx, qy);
+  }
+  points
+}
+
+/// Number of distinct fours through (x, y) in one direction. A straight four
+/// (two completion points five apart) counts once; split shapes such as
+/// `B.BBB.B` count twice.
+fn fours_in_direction(board: &mut Board, x: usize, y: usize, dx: i32, dy: i32) -> usize {
+  let points = five_points(board, x, y, dx, dy);
+  if is_straight(&points) {
+    points.len() - 1
+  } else {
+    points.len()
+  }
+}
+
+fn is_straight(points: &[i32]) -> bool {
+  points.iter().any(|a| points.iter().any(|b| b - a == 5))
+}
+
+/// A three is real only if it can become a straight four by a move that is
+/// not itself forbidden.
+fn is_real_three(board: &mut Board, x: usize, y: usize, dx: i32, dy: i32, depth: u8) -> bool {
+  for k in -4..=4 {
+    if k == 0 {
+      continue;
+    }
+    let Some((qx, qy)) = offset(board, x, y, dx, dy, k) else {
+      continue;
+    };
+    if !board.is_empty(qx, qy) {
+      continue;
+    }
+    board.set(qx, qy, Player::B);
+    let makes_straight_four = is_straight(&five_points(board, x, y, dx, dy));
+    board.clear(qx, qy);
+    if makes_straight_four && !is_forbidden(board, qx, qy, depth + 1) {
+      return true;
+    }
+  }
+  false
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn board(black: &[(usize, usize)], white: &[(usize, usize)]) -> Board {
+    let mut board = Board::new(15);
+    for &(x, y) in black {
+      board.set(x, y, Player::B);
+    }
+    for &(x, y) in white {
+      board.set(x, y, Player::W);
+    }
+    board
+  }
+
+  fn black(x: usize, y: usize) -> Move {
+    Move {
+      x,
+      y,
+      player: Player::B,
+      t: None,
+    }
+  }
+
+  #[test]
+  fn double_three_is_forbidden() {
+    let board = board(&[(5, 7), (6, 7), (7, 5), (7, 6)], &[]);
+    assert!(!RenjuRuleSet.is_legal(&board, &black(7, 7)));
+    assert!(StandardRuleSet.is_legal(&board, &black(7, 7)));
+  }
+
+  #[test]
+  fn dead_three_does_not_count() {
+    // White closes one end of the horizontal three, so it can never become a straight four
+    let board = board(&[(5, 7), (6, 7), (7, 5), (7, 6)], &[(4, 7)]);
+    assert!(RenjuRuleSet.is_legal(&board, &black(7, 7)));
+  }
+
+  #[test]
+  fn overline_is_forbidden_for_black_only() {
+    let board = board(&[(3, 7), (4, 7), (5, 7), (6, 7), (8, 7)], &[]);
+    assert!(!RenjuRuleSet.is_legal(&board, &black(7, 7)));
+
+    let white = Move {
+      player: Player::W,
+      ..black(7, 7)
+    };
+    let mut overline = Board::new(15);
+    for x in [3, 4, 5, 6, 7, 8] {
+      overline.set(x, 7, Player::W);
+    }
+    assert!(RenjuRuleSet.is_legal(&board, &white));
+    assert_eq!(RenjuRuleSet.check_win(&overline, &white), Some(GameResult::WWin));
+  }
+
+  #[test]
+  fn four_four_on_one_line_is_forbidden() {
+    // B.BBB.B: both gaps complete a five
+    let board = board(&[(3, 7), (5, 7), (6, 7), (9, 7)], &[]);
+    assert!(!RenjuRuleSet.is_legal(&board, &black(7, 7)));
+  }
+
+  #[test]
+  fn four_three_is_allowed() {
+    let board = board(&[(4, 7), (5, 7), (6, 7), (7, 5), (7, 6)], &[]);
+    assert!(RenjuRuleSet.is_legal(&board, &black(7, 7)));
+  }
+
+  #[test]
+  fn five_beats_a_forbidden_shape() {
+    // The move also makes a double three, but the exact five wins first
+    let mut board = board(&[(3, 7), (4, 7), (5, 7), (6, 7), (7, 5), (7, 6), (5, 5), (6, 6)], &[]);
+    assert!(RenjuRuleSet.is_legal(&board, &black(7, 7)));
+    board.set(7, 7, Player::B);
+    assert_eq!(RenjuRuleSet.check_win(&board, &black(7, 7)), Some(GameResult::BWin));
+  }
+
+  #[test]
+  fn three_whose_four_point_is_forbidden_is_fake() {
+    // Without support, (7, 7) makes a horizontal and a vertical three
+    let threes = [(8, 7), (9, 7), (7, 5), (7, 6)];
+    assert!(!RenjuRuleSet.is_legal(&board(&threes, &[]), &black(7, 7)));
+
+    // Columns 6 and 10 turn both straight-four points of the horizontal three
+    // into overlines, so only the vertical three is real
+    let mut stones = threes.to_vec();
+    for x in [6, 10] {
+      stones.extend([(x, 4), (x, 5), (x, 6), (x, 8), (x, 9)]);
+    }
+    let mut board = board(&stones, &[]);
+    assert!(RenjuRuleSet.is_legal(&board, &black(7, 7)));
+    board.set(7, 7, Player::B);
+    assert!(!RenjuRuleSet.is_legal(&board, &black(6, 7)));
+    assert!(!RenjuRuleSet.is_legal(&board, &black(10, 7)));
+  }
+}
@@ -0,0 +1,334 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::engine::{check_board_size, Board};
+use crate::rating::{game_seed, play_match_game, score_for_result, MatchPlayer};
+use crate::rules::rules_for;
+use crate::tournament::{default_board_size, default_parallelism, default_rule_set};
+use crate::tune::{elo_estimate, MatchScore};
+use crate::types::{Coord, GameRecord, GameResult, Move, Player, Players, RuleSetKind, SelfPlayMeta};
+
+// Opening moves are drawn from the square this far around the centre
+const OPENING_RADIUS: usize = 3;
+// With this many random stones neither side can have five yet
+const MAX_OPENING_PLIES: usize = 8;
+
+/// A sequential probability ratio test of a candidate against a baseline:
+/// H0 says the candidate is `elo0` stronger, H1 says `elo1` stronger.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SprtSettings {
+  #[serde(default)]
+  pub elo0: f64,
+  #[serde(default = "default_elo1")]
+  pub elo1: f64,
+  /// Chance of accepting H1 when H0 holds.
+  #[serde(default = "default_error_rate")]
+  pub alpha: f64,
+  /// Chance of accepting H0 when H1 holds.
+  #[serde(default = "default_error_rate")]
+  pub beta: f64,
+  /// Game pairs played at most before the test gives up undecided.
+  #[serde(default = "default_max_pairs")]
+  pub max_pairs: u32,
+  /// Random moves each pair's opening starts with; both games of a pair
+  /// share the opening with colours reversed.
+  #[serde(default = "default_opening_plies")]
+  pub opening_plies: usize,
+  #[serde(default = "default_parallelism")]
+  pub parallelism: usize,
+  #[serde(default = "default_board_size")]
+  pub board_size: usize,
+  #[serde(default = "default_rule_set")]
+  pub rule_set: RuleSetKind,
+  /// Seed of the openings and games; unset picks one at random.
+  #[serde(default)]
+  pub seed: Option<u64>,
+}
+
+impl Default for SprtSettings {
+  fn default() -> Self {
+    Self {
+      elo0: 0.0,
+      elo1: default_elo1(),
+      alpha: default_error_rate(),
+      beta: default_error_rate(),
+      max_pairs: default_max_pairs(),
+      opening_plies: default_opening_plies(),
+      parallelism: default_parallelism(),
+      board_size: default_board_size(),
+      rule_set: default_rule_set(),
+      seed: None,
+    }
+  }
+}
+
+fn default_elo1() -> f64 {
+  10.0
+}
+
+fn default_error_rate() -> f64 {
+  0.05
+}
+
+fn default_max_pairs() -> u32 {
+  2000
+}
+
+fn default_opening_plies() -> usize {
+  4
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SprtVerdict {
+  /// The candidate is no better than `elo0`.
+  H0,
+  /// The candidate is at least `elo1` better.
+  H1,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SprtReport {
+  pub candidate: String,
+  pub baseline: String,
+  pub elo0: f64,
+  pub elo1: f64,
+  pub pairs: u32,
+  /// Pairs by the candidate's points over the two games: 0, ½, 1, 1½ and 2.
+  pub pentanomial: [u32; 5],
+  /// Single games from the candidate's side.
+  pub wins: u32,
+  pub draws: u32,
+  pub losses: u32,
+  /// Log-likelihood ratio of H1 over H0; the test ends once it leaves the bounds.
+  pub llr: f64,
+  pub lower_bound: f64,
+  pub upper_bound: f64,
+  /// Candidate's Elo over the baseline, from the pair scores.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub elo: Option<f64>,
+  /// Half-width of the 95% confidence interval of `elo`.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub elo_margin: Option<f64>,
+  /// Unset while the test runs, or when it ended undecided.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub verdict: Option<SprtVerdict>,
+  pub stopped: bool,
+  /// Seed the run used; passing it back in repeats the openings.
+  pub seed: u64,
+}
+
+/// Plays pairs of games between `candidate` and `baseline` until the LLR
+/// crosses a bound, `max_pairs` is reached or `stop_flag` is set. Each pair
+/// starts both games from the same random opening, the candidate taking
+/// Black in the first and White in the second, so an unbalanced opening
+/// costs both sides alike. `on_pair` sees each finished pair with the
+/// statistics so far.
+pub fn run_sprt(
+  settings: &SprtSettings,
+  candidate: &(String, MatchPlayer),
+  baseline: &(String, MatchPlayer),
+  stop_flag: &AtomicBool,
+  mut on_pair: impl FnMut(&SprtReport, &[GameRecord; 2]) -> Result<(), String>,
+) -> Result<SprtReport, String> {
+  check_board_size(settings.board_size)?;
+  if settings.elo1 <= settings.elo0 {
+    return Err("elo1 must be above elo0".to_string());
+  }
+  let error_rate = |rate: f64| rate > 0.0 && rate < 0.5;
+  if !error_rate(settings.alpha) || !error_rate(settings.beta) {
+    return Err("alpha and beta must be between 0 and 0.5".to_string());
+  }
+  if settings.max_pairs == 0 {
+    return Err("The test needs at least one pair".to_string());
+  }
+  if settings.opening_plies > MAX_OPENING_PLIES {
+    return Err(format!("Openings can have at most {} moves", MAX_OPENING_PLIES));
+  }
+  let seed = settings.seed.unwrap_or_else(rand::random);
+  let max_pairs = settings.max_pairs as usize;
+  let mut report = SprtReport {
+    candidate: candidate.0.clone(),
+    baseline: baseline.0.clone(),
+    elo0: settings.elo0,
+    elo1: settings.elo1,
+    pairs: 0,
+    pentanomial: [0; 5],
+    wins: 0,
+    draws: 0,
+    losses: 0,
+    llr: 0.0,
+    lower_bound: (settings.beta / (1.0 - settings.alpha)).ln(),
+    upper_bound: ((1.0 - settings.beta) / settings.alpha).ln(),
+    elo: None,
+    elo_margin: None,
+    verdict: None,
+    stopped: false,
+    seed,
+  };
+
+  let next = AtomicUsize::new(0);
+  let workers = settings.parallelism.clamp(1, max_pairs);
+  let (tx, rx) = mpsc::channel();
+  thread::scope(|scope| -> Result<(), String> {
+    for _ in 0..workers {
+      let tx = tx.clone();
+      let next = &next;
+      scope.spawn(move || loop {
+        if stop_flag.load(Ordering::Relaxed) {
+          break;
+        }
+        let pair = next.fetch_add(1, Ordering::Relaxed);
+        if pair >= max_pairs {
+          break;
+        }
+        let records = play_pair(settings, candidate, baseline, seed, pair);
+        if tx.send(records).is_err() {
+          break;
+        }
+      });
+    }
+    drop(tx);
+
+    // Dropping the receiver once decided stops the workers after their current pair
+    for records in rx {
+      let records = records?;
+      add_pair(&mut report, &records, settings);
+      on_pair(&report, &records)?;
+      if report.verdict.is_some() {
+        break;
+      }
+    }
+    Ok(())
+  })?;
+
+  report.stopped = report.verdict.is_none() && report.pairs < settings.max_pairs;
+  Ok(report)
+}
+
+// Both games of pair `pair`: the candidate is Black in the first
+fn play_pair(
+  settings: &SprtSettings,
+  candidate: &(String, MatchPlayer),
+  baseline: &(String, MatchPlayer),
+  seed: u64,
+  pair: usize,
+) -> Result<[GameRecord; 2], String> {
+  let mut rng = StdRng::seed_from_u64(game_seed(!seed, pair as u64));
+  let opening = random_opening(settings.board_size, settings.rule_set, settings.opening_plies, &mut rng);
+  let play = |game: usize, black: &(String, MatchPlayer), white: &(String, MatchPlayer)| {
+    let game_index = (2 * pair + game) as u64;
+    let game_seed = game_seed(seed, game_index);
+    let state = play_match_game(settings.board_size, settings.rule_set, &opening, &black.1, &white.1, game_seed)?;
+    let mut record = state.to_record();
+    record.players = Players {
+      black: black.0.clone(),
+      white: white.0.clone(),
+    };
+    record.meta.self_play = Some(SelfPlayMeta {
+      black_id: black.0.clone(),
+      white_id: white.0.clone(),
+      black_config: black.1.config(game_seed),
+      white_config: white.1.config(game_seed),
+      series_seed: seed,
+      game: game_index,
+    });
+    Ok::<_, String>(record)
+  };
+  Ok([play(0, candidate, baseline)?, play(1, baseline, candidate)?])
+}
+
+// Legal random moves near the centre, alternating from Black
+fn random_opening(board_size: usize, rule_set: RuleSetKind, plies: usize, rng: &mut StdRng) -> Vec<Coord> {
+  let rules = rules_for(rule_set);
+  let center = board_size / 2;
+  let low = center.saturating_sub(OPENING_RADIUS);
+  let high = (center + OPENING_RADIUS).min(board_size - 1);
+  let mut board = Board::new(board_size);
+  let mut opening = Vec::with_capacity(plies);
+  let mut player = Player::B;
+  while opening.len() < plies {
+    let x = rng.gen_range(low..=high);
+    let y = rng.gen_range(low..=high);
+    let mv = Move {
+      x,
+      y,
+      player,
+      t: None,
+    };
+    if !board.is_empty(x, y) || !rules.is_legal(&board, &mv) {
+      continue;
+    }
+    board.set(x, y, player);
+    opening.push(Coord { x, y });
+    player = player.other();
+  }
+  opening
+}
+
+// Adds a finished pair to the counts and redoes the test
+fn add_pair(report: &mut SprtReport, records: &[GameRecord; 2], settings: &SprtSettings) {
+  let mut points = 0.0;
+  for (record, color) in records.iter().zip([Player::B, Player::W]) {
+    let score = score_for_result(record.result.unwrap_or(GameResult::Draw), color);
+    match score {
+      s if s > 0.5 => report.wins += 1,
+      s if s < 0.5 => report.losses += 1,
+      _ => report.draws += 1,
+    }
+    points += score;
+  }
+  report.pentanomial[(points * 2.0).round() as usize] += 1;
+  report.pairs += 1;
+
+  // Each pair is one sample of the candidate's average score over its two games
+  let mut score = MatchScore {
+    games: 0,
+    points: 0.0,
+    squares: 0.0,
+  };
+  for (count, value) in report.pentanomial.iter().zip([0.0, 0.25, 0.5, 0.75, 1.0]) {
+    score.games += count;
+    score.points += *count as f64 * value;
+    score.squares += *count as f64 * value * value;
+  }
+  let (elo, margin) = elo_estimate(&score);
+  report.elo = Some(elo);
+  report.elo_margin = Some(margin);
+  report.llr = llr(&report.pentanomial, settings.elo0, settings.elo1);
+  report.verdict = if report.llr >= report.upper_bound {
+    Some(SprtVerdict::H1)
+  } else if report.llr <= report.lower_bound {
+    Some(SprtVerdict::H0)
+  } else {
+    None
+  };
+}
+
+// Generalised SPRT on the pentanomial counts: the normal approximation of
+// the log-likelihood ratio between the expected scores under H1 and H0
+fn llr(pentanomial: &[u32; 5], elo0: f64, elo1: f64) -> f64 {
+  let pairs = pentanomial.iter().sum::<u32>() as f64;
+  let values = [0.0, 0.25, 0.5, 0.75, 1.0];
+  let mean = pentanomial.iter().zip(values).map(|(&count, value)| count as f64 * value).sum::<f64>() / pairs;
+  let variance = pentanomial
+    .iter()
+    .zip(values)
+    .map(|(&count, value)| count as f64 * (value - mean).powi(2))
+    .sum::<f64>()
+    / pairs;
+  // Until two pairs have ended differently there is nothing to test against
+  if variance <= 0.0 {
+    return 0.0;
+  }
+  let expected = |elo: f64| 1.0 / (1.0 + 10f64.powf(-elo / 400.0));
+  let (s0, s1) = (expected(elo0), expected(elo1));
+  pairs * (s1 - s0) * (2.0 * mean - s0 - s1) / (2.0 * variance)
+}
@@ -0,0 +1,414 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::Board;
+use crate::rules::{rules_for, RuleSet};
+use crate::types::{Coord, Move, Player, RuleSetKind};
+
+const DIRECTIONS: [(i32, i32); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+// Limits on the number of attacking moves in a sequence
+const VCF_MAX_DEPTH: u8 = 20;
+const VCT_MAX_DEPTH: u8 = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThreatKind {
+  /// Victory by continuous fours
+  Vcf,
+  /// Victory by continuous threats (fours and threes)
+  Vct,
+}
+
+/// A forced win for the attacker. `moves` alternates attacker and defender,
+/// starting with the attacker and ending with the winning five. Against a
+/// three the defender has several replies; the line shows one of them, the
+/// others lose as well.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForcedWin {
+  pub kind: ThreatKind,
+  pub moves: Vec<Coord>,
+}
+
+/// Looks for a VCF first and then, with whatever budget is left, a VCT.
+/// `max_nodes` bounds the total number of positions visited.
+pub fn find_forced_win(board: &Board, rule_set: RuleSetKind, attacker: Player, max_nodes: u32) -> Option<ForcedWin> {
+  if max_nodes == 0 {
+    return None;
+  }
+  let rules = rules_for(rule_set);
+  let mut work = board.clone();
+
+  let mut vcf = Solver::new(rules.as_ref(), attacker, max_nodes, false);
+  if let Some(moves) = vcf.attack(&mut work, VCF_MAX_DEPTH) {
+    return Some(ForcedWin {
+      kind: ThreatKind::Vcf,
+      moves,
+    });
+  }
+
+  let remaining = max_nodes.saturating_sub(vcf.nodes);
+  let mut vct = Solver::new(rules.as_ref(), attacker, remaining, true);
+  vct.attack(&mut work, VCT_MAX_DEPTH).map(|moves| ForcedWin {
+    kind: ThreatKind::Vct,
+    moves,
+  })
+}
+
+struct Solver<'a> {
+  rules: &'a dyn RuleSet,
+  attacker: Player,
+  nodes: u32,
+  max_nodes: u32,
+  allow_threes: bool,
+  // Attacker-to-move positions already shown not to win at a given depth
+  failed: HashSet<(u64, u8)>,
+}
+
+impl<'a> Solver<'a> {
+  fn new(rules: &'a dyn RuleSet, attacker: Player, max_nodes: u32, allow_threes: bool) -> Self {
+    Self {
+      rules,
+      attacker,
+      nodes: 0,
+      max_nodes,
+      allow_threes,
+      failed: HashSet::new(),
+    }
+  }
+
+  fn defender(&self) -> Player {
+    self.attacker.other()
+  }
+
+  fn spend_node(&mut self) -> bool {
+    if self.nodes >= self.max_nodes {
+      return false;
+    }
+    self.nodes += 1;
+    true
+  }
+
+  // OR node: the attacker needs one threat that wins against every defence.
+  fn attack(&mut self, board: &mut Board, depth: u8) -> Option<Vec<Coord>> {
+    if !self.spend_node() {
+      return None;
+    }
+    if let Some(&five) = five_points(board, self.rules, self.attacker).first() {
+      return Some(vec![five]);
+    }
+    // The defender would complete a five before any threat of ours matters
+    if !five_points(board, self.rules, self.defender()).is_empty() {
+      return None;
+    }
+    if depth == 0 {
+      return None;
+    }
+    let key = (board.zobrist_hash(), depth);
+    if self.failed.contains(&key) {
+      return None;
+    }
+
+    for threat in self.threat_moves(board) {
+      board.set(threat.x, threat.y, self.attacker);
+      let line = self.defend(board, depth);
+      board.clear(threat.x, threat.y);
+      if let Some(line) = line {
+        let mut moves = vec![threat];
+        moves.extend(line);
+        return Some(moves);
+      }
+      if self.nodes >= self.max_nodes {
+        return None;
+      }
+    }
+
+    self.failed.insert(key);
+    None
+  }
+
+  // AND node: every defence the defender has must still lose.
+  fn defend(&mut self, board: &mut Board, depth: u8) -> Option<Vec<Coord>> {
+    if !self.spend_node() {
+      return None;
+    }
+    if !five_points(board, self.rules, self.defender()).is_empty() {
+      return None;
+    }
+
+    let fives = five_points(board, self.rules, self.attacker);
+    if fives.len() >= 2 {
+      return Some(vec![fives[0], fives[1]]);
+    }
+    if let Some(&block) = fives.first() {
+      // A four: blocking it is the only reply
+      board.set(block.x, block.y, self.defender());
+      let line = self.attack(board, depth - 1);
+      board.clear(block.x, block.y);
+      return line.map(|line| prepend(block, line));
+    }
+    if !self.allow_threes {
+      return None;
+    }
+
+    let defences = self.three_defences(board);
+    if defences.is_empty() {
+      return None;
+    }
+    let mut main_line = None;
+    for defence in defences {
+      board.set(defence.x, defence.y, self.defender());
+      let line = self.after_defence(board, defence, depth);
+      board.clear(defence.x, defence.y);
+      let line = line?;
+      if main_line.is_none() {
+        main_line = Some(prepend(defence, line));
+      }
+    }
+    main_line
+  }
+
+  // Continues after the defender answered a three. A defence that makes a four
+  // forces the attacker to block before the three can be used.
+  fn after_defence(&mut self, board: &mut Board, defence: Coord, depth: u8) -> Option<Vec<Coord>> {
+    let counter = five_points_through(board, self.rules, self.defender(), defence);
+    match counter.len() {
+      0 => self.attack(board, depth - 1),
+      1 => {
+        let block = counter[0];
+        board.set(block.x, block.y, self.attacker);
+        let line = self.defend(board, depth);
+        board.clear(block.x, block.y);
+        line.map(|line| prepend(block, line))
+      }
+      _ => None,
+    }
+  }
+
+  fn threat_moves(&self, board: &mut Board) -> Vec<Coord> {
+    threat_moves(board, self.rules, self.attacker, self.allow_threes)
+  }
+
+  // Replies that may stop the attacker's threes: the points where the attacker
+  // would make a straight four, the fives those fours would threaten, and any
+  // move giving the defender a four of their own.
+  fn three_defences(&self, board: &mut Board) -> Vec<Coord> {
+    let mut defences = Vec::new();
+    let mut seen = HashSet::new();
+    for point in straight_four_points(board, self.rules, self.attacker) {
+      if seen.insert(point) {
+        defences.push(point);
+      }
+      board.set(point.x, point.y, self.attacker);
+      for five in five_points_through(board, self.rules, self.attacker, point) {
+        if seen.insert(five) {
+          defences.push(five);
+        }
+      }
+      board.clear(point.x, point.y);
+    }
+    if defences.is_empty() {
+      return defences;
+    }
+
+    let defender = self.defender();
+    for point in nearby_points(board, defender, 3, false) {
+      if seen.contains(&point) {
+        continue;
+      }
+      board.set(point.x, point.y, defender);
+      let is_four = !five_points_through(board, self.rules, defender, point).is_empty();
+      board.clear(point.x, point.y);
+      if is_four {
+        seen.insert(point);
+        defences.push(point);
+      }
+    }
+
+    defences.retain(|point| self.rules.is_legal(board, &place(*point, defender)));
+    defences
+  }
+}
+
+/// Legal moves for `player` that make a four, followed (with `allow_threes`)
+/// by those that make a three, i.e. threaten to make a straight four next move.
+pub fn threat_moves(board: &mut Board, rules: &dyn RuleSet, player: Player, allow_threes: bool) -> Vec<Coord> {
+  let mut fours = Vec::new();
+  let mut threes = Vec::new();
+  for point in nearby_points(board, player, 3, allow_threes) {
+    if !rules.is_legal(board, &place(point, player)) {
+      continue;
+    }
+    board.set(point.x, point.y, player);
+    if !five_points_through(board, rules, player, point).is_empty() {
+      fours.push(point);
+    } else if allow_threes && makes_straight_four_threat(board, rules, player, point) {
+      threes.push(point);
+    }
+    board.clear(point.x, point.y);
+  }
+  fours.extend(threes);
+  fours
+}
+
+fn prepend(first: Coord, rest: Vec<Coord>) -> Vec<Coord> {
+  let mut moves = Vec::with_capacity(rest.len() + 1);
+  moves.push(first);
+  moves.extend(rest);
+  moves
+}
+
+fn place(point: Coord, player: Player) -> Move {
+  Move {
+    x: point.x,
+    y: point.y,
+    player,
+    t: None,
+  }
+}
+
+fn wins_at(board: &mut Board, rules: &dyn RuleSet, player: Player, point: Coord) -> bool {
+  board.set(point.x, point.y, player);
+  let wins = rules.check_win(board, &place(point, player)).is_some();
+  board.clear(point.x, point.y);
+  wins
+}
+
+/// Empty points where `player` would complete a winning five right now.
+pub fn five_points(board: &mut Board, rules: &dyn RuleSet, player: Player) -> Vec<Coord> {
+  nearby_points(board, player, 4, false)
+    .into_iter()
+    .filter(|point| wins_at(board, rules, player, *point))
+    .collect()
+}
+
+/// Winning points on the lines through `origin`, which must hold a `player` stone.
+pub fn five_points_through(board: &mut Board, rules: &dyn RuleSet, player: Player, origin: Coord) -> Vec<Coord> {
+  let mut points = Vec::new();
+  for dir in DIRECTIONS {
+    if stones_in_window(board, origin, dir, player) >= 3 {
+      for point in five_points_on(board, rules, player, origin, dir) {
+        if !points.contains(&point) {
+          points.push(point);
+        }
+      }
+    }
+  }
+  points
+}
+
+// Winning points for `player` on the line through `origin` in direction `dir`.
+fn five_points_on(
+  board: &mut Board,
+  rules: &dyn RuleSet,
+  player: Player,
+  origin: Coord,
+  dir: (i32, i32),
+) -> Vec<Coord> {
+  line_points(board, origin, dir)
+    .into_iter()
+    .filter(|point| {
+      board.is_empty(point.x, point.y)
+        && stones_in_window(board, *point, dir, player) >= 4
+        && wins_at(board, rules, player, *point)
+    })
+    .collect()
+}
+
+/// Whether some empty point on the lines through `origin` would give `player`
+/// a straight four there, i.e. two ways to complete a five.
+fn makes_straight_four_threat(board: &mut Board, rules: &dyn RuleSet, player: Player, origin: Coord) -> bool {
+  for dir in DIRECTIONS {
+    if stones_in_window(board, origin, dir, player) < 2 {
+      continue;
+    }
+    for point in line_points(board, origin, dir) {
+      if !board.is_empty(point.x, point.y) || stones_in_window(board, point, dir, player) < 3 {
+        continue;
+      }
+      board.set(point.x, point.y, player);
+      let fives = five_points_on(board, rules, player, point, dir).len();
+      board.clear(point.x, point.y);
+      if fives >= 2 && rules.is_legal(board, &place(point, player)) {
+        return true;
+      }
+    }
+  }
+  false
+}
+
+/// Empty points where `player` would make a straight four (or two fours at once).
+fn straight_four_points(board: &mut Board, rules: &dyn RuleSet, player: Player) -> Vec<Coord> {
+  nearby_points(board, player, 3, false)
+    .into_iter()
+    .filter(|point| {
+      board.set(point.x, point.y, player);
+      let fives = five_points_through(board, rules, player, *point).len();
+      board.clear(point.x, point.y);
+      fives >= 2 && rules.is_legal(board, &place(*point, player))
+    })
+    .collect()
+}
+
+// Points within four steps of `origin` along one line.
+fn line_points(board: &Board, origin: Coord, (dx, dy): (i32, i32)) -> Vec<Coord> {
+  (-4..=4)
+    .filter(|&k| k != 0)
+    .filter_map(|k| offset(board, origin, dx * k, dy * k))
+    .collect()
+}
+
+fn offset(board: &Board, origin: Coord, dx: i32, dy: i32) -> Option<Coord> {
+  let x = origin.x as i32 + dx;
+  let y = origin.y as i32 + dy;
+  if x >= 0 && y >= 0 && board.in_bounds(x as usize, y as usize) {
+    Some(Coord {
+      x: x as usize,
+      y: y as usize,
+    })
+  } else {
+    None
+  }
+}
+
+// Stones of `player` within four steps of `point` along one line, not counting the point itself.
+fn stones_in_window(board: &Board, point: Coord, (dx, dy): (i32, i32), player: Player) -> usize {
+  (-4..=4)
+    .filter(|&k| k != 0)
+    .filter(|&k| {
+      offset(board, point, dx * k, dy * k)
+        .map(|c| board.get(c.x, c.y) == Some(player))
+        .unwrap_or(false)
+    })
+    .count()
+}
+
+// Empty points with at least `min_stones` of `player`'s stones within four
+// steps along one line; fewer cannot make the shape being looked for. With
+// `loose`, one stone less is enough (a three needs one move more than a four).
+fn nearby_points(board: &Board, player: Player, min_stones: usize, loose: bool) -> Vec<Coord> {
+  let needed = if loose { min_stones - 1 } else { min_stones };
+  let size = board.size();
+  // Counts per cell and direction, accumulated outwards from each stone
+  let mut counts = vec![[0usize; 4]; size * size];
+  for y in 0..size {
+    for x in 0..size {
+      if board.get(x, y) != Some(player) {
+        continue;
+      }
+      for (d, &(dx, dy)) in DIRECTIONS.iter().enumerate() {
+        for k in (-4..=4).filter(|&k| k != 0) {
+          if let Some(c) = offset(board, Coord { x, y }, dx * k, dy * k) {
+            counts[c.y * size + c.x][d] += 1;
+          }
+        }
+      }
+    }
+  }
+  board
+    .empty_coords()
+    .into_iter()
+    .filter(|point| counts[point.y * size + point.x].iter().any(|&n| n >= needed))
+    .collect()
+}
@@ -0,0 +1,406 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::{check_board_size, DEFAULT_BOARD_SIZE};
+use crate::eval::load_weights;
+use crate::rating::{game_seed, play_match_game, score_for_result, MatchPlayer, RatingStore};
+use crate::tune::{elo_estimate, MatchScore};
+use crate::types::{AiConfig, EngineConfig, GameRecord, GameResult, Player, Players, RuleSetKind, SelfPlayMeta};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TournamentFormat {
+  /// Every player meets every other player.
+  #[default]
+  RoundRobin,
+  /// The first player meets each of the others; they do not play each other.
+  Gauntlet,
+}
+
+/// One entrant: a ladder profile's config, optionally with its own weights,
+/// a full config given inline, or an external Gomocup engine.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentPlayer {
+  /// Shown in the crosstable and saved games; defaults to the profile id.
+  #[serde(default)]
+  pub name: String,
+  #[serde(default)]
+  pub profile: Option<String>,
+  #[serde(default)]
+  pub config: Option<AiConfig>,
+  /// Evaluation weights file replacing the config's weights.
+  #[serde(default)]
+  pub weights: Option<String>,
+  #[serde(default)]
+  pub engine: Option<EngineConfig>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentSettings {
+  #[serde(default)]
+  pub format: TournamentFormat,
+  #[serde(default)]
+  pub players: Vec<TournamentPlayer>,
+  /// Games each pairing plays, colours alternating.
+  #[serde(default = "default_games_per_pair")]
+  pub games_per_pair: u32,
+  #[serde(default = "default_parallelism")]
+  pub parallelism: usize,
+  #[serde(default = "default_board_size")]
+  pub board_size: usize,
+  #[serde(default = "default_rule_set")]
+  pub rule_set: RuleSetKind,
+  /// Seed of the games; unset picks one at random.
+  #[serde(default)]
+  pub seed: Option<u64>,
+}
+
+impl Default for TournamentSettings {
+  fn default() -> Self {
+    Self {
+      format: TournamentFormat::default(),
+      players: Vec::new(),
+      games_per_pair: default_games_per_pair(),
+      parallelism: default_parallelism(),
+      board_size: default_board_size(),
+      rule_set: default_rule_set(),
+      seed: None,
+    }
+  }
+}
+
+fn default_games_per_pair() -> u32 {
+  2
+}
+
+pub(crate) fn default_parallelism() -> usize {
+  thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+pub(crate) fn default_board_size() -> usize {
+  DEFAULT_BOARD_SIZE
+}
+
+pub(crate) fn default_rule_set() -> RuleSetKind {
+  RuleSetKind::Standard
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentGame {
+  /// Index in the schedule; game `i` is seeded with `game_seed(seed, i)`.
+  pub index: usize,
+  pub black: usize,
+  pub white: usize,
+  pub result: GameResult,
+  pub moves: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Standing {
+  pub player: usize,
+  pub name: String,
+  pub games: u32,
+  pub wins: u32,
+  pub draws: u32,
+  pub losses: u32,
+  pub points: f64,
+  /// Performance against the average of the opponents faced.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub elo: Option<f64>,
+  /// Half-width of the 95% confidence interval of `elo`.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub elo_margin: Option<f64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentReport {
+  pub format: TournamentFormat,
+  pub board_size: usize,
+  pub rule_set: RuleSetKind,
+  pub games_per_pair: u32,
+  pub players: Vec<String>,
+  pub total_games: u32,
+  pub completed_games: u32,
+  /// `points[i][j]` is what player i scored against player j.
+  pub points: Vec<Vec<f64>>,
+  /// `pair_games[i][j]` is how many games players i and j have played.
+  pub pair_games: Vec<Vec<u32>>,
+  pub standings: Vec<Standing>,
+  pub games: Vec<TournamentGame>,
+  pub stopped: bool,
+  /// Seed the run used; passing it back in repeats the run.
+  pub seed: u64,
+}
+
+/// Each player as it will play, with heuristic configs taken from the
+/// ladder profiles in `ladder` unless given inline. Games run side by side,
+/// so each search keeps to one thread.
+pub fn resolve_players(players: &[TournamentPlayer], ladder: &RatingStore) -> Result<Vec<(String, MatchPlayer)>, String> {
+  let mut resolved: Vec<(String, MatchPlayer)> = Vec::new();
+  for player in players {
+    let side = match (&player.engine, &player.config, &player.profile) {
+      (Some(engine), _, _) => MatchPlayer::Engine(engine.clone()),
+      (None, config, profile) => {
+        let mut config = match (config, profile) {
+          (Some(config), _) => *config,
+          (None, Some(profile)) => ladder
+            .get_profile(profile)
+            .and_then(|profile| profile.config)
+            .ok_or_else(|| format!("Unknown profile {}", profile))?,
+          (None, None) => return Err("Each player needs a profile, a config or an engine".to_string()),
+        };
+        if let Some(path) = &player.weights {
+          config.weights = load_weights(Path::new(path)).map_err(|e| format!("Cannot load weights {}: {}", path, e))?;
+        }
+        MatchPlayer::Ai(config)
+      }
+    };
+    let name = if !player.name.trim().is_empty() {
+      player.name.trim().to_string()
+    } else if let Some(profile) = &player.profile {
+      profile.clone()
+    } else if let Some(stem) = player.engine.as_ref().and_then(|engine| Path::new(&engine.path).file_stem()) {
+      stem.to_string_lossy().into_owned()
+    } else {
+      format!("player{}", resolved.len() + 1)
+    };
+    if resolved.iter().any(|(other, _)| *other == name) {
+      return Err(format!("Duplicate player name {}", name));
+    }
+    resolved.push((name, side));
+  }
+  Ok(resolved)
+}
+
+/// Plays the tournament. Games are scheduled round by round, each round
+/// holding one game of every pairing, so a run that stops early is still
+/// balanced. `on_game` sees each finished game with the standings so far.
+pub fn run_tournament(
+  settings: &TournamentSettings,
+  players: &[(String, MatchPlayer)],
+  stop_flag: &AtomicBool,
+  mut on_game: impl FnMut(&TournamentReport, &GameRecord) -> Result<(), String>,
+) -> Result<TournamentReport, String> {
+  check_board_size(settings.board_size)?;
+  if players.len() < 2 {
+    return Err("A tournament needs at least two players".to_string());
+  }
+  if settings.games_per_pair == 0 {
+    return Err("Each pairing needs at least one game".to_string());
+  }
+  let pairs: Vec<(usize, usize)> = match settings.format {
+    TournamentFormat::RoundRobin => (0..players.len())
+      .flat_map(|a| ((a + 1)..players.len()).map(move |b| (a, b)))
+      .collect(),
+    TournamentFormat::Gauntlet => (1..players.len()).map(|b| (0, b)).collect(),
+  };
+  let seed = settings.seed.unwrap_or_else(rand::random);
+  let total_games = pairs.len() * settings.games_per_pair as usize;
+  let count = players.len();
+  let mut report = TournamentReport {
+    format: settings.format,
+    board_size: settings.board_size,
+    rule_set: settings.rule_set,
+    games_per_pair: settings.games_per_pair,
+    players: players.iter().map(|(name, _)| name.clone()).collect(),
+    total_games: total_games as u32,
+    completed_games: 0,
+    points: vec![vec![0.0; count]; count],
+    pair_games: vec![vec![0; count]; count],
+    standings: Vec::new(),
+    games: Vec::new(),
+    stopped: false,
+    seed,
+  };
+  report.standings = standings(&report);
+
+  let next = AtomicUsize::new(0);
+  let workers = settings.parallelism.clamp(1, total_games);
+  let (tx, rx) = mpsc::channel();
+  thread::scope(|scope| -> Result<(), String> {
+    for _ in 0..workers {
+      let tx = tx.clone();
+      let next = &next;
+      let pairs = &pairs;
+      scope.spawn(move || loop {
+        if stop_flag.load(Ordering::Relaxed) {
+          break;
+        }
+        let index = next.fetch_add(1, Ordering::Relaxed);
+        if index >= total_games {
+          break;
+        }
+        let round = index / pairs.len();
+        let (a, b) = pairs[index % pairs.len()];
+        let (black, white) = if round % 2 == 1 { (b, a) } else { (a, b) };
+        let game = play_match_game(
+          settings.board_size,
+          settings.rule_set,
+          &[],
+          &players[black].1,
+          &players[white].1,
+          game_seed(seed, index as u64),
+        );
+        if tx.send((index, black, white, game)).is_err() {
+          break;
+        }
+      });
+    }
+    drop(tx);
+
+    for (index, black, white, game) in rx {
+      let outcome = game.and_then(|mut game| {
+        game.players = Players {
+          black: players[black].0.clone(),
+          white: players[white].0.clone(),
+        };
+        let mut record = game.to_record();
+        record.meta.self_play = Some(SelfPlayMeta {
+          black_id: players[black].0.clone(),
+          white_id: players[white].0.clone(),
+          black_config: players[black].1.config(game.seed),
+          white_config: players[white].1.config(game.seed),
+          series_seed: seed,
+          game: index as u64,
+        });
+        let result = record.result.unwrap_or(GameResult::Draw);
+        let black_score = score_for_result(result, Player::B);
+        report.points[black][white] += black_score;
+        report.points[white][black] += 1.0 - black_score;
+        report.pair_games[black][white] += 1;
+        report.pair_games[white][black] += 1;
+        report.completed_games += 1;
+        report.games.push(TournamentGame {
+          index,
+          black,
+          white,
+          result,
+          moves: record.moves.len(),
+        });
+        report.standings = standings(&report);
+        on_game(&report, &record)
+      });
+      // Dropping the receiver stops the workers after their current game
+      outcome?;
+    }
+    Ok(())
+  })?;
+
+  report.games.sort_by_key(|game| game.index);
+  report.stopped = report.completed_games < report.total_games;
+  Ok(report)
+}
+
+// Standings by points, then by fewer games played
+fn standings(report: &TournamentReport) -> Vec<Standing> {
+  let mut standings: Vec<Standing> = report
+    .players
+    .iter()
+    .enumerate()
+    .map(|(player, name)| {
+      let mut standing = Standing {
+        player,
+        name: name.clone(),
+        games: report.pair_games[player].iter().sum(),
+        wins: 0,
+        draws: 0,
+        losses: 0,
+        points: report.points[player].iter().sum(),
+        elo: None,
+        elo_margin: None,
+      };
+      let mut squares = 0.0;
+      for game in &report.games {
+        let color = if game.black == player {
+          Player::B
+        } else if game.white == player {
+          Player::W
+        } else {
+          continue;
+        };
+        let score = score_for_result(game.result, color);
+        match score {
+          s if s > 0.5 => standing.wins += 1,
+          s if s < 0.5 => standing.losses += 1,
+          _ => standing.draws += 1,
+        }
+        squares += score * score;
+      }
+      if standing.games > 0 {
+        let (elo, margin) = elo_estimate(&MatchScore {
+          games: standing.games,
+          points: standing.points,
+          squares,
+        });
+        standing.elo = Some(elo);
+        standing.elo_margin = Some(margin);
+      }
+      standing
+    })
+    .collect();
+  standings.sort_by(|a, b| b.points.total_cmp(&a.points).then(a.games.cmp(&b.games)));
+  standings
+}
+
+/// The standings as a plain-text crosstable, one row per player in
+/// standings order, with the points scored against each opponent.
+pub fn format_crosstable(report: &TournamentReport) -> String {
+  let width = report.players.iter().map(|name| name.len()).max().unwrap_or(0).max(4);
+  let order: Vec<usize> = report.standings.iter().map(|standing| standing.player).collect();
+  let mut text = String::new();
+  let _ = write!(text, "{:>3}  {:<width$}  {:>6}  {:>5}  {:>13}", "#", "Name", "Points", "Games", "Elo");
+  for rank in 1..=order.len() {
+    let _ = write!(text, "  {:>5}", rank);
+  }
+  text.push('\n');
+  for (rank, standing) in report.standings.iter().enumerate() {
+    let elo = match (standing.elo, standing.elo_margin) {
+      (Some(elo), Some(margin)) => format!("{:+.0} ± {:.0}", elo, margin),
+      _ => "-".to_string(),
+    };
+    let _ = write!(
+      text,
+      "{:>3}  {:<width$}  {:>6.1}  {:>5}  {:>13}",
+      rank + 1,
+      standing.name,
+      standing.points,
+      standing.games,
+      elo
+    );
+    for &opponent in &order {
+      let cell = if opponent == standing.player {
+        "x".to_string()
+      } else if report.pair_games[standing.player][opponent] == 0 {
+        ".".to_string()
+      } else {
+        format!(
+          "{}/{}",
+          report.points[standing.player][opponent],
+          report.pair_games[standing.player][opponent]
+        )
+      };
+      let _ = write!(text, "  {:>5}", cell);
+    }
+    text.push('\n');
+  }
+  text
+}
+
+/// Writes the report to `results.json` and the crosstable to
+/// `crosstable.txt` in `dir`.
+pub fn save_report(report: &TournamentReport, dir: &Path) -> Result<(), String> {
+  let data = serde_json::to_string_pretty(report).map_err(|e| e.to_string())?;
+  fs::write(dir.join("results.json"), data).map_err(|e| e.to_string())?;
+  fs::write(dir.join("crosstable.txt"), format_crosstable(report)).map_err(|e| e.to_string())
+}
@@ -0,0 +1,239 @@
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+use crate::types::Coord;
+
+pub const MIN_HASH_MB: usize = 1;
+pub const MAX_HASH_MB: usize = 1024;
+
+const SLOT_BYTES: usize = 16;
+const NO_MOVE: u64 = 0xFFFF;
+// Generations wrap around within the six bits kept per entry
+const GENERATION_MASK: u8 = 0x3F;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TTFlag {
+  Exact,      // Exact score
+  LowerBound, // Alpha cutoff (score >= beta)
+  UpperBound, // Beta cutoff (score <= alpha)
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TTEntry {
+  depth: u8,
+  score: i32,
+  flag: TTFlag,
+  generation: u8,
+  best_move: Option<Coord>, // Move that produced the score, for ordering and PV reconstruction
+}
+
+// `check` holds the position hash XOR the packed entry. Two threads writing the
+// same slot at once leave a pair that fails the check, so a torn entry reads
+// as a miss instead of a wrong score.
+#[derive(Default)]
+struct Slot {
+  check: AtomicU64,
+  data: AtomicU64,
+}
+
+/// Lock-free transposition table shared by every search thread. A game keeps
+/// one per AI seat so later moves start from what earlier searches learned;
+/// entries left over from older searches are the first to be replaced.
+pub struct TranspositionTable {
+  slots: Vec<Slot>,
+  mask: usize,
+  generation: AtomicU8,
+  size_mb: usize, // As requested; the slot count is rounded up to a power of two
+}
+
+impl TranspositionTable {
+  /// A table of roughly `size_mb` megabytes, clamped to MIN_HASH_MB..=MAX_HASH_MB.
+  pub fn new(size_mb: usize) -> Self {
+    let size_mb = size_mb.clamp(MIN_HASH_MB, MAX_HASH_MB);
+    Self {
+      size_mb,
+      ..Self::with_entries(size_mb * 1024 * 1024 / SLOT_BYTES)
+    }
+  }
+
+  /// A table with room for at least `entries` positions (rounded to a power of two).
+  pub fn with_entries(entries: usize) -> Self {
+    let count = entries.max(1).next_power_of_two();
+    Self {
+      slots: (0..count).map(|_| Slot::default()).collect(),
+      mask: count - 1,
+      generation: AtomicU8::new(0),
+      size_mb: (count * SLOT_BYTES).div_ceil(1024 * 1024),
+    }
+  }
+
+  pub fn size_mb(&self) -> usize {
+    self.size_mb
+  }
+
+  /// Marks the start of a new search; entries from earlier ones become replaceable.
+  pub fn new_search(&self) {
+    let next = (self.generation.load(Ordering::Relaxed) + 1) & GENERATION_MASK;
+    self.generation.store(next, Ordering::Relaxed);
+  }
+
+  pub fn probe(&self, hash: u64, depth: u8) -> Option<(i32, TTFlag)> {
+    self
+      .load(hash)
+      .filter(|entry| entry.depth >= depth)
+      .map(|entry| (entry.score, entry.flag))
+  }
+
+  pub fn best_move(&self, hash: u64) -> Option<Coord> {
+    self.load(hash).and_then(|entry| entry.best_move)
+  }
+
+  pub fn store(&self, hash: u64, depth: u8, score: i32, flag: TTFlag, best_move: Option<Coord>) {
+    let slot = &self.slots[hash as usize & self.mask];
+    let generation = self.generation.load(Ordering::Relaxed);
+    // Replace if slot is empty, stale, or the new entry has greater/equal depth
+    let should_replace = match decode(slot.data.load(Ordering::Relaxed)) {
+      None => true,
+      Some(existing) => existing.generation != generation || depth >= existing.depth,
+    };
+    if should_replace {
+      let data = encode(&TTEntry {
+        depth,
+        score,
+        flag,
+        generation,
+        best_move,
+      });
+      slot.check.store(hash ^ data, Ordering::Relaxed);
+      slot.data.store(data, Ordering::Relaxed);
+    }
+  }
+
+  fn load(&self, hash: u64) -> Option<TTEntry> {
+    let slot = &self.slots[hash as usize & self.mask];
+    let check = slot.check.load(Ordering::Relaxed);
+    let data = slot.data.load(Ordering::Relaxed);
+    if check ^ data != hash {
+      return None;
+    }
+    decode(data)
+  }
+}
+
+// Layout: score in bits 0-31, depth 32-39, flag 40-41, generation 42-47,
+// best move 48-63 (x and y one byte each). A zero flag marks an empty slot.
+fn encode(entry: &TTEntry) -> u64 {
+  let flag = match entry.flag {
+    TTFlag::Exact => 1,
+    TTFlag::LowerBound => 2,
+    TTFlag::UpperBound => 3,
+  };
+  let best_move = entry
+    .best_move
+    .map(|coord| ((coord.x as u64) << 8) | coord.y as u64)
+    .unwrap_or(NO_MOVE);
+  (entry.score as u32 as u64)
+    | ((entry.depth as u64) << 32)
+    | (flag << 40)
+    | (((entry.generation & GENERATION_MASK) as u64) << 42)
+    | (best_move << 48)
+}
+
+fn decode(data: u64) -> Option<TTEntry> {
+  let flag = match (data >> 40) & 0x3 {
+    1 => TTFlag::Exact,
+    2 => TTFlag::LowerBound,
+    3 => TTFlag::UpperBound,
+    _ => return None,
+  };
+  let best_move = match data >> 48 {
+    NO_MOVE => None,
+    packed => Some(Coord {
+      x: (packed >> 8) as usize,
+      y: (packed & 0xFF) as usize,
+    }),
+  };
+  Some(TTEntry {
+    depth: (data >> 32) as u8,
+    score: data as u32 as i32,
+    flag,
+    generation: ((data >> 42) as u8) & GENERATION_MASK,
+    best_move,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn entries_round_trip_through_packing() {
+    let entries = [
+      TTEntry {
+        depth: 7,
+        score: -123_456,
+        flag: TTFlag::UpperBound,
+        generation: 63,
+        best_move: Some(Coord { x: 19, y: 0 }),
+      },
+      TTEntry {
+        depth: 255,
+        score: i32::MAX,
+        flag: TTFlag::Exact,
+        generation: 0,
+        best_move: None,
+      },
+      TTEntry {
+        depth: 0,
+        score: i32::MIN,
+        flag: TTFlag::LowerBound,
+        generation: 17,
+        best_move: Some(Coord { x: 7, y: 12 }),
+      },
+    ];
+    for entry in entries {
+      let decoded = decode(encode(&entry)).expect("packed entry decodes");
+      assert_eq!(decoded.depth, entry.depth);
+      assert_eq!(decoded.score, entry.score);
+      assert_eq!(decoded.flag, entry.flag);
+      assert_eq!(decoded.generation, entry.generation);
+      assert_eq!(decoded.best_move, entry.best_move);
+    }
+    assert!(decode(0).is_none());
+  }
+
+  #[test]
+  fn stored_entries_are_found_by_their_hash_only() {
+    let tt = TranspositionTable::with_entries(16);
+    let hash = 0x1234_5678_9ABC_DEF0;
+    tt.store(hash, 5, -42, TTFlag::Exact, Some(Coord { x: 3, y: 4 }));
+    assert_eq!(tt.probe(hash, 5), Some((-42, TTFlag::Exact)));
+    assert_eq!(tt.best_move(hash), Some(Coord { x: 3, y: 4 }));
+    assert_eq!(tt.probe(hash, 6), None);
+
+    // Same slot, different key: the XOR check turns it into a miss
+    let other = hash ^ (1 << 40);
+    assert_eq!(other as usize & tt.mask, hash as usize & tt.mask);
+    assert_eq!(tt.probe(other, 0), None);
+    assert_eq!(tt.best_move(other), None);
+  }
+
+  #[test]
+  fn torn_writes_read_as_misses() {
+    let tt = TranspositionTable::with_entries(16);
+    let hash = 0xDEAD_BEEF;
+    tt.store(hash, 3, 10, TTFlag::LowerBound, None);
+    // Another thread's data landing without its matching check word
+    let slot = &tt.slots[hash as usize & tt.mask];
+    slot.data.store(
+      encode(&TTEntry {
+        depth: 9,
+        score: 999,
+        flag: TTFlag::Exact,
+        generation: 0,
+        best_move: None,
+      }),
+      Ordering::Relaxed,
+    );
+    assert_eq!(tt.probe(hash, 0), None);
+  }
+}
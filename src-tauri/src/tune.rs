@@ -0,0 +1,370 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::eval::{check_weights, MAX_DEFENSE, MAX_DEFENSE_WEIGHT, MAX_THREAT_SCORE};
+use crate::rating::{game_seed, play_ai_game, score_for_result};
+use crate::types::{AiConfig, EvalWeights, Player};
+
+// Gain schedule exponents recommended by Spall for SPSA
+const STEP_DECAY: f64 = 0.602;
+const PERTURBATION_DECAY: f64 = 0.101;
+// Weights SPSA adjusts; `five` stays fixed since it only has to outweigh the rest
+const TUNED_WEIGHTS: usize = 13;
+// Parameter indices: the nine patterns come first, then the two bonuses
+const PATTERNS: usize = 9;
+const THREATS: usize = 11;
+const DEFENSE: usize = 12;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TuneSettings {
+  pub iterations: u32,
+  /// Games per iteration between the two perturbed weight sets, colours alternating.
+  pub games_per_iteration: u32,
+  /// Games of the closing match between the tuned and the starting weights; 0 skips it.
+  pub verification_games: u32,
+  /// Size of a perturbation relative to each starting weight (0.1 tries ±10%).
+  pub perturbation: f64,
+  /// Size of the first update, in perturbations per unit of match score.
+  pub learning_rate: f64,
+  pub parallelism: usize,
+  /// Seed of the perturbations and games; unset picks one at random.
+  #[serde(default)]
+  pub seed: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TuneProgress {
+  pub iteration: u32,
+  pub iterations: u32,
+  pub games_played: u32,
+  pub weights: EvalWeights,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TuneReport {
+  pub iterations: u32, // Completed, which is fewer than requested when stopped
+  pub games_played: u32,
+  pub baseline: EvalWeights,
+  pub tuned: EvalWeights,
+  pub verification_games: u32,
+  /// Tuned weights' score against the baseline, from 0 to 1.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub verification_score: Option<f64>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub elo_gain: Option<f64>,
+  /// Half-width of the 95% confidence interval of `elo_gain`.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub elo_margin: Option<f64>,
+  pub stopped: bool,
+  /// Seed the run used; passing it back in repeats the run.
+  pub seed: u64,
+}
+
+// Score of side A over a match, from A's point of view
+pub(crate) struct MatchScore {
+  pub games: u32,
+  pub points: f64,
+  pub squares: f64, // Sum of squared per-game scores, for the variance
+}
+
+impl MatchScore {
+  pub fn mean(&self) -> f64 {
+    if self.games == 0 {
+      0.5
+    } else {
+      self.points / self.games as f64
+    }
+  }
+}
+
+/// Tunes `base.weights` by SPSA: each iteration nudges every weight up or
+/// down at random, plays the two resulting weight sets against each other
+/// and moves the weights towards whichever side scored better. A final match
+/// against the starting weights measures the Elo gained.
+pub fn run_spsa(
+  base: AiConfig,
+  settings: &TuneSettings,
+  stop_flag: &AtomicBool,
+  mut on_progress: impl FnMut(&TuneProgress),
+) -> Result<TuneReport, String> {
+  if settings.iterations == 0 || settings.games_per_iteration == 0 {
+    return Err("Tuning needs at least one iteration and one game per iteration".to_string());
+  }
+  if settings.perturbation <= 0.0 || settings.learning_rate <= 0.0 {
+    return Err("Perturbation and learning rate must be positive".to_string());
+  }
+  // Games run side by side, so each search keeps to one thread
+  let base = AiConfig { threads: 1, ..base };
+  let baseline = base.weights;
+  let start = to_params(&baseline);
+  // One perturbation step per weight; at least 1 so small integer weights can move
+  let scales = start.map(|value| (value * settings.perturbation).max(1.0));
+  let mut params = start;
+
+  let stability = settings.iterations as f64 / 10.0;
+  let step = settings.learning_rate * (1.0 + stability).powf(STEP_DECAY);
+  let seed = settings.seed.unwrap_or_else(rand::random);
+  let mut rng = StdRng::seed_from_u64(seed);
+  let mut games_played = 0;
+  let mut completed = 0;
+
+  for k in 0..settings.iterations {
+    if stop_flag.load(Ordering::Relaxed) {
+      break;
+    }
+    let a_k = step / (k as f64 + 1.0 + stability).powf(STEP_DECAY);
+    let c_k = 1.0 / (k as f64 + 1.0).powf(PERTURBATION_DECAY);
+    let delta: [f64; TUNED_WEIGHTS] = std::array::from_fn(|_| if rng.gen_bool(0.5) { 1.0 } else { -1.0 });
+    let mut plus = std::array::from_fn(|i| params[i] + c_k * delta[i] * scales[i]);
+    let mut minus = std::array::from_fn(|i| params[i] - c_k * delta[i] * scales[i]);
+    clamp_params(&mut plus);
+    clamp_params(&mut minus);
+
+    let plus_config = AiConfig {
+      weights: from_params(&baseline, &plus),
+      ..base
+    };
+    let minus_config = AiConfig {
+      weights: from_params(&baseline, &minus),
+      ..base
+    };
+    check_weights(&plus_config.weights)?;
+    check_weights(&minus_config.weights)?;
+    let result = play_match(
+      plus_config,
+      minus_config,
+      settings.games_per_iteration,
+      settings.parallelism,
+      rng.gen(),
+      stop_flag,
+    )?;
+    games_played += result.games;
+    if result.games < settings.games_per_iteration {
+      break; // Stopped mid-match
+    }
+
+    // Score difference between the two sides, from -1 to 1
+    let gain = 2.0 * result.mean() - 1.0;
+    for ((param, delta), scale) in params.iter_mut().zip(delta).zip(scales) {
+      *param += a_k * gain / (2.0 * c_k * delta) * scale;
+    }
+    clamp_params(&mut params);
+    completed += 1;
+    on_progress(&TuneProgress {
+      iteration: completed,
+      iterations: settings.iterations,
+      games_played,
+      weights: from_params(&baseline, &params),
+    });
+  }
+
+  let tuned = from_params(&baseline, &params);
+  check_weights(&tuned)?;
+  let mut report = TuneReport {
+    iterations: completed,
+    games_played,
+    baseline,
+    tuned,
+    verification_games: 0,
+    verification_score: None,
+    elo_gain: None,
+    elo_margin: None,
+    stopped: stop_flag.load(Ordering::Relaxed),
+    seed,
+  };
+  if report.stopped || settings.verification_games == 0 {
+    return Ok(report);
+  }
+
+  let tuned_config = AiConfig { weights: tuned, ..base };
+  let result = play_match(
+    tuned_config,
+    base,
+    settings.verification_games,
+    settings.parallelism,
+    rng.gen(),
+    stop_flag,
+  )?;
+  report.games_played += result.games;
+  report.verification_games = result.games;
+  report.stopped = result.games < settings.verification_games;
+  if result.games > 0 {
+    let (elo, margin) = elo_estimate(&result);
+    report.verification_score = Some(result.mean());
+    report.elo_gain = Some(elo);
+    report.elo_margin = Some(margin);
+  }
+  Ok(report)
+}
+
+/// Writes the tuned weights to `path` and the report next to it, as
+/// `<name>.report.json`.
+pub fn save_results(report: &TuneReport, path: &Path) -> Result<PathBuf, String> {
+  check_weights(&report.tuned)?;
+  let weights = serde_json::to_string_pretty(&report.tuned).map_err(|e| e.to_string())?;
+  fs::write(path, weights).map_err(|e| e.to_string())?;
+  let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("weights");
+  let report_path = path.with_file_name(format!("{}.report.json", stem));
+  let data = serde_json::to_string_pretty(report).map_err(|e| e.to_string())?;
+  fs::write(&report_path, data).map_err(|e| e.to_string())?;
+  Ok(report_path)
+}
+
+// Plays `games` games between `a` and `b`, A taking Black in the even ones;
+// game `i` is seeded with `game_seed(seed, i)`. Stops early (with fewer
+// games) once `stop_flag` is set.
+fn play_match(
+  a: AiConfig,
+  b: AiConfig,
+  games: u32,
+  parallelism: usize,
+  seed: u64,
+  stop_flag: &AtomicBool,
+) -> Result<MatchScore, String> {
+  let next = AtomicUsize::new(0);
+  let scores = Mutex::new(Vec::with_capacity(games as usize));
+  let workers = parallelism.clamp(1, games.max(1) as usize);
+
+  thread::scope(|scope| {
+    for _ in 0..workers {
+      scope.spawn(|| loop {
+        if stop_flag.load(Ordering::Relaxed) {
+          break;
+        }
+        let game = next.fetch_add(1, Ordering::Relaxed);
+        if game >= games as usize {
+          break;
+        }
+        let (black, white, a_color) = if game % 2 == 1 {
+          (b, a, Player::W)
+        } else {
+          (a, b, Player::B)
+        };
+        let result = play_ai_game(black, white, game_seed(seed, game as u64));
+        let score = result.map(|result| score_for_result(result, a_color));
+        if let Ok(mut scores) = scores.lock() {
+          scores.push(score);
+        }
+      });
+    }
+  });
+
+  let scores = scores.into_inner().map_err(|_| "Tuning lock poisoned".to_string())?;
+  let mut result = MatchScore {
+    games: 0,
+    points: 0.0,
+    squares: 0.0,
+  };
+  for score in scores {
+    let score = score?;
+    result.games += 1;
+    result.points += score;
+    result.squares += score * score;
+  }
+  Ok(result)
+}
+
+// Elo difference implied by the match score, with the half-width of its 95% interval
+pub(crate) fn elo_estimate(result: &MatchScore) -> (f64, f64) {
+  let n = result.games as f64;
+  // Keep a clean sweep finite
+  let mean = result.mean().clamp(0.5 / n, 1.0 - 0.5 / n);
+  // Spread about the clamped mean, so a sweep still gets a margin
+  let variance = (result.squares / n - 2.0 * mean * result.mean() + mean * mean).max(0.0);
+  let std_error = (variance / n).sqrt();
+  let elo = 400.0 * (mean / (1.0 - mean)).log10();
+  // Slope of the Elo curve at `mean`
+  let slope = 400.0 / (std::f64::consts::LN_10 * mean * (1.0 - mean));
+  (elo, 1.96 * std_error * slope)
+}
+
+fn to_params(weights: &EvalWeights) -> [f64; TUNED_WEIGHTS] {
+  [
+    weights.open_four,
+    weights.semi_four,
+    weights.broken_four,
+    weights.open_three,
+    weights.broken_three,
+    weights.semi_three,
+    weights.open_two,
+    weights.semi_two,
+    weights.open_one,
+    weights.open_four_bonus,
+    weights.double_three_bonus,
+    weights.center,
+    weights.defense,
+  ]
+  .map(|value| value as f64)
+}
+
+// Keeps the parameters within the limits `check_weights` enforces: none
+// negative, defense capped, and the threat weights scaled down together
+// when a busy position would score too much. Room is left for each threat
+// weight rounding up by a half.
+fn clamp_params(params: &mut [f64; TUNED_WEIGHTS]) {
+  for param in params.iter_mut() {
+    *param = param.max(0.0);
+  }
+  params[DEFENSE] = params[DEFENSE].min(MAX_DEFENSE as f64);
+  let factor = MAX_DEFENSE_WEIGHT as f64 / 10.0 * params[DEFENSE].round().max(100.0) / 100.0;
+  let patterns: f64 = params[..PATTERNS].iter().sum();
+  let bonuses: f64 = params[PATTERNS..THREATS].iter().sum();
+  let busiest = (2.0 * patterns + bonuses) * factor;
+  let limit = MAX_THREAT_SCORE as f64 - (2 * PATTERNS + THREATS - PATTERNS) as f64 * 0.5 * factor;
+  if busiest > limit {
+    let shrink = limit / busiest;
+    for param in &mut params[..THREATS] {
+      *param *= shrink;
+    }
+  }
+}
+
+fn from_params(baseline: &EvalWeights, params: &[f64; TUNED_WEIGHTS]) -> EvalWeights {
+  let value = |i: usize| params[i].round() as i32;
+  EvalWeights {
+    five: baseline.five,
+    open_four: value(0),
+    semi_four: value(1),
+    broken_four: value(2),
+    open_three: value(3),
+    broken_three: value(4),
+    semi_three: value(5),
+    open_two: value(6),
+    semi_two: value(7),
+    open_one: value(8),
+    open_four_bonus: value(9),
+    double_three_bonus: value(10),
+    center: value(11),
+    defense: value(12),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn clamped_params_pass_the_weight_checks() {
+    let baseline = EvalWeights::default();
+    let cases = [
+      [-5.0; TUNED_WEIGHTS],
+      [1e9; TUNED_WEIGHTS],
+      std::array::from_fn(|i| if i == DEFENSE { 1e4 } else { 40_000.3 }),
+      to_params(&baseline).map(|value| value * 3.0 + 0.49),
+    ];
+    for mut params in cases {
+      clamp_params(&mut params);
+      assert_eq!(check_weights(&from_params(&baseline, &params)), Ok(()), "{params:?}");
+    }
+  }
+}
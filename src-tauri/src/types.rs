@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum Player {
   B,
@@ -20,6 +20,96 @@ impl Player {
 #[serde(rename_all = "snake_case")]
 pub enum RuleSetKind {
   Standard,
+  Renju,
+  /// Overlines do not win for either colour.
+  ExactFive,
+  /// Overlines do not win for Black; White may still win with six or more.
+  ExactFiveBlack,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpeningRule {
+  #[default]
+  Free,
+  Swap,
+  Swap2,
+}
+
+/// Where a Swap/Swap2 opening currently stands. Seats are named after their
+/// tentative colours: the proposer starts as Black, the responder as White.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpeningPhase {
+  /// The proposer places the first three stones (black, white, black).
+  ProposerPlace,
+  /// The responder picks a colour or, under Swap2, elects to place two more stones.
+  ResponderChoice,
+  /// Swap2 only: the responder places stones four and five (white, black).
+  ResponderPlace,
+  /// Swap2 only: the proposer picks a colour after the extra stones.
+  ProposerChoice,
+  Done,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpeningChoice {
+  TakeBlack,
+  TakeWhite,
+  PlaceTwo,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpeningDecision {
+  pub ply: usize,
+  pub by: Player,
+  pub choice: OpeningChoice,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpeningState {
+  pub rule: OpeningRule,
+  pub phase: OpeningPhase,
+  #[serde(default)]
+  pub decisions: Vec<OpeningDecision>,
+  /// True when the final choice swapped the seats, i.e. the proposer ended up White.
+  #[serde(default)]
+  pub swapped: bool,
+}
+
+impl OpeningState {
+  pub fn new(rule: OpeningRule) -> Self {
+    let phase = match rule {
+      OpeningRule::Free => OpeningPhase::Done,
+      OpeningRule::Swap | OpeningRule::Swap2 => OpeningPhase::ProposerPlace,
+    };
+    Self {
+      rule,
+      phase,
+      decisions: Vec::new(),
+      swapped: false,
+    }
+  }
+
+  pub fn is_done(&self) -> bool {
+    self.phase == OpeningPhase::Done
+  }
+
+  pub fn awaiting_choice(&self) -> bool {
+    matches!(
+      self.phase,
+      OpeningPhase::ResponderChoice | OpeningPhase::ProposerChoice
+    )
+  }
+}
+
+impl Default for OpeningState {
+  fn default() -> Self {
+    OpeningState::new(OpeningRule::Free)
+  }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -30,6 +120,36 @@ pub enum GameResult {
   Draw,
 }
 
+/// How a finished game was decided.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Termination {
+  Five,
+  BoardFull,
+  /// The side to act ran out of time and lost.
+  Timeout,
+  /// An external engine crashed, answered too late or played an illegal move.
+  Forfeit,
+}
+
+/// Fischer clock: main time plus an increment added after every move, in milliseconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeControl {
+  pub main_ms: u64,
+  #[serde(default)]
+  pub increment_ms: u64,
+}
+
+/// Time left on both clocks. The seat to act is the one whose clock is running.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClockState {
+  pub time_control: TimeControl,
+  pub black_ms: u64,
+  pub white_ms: u64,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Move {
@@ -40,6 +160,14 @@ pub struct Move {
   pub t: Option<i64>,
 }
 
+impl Move {
+  /// The same move on a `size` × `size` board after applying `symmetry`.
+  pub fn transformed(&self, size: usize, symmetry: Symmetry) -> Move {
+    let (x, y) = symmetry.apply(size, self.x, self.y);
+    Move { x, y, ..self.clone() }
+  }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Players {
@@ -56,6 +184,27 @@ pub struct Meta {
   pub updated_at: Option<i64>,
   #[serde(default, skip_serializing_if = "Option::is_none")]
   pub game_id: Option<String>,
+  /// `Some(false)` once a move has been taken back; such games never affect ratings.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub rated: Option<bool>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub self_play: Option<SelfPlayMeta>,
+}
+
+/// Who played a self-play or tournament game, and how to replay it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfPlayMeta {
+  pub black_id: String,
+  pub white_id: String,
+  /// Heuristic configs; LLM and engine sides have none.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub black_config: Option<AiConfig>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub white_config: Option<AiConfig>,
+  /// Seed of the whole run; the record's own seed is `game_seed(series_seed, game)`.
+  pub series_seed: u64,
+  pub game: u64,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -66,7 +215,17 @@ pub struct GameRecord {
   pub rule_set: RuleSetKind,
   pub players: Players,
   pub result: Option<GameResult>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub termination: Option<Termination>,
   pub moves: Vec<Move>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub opening: Option<OpeningState>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub clock: Option<ClockState>,
+  /// Seed the heuristic AI played this game's moves with, so an AI-vs-AI
+  /// game can be replayed move for move.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub seed: Option<u64>,
   pub meta: Meta,
 }
 
@@ -78,9 +237,35 @@ pub struct GameSnapshot {
   pub rule_set: RuleSetKind,
   pub to_move: Player,
   pub result: Option<GameResult>,
+  pub termination: Option<Termination>,
   pub moves: Vec<Move>,
   pub mode: GameMode,
+  pub opening: OpeningState,
   pub can_human_move: bool,
+  pub forbidden: Vec<Coord>,
+  pub can_undo: bool,
+  pub can_redo: bool,
+  pub rated: bool,
+  pub clock: Option<ClockState>,
+}
+
+/// One root move considered by the engine. `score` is from the side to move's
+/// point of view and `pv` starts with the move itself.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveAnalysis {
+  pub coord: Coord,
+  pub score: i32,
+  pub depth: u8,
+  pub nodes: u64,
+  pub pv: Vec<Coord>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionAnalysis {
+  pub to_move: Player,
+  pub moves: Vec<MoveAnalysis>,
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
@@ -91,6 +276,86 @@ pub struct AiConfig {
   pub randomness: u8,
   pub max_nodes: u32,
   pub defense_weight: i32,
+  /// Node budget of the VCF/VCT solver run before the main search; 0 disables it.
+  #[serde(default)]
+  pub threat_nodes: u32,
+  /// Node cap, per leaf, of the quiescence search that plays out fours and
+  /// their forced replies beyond the search depth. Not counted in `max_nodes`;
+  /// 0 scores leaves statically.
+  #[serde(default)]
+  pub quiescence_nodes: u32,
+  /// Wall-clock limit per move in milliseconds; 0 leaves only depth and node limits.
+  #[serde(default)]
+  pub move_time_ms: u64,
+  /// Transposition table size in megabytes; the table lasts for the whole game.
+  #[serde(default = "default_hash_mb")]
+  pub hash_mb: usize,
+  /// Search threads sharing the transposition table; 0 uses one per CPU core.
+  #[serde(default)]
+  pub threads: usize,
+  /// Pattern weights of the static evaluation.
+  #[serde(default)]
+  pub weights: EvalWeights,
+  /// Play from the opening book while the position is in it.
+  #[serde(default)]
+  pub use_book: bool,
+  /// How freely book moves are chosen: 0 always plays the best scoring one.
+  #[serde(default)]
+  pub book_variety: u8,
+  /// Seed of the AI's random choices. With a seed, the same position always
+  /// gets the same move; without one every move draws a fresh seed.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub seed: Option<u64>,
+}
+
+fn default_hash_mb() -> usize {
+  16
+}
+
+/// Scores the static evaluation gives to each shape. A weights file may list
+/// only the fields it changes; the rest keep their defaults.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EvalWeights {
+  pub five: i32,
+  pub open_four: i32,
+  pub semi_four: i32,
+  pub broken_four: i32,
+  pub open_three: i32,
+  pub broken_three: i32,
+  pub semi_three: i32,
+  pub open_two: i32,
+  pub semi_two: i32,
+  pub open_one: i32,
+  /// Bonus for having at least one open four on the board.
+  pub open_four_bonus: i32,
+  /// Bonus for two or more open threes at once.
+  pub double_three_bonus: i32,
+  /// Centre pull: each stone earns `center * (size - distance to centre) / 3`.
+  pub center: i32,
+  /// Opponent threats count this many percent on top of the profile's defense weight.
+  pub defense: i32,
+}
+
+impl Default for EvalWeights {
+  fn default() -> Self {
+    Self {
+      five: 200_000,
+      open_four: 50_000,
+      semi_four: 10_000,
+      broken_four: 7_000,
+      open_three: 3_000,
+      broken_three: 1_200,
+      semi_three: 400,
+      open_two: 300,
+      semi_two: 80,
+      open_one: 6,
+      open_four_bonus: 10_000,
+      double_three_bonus: 6_000,
+      center: 1,
+      defense: 100,
+    }
+  }
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -98,6 +363,8 @@ pub struct AiConfig {
 pub enum ProfileKind {
   Heuristic,
   Llm,
+  /// An external program speaking the Gomocup protocol.
+  Engine,
 }
 
 impl Default for ProfileKind {
@@ -126,6 +393,30 @@ pub struct LlmConfig {
   pub api_key_set: bool,
 }
 
+/// How to run an external Gomocup-protocol engine.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineConfig {
+  /// Path of the engine executable; it is started in its own directory.
+  pub path: String,
+  #[serde(default)]
+  pub args: Vec<String>,
+  /// Thinking time per move the engine is given (INFO timeout_turn).
+  #[serde(default = "default_engine_turn_ms")]
+  pub timeout_turn_ms: u64,
+  /// Memory limit passed to the engine (INFO max_memory); 0 sets none.
+  #[serde(default = "default_engine_memory_mb")]
+  pub max_memory_mb: u64,
+}
+
+fn default_engine_turn_ms() -> u64 {
+  5000
+}
+
+fn default_engine_memory_mb() -> u64 {
+  256
+}
+
 fn default_temperature() -> f32 {
   0.4
 }
@@ -146,13 +437,72 @@ fn default_candidate_limit() -> usize {
   12
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Coord {
   pub x: usize,
   pub y: usize,
 }
 
+impl Coord {
+  /// This point on a `size` × `size` board after applying `symmetry`.
+  pub fn transformed(self, size: usize, symmetry: Symmetry) -> Coord {
+    let (x, y) = symmetry.apply(size, self.x, self.y);
+    Coord { x, y }
+  }
+}
+
+/// One of the eight symmetries of the square board: an optional left-right
+/// mirror followed by `turns` quarter turns clockwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Symmetry {
+  pub mirror: bool,
+  pub turns: u8,
+}
+
+impl Symmetry {
+  pub const IDENTITY: Symmetry = Symmetry {
+    mirror: false,
+    turns: 0,
+  };
+
+  pub const ALL: [Symmetry; 8] = [
+    Symmetry { mirror: false, turns: 0 },
+    Symmetry { mirror: false, turns: 1 },
+    Symmetry { mirror: false, turns: 2 },
+    Symmetry { mirror: false, turns: 3 },
+    Symmetry { mirror: true, turns: 0 },
+    Symmetry { mirror: true, turns: 1 },
+    Symmetry { mirror: true, turns: 2 },
+    Symmetry { mirror: true, turns: 3 },
+  ];
+
+  /// Maps (x, y) on a `size` × `size` board.
+  pub fn apply(self, size: usize, x: usize, y: usize) -> (usize, usize) {
+    let last = size - 1;
+    let (x, y) = if self.mirror { (last - x, y) } else { (x, y) };
+    match self.turns % 4 {
+      0 => (x, y),
+      1 => (last - y, x),
+      2 => (last - x, last - y),
+      _ => (y, last - x),
+    }
+  }
+
+  /// The symmetry that undoes this one. Mirrored symmetries are reflections
+  /// and undo themselves; rotations are undone by turning the other way.
+  pub fn inverse(self) -> Symmetry {
+    if self.mirror {
+      self
+    } else {
+      Symmetry {
+        mirror: false,
+        turns: (4 - self.turns % 4) % 4,
+      }
+    }
+  }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum GameMode {
@@ -92,6 +92,10 @@ pub fn users_path() -> PathBuf {
   data_root().join("users.json")
 }
 
+pub fn opening_book_path() -> PathBuf {
+  data_root().join("opening_book.json")
+}
+
 pub fn user_dir(id: &str) -> PathBuf {
   data_root().join("users").join(id)
 }
@@ -47,6 +47,8 @@ function App() {
     newGame,
     makeMove,
     requestAiMove,
+    undoMove,
+    redoMove,
     saveGame,
     loadGame,
     exportTraining,
@@ -61,6 +63,8 @@ function App() {
     createLlmProfile,
     updateLlmProfile,
     deleteLlmProfile,
+    createEngineProfile,
+    updateEngineProfile,
   } = useRating()
 
   const { users, activeUser, activeUserDir, refreshUsers, createUser, switchUser, deleteUser, updateUser } =
@@ -300,6 +304,8 @@ function App() {
       autoPlaySpeed={autoPlay.speed}
       onMove={handleMove}
       onNewGame={handleNewGame}
+      onUndo={undoMove}
+      onRedo={redoMove}
       onSave={handleSave}
       onLoad={handleLoad}
       onSelectProfile={handleSelectProfile}
@@ -342,16 +348,22 @@ function App() {
           selfPlayMaxLevel={selfPlay.maxLevel}
           selfPlayIncludeLlm={selfPlay.includeLlm}
           selfPlayLlmIds={selfPlay.llmIds}
+          selfPlayEngineIds={selfPlay.engineIds}
+          selfPlaySaveGames={selfPlay.saveGames}
           onBack={goBack}
           onCreateLlm={createLlmProfile}
           onUpdateLlm={updateLlmProfile}
           onDeleteLlm={deleteLlmProfile}
+          onCreateEngine={createEngineProfile}
+          onUpdateEngine={updateEngineProfile}
           onSelfPlayGamesChange={selfPlay.setGamesPerPair}
           onSelfPlayParallelChange={selfPlay.setParallelism}
           onSelfPlayMinLevelChange={selfPlay.setMinLevel}
           onSelfPlayMaxLevelChange={selfPlay.setMaxLevel}
           onSelfPlayIncludeLlmChange={selfPlay.toggleIncludeLlm}
           onSelfPlayToggleLlmId={selfPlay.toggleLlmId}
+          onSelfPlayToggleEngineId={selfPlay.toggleEngineId}
+          onSelfPlaySaveGamesChange={selfPlay.setSaveGames}
           onSelfPlayStart={selfPlay.start}
           onSelfPlayStop={selfPlay.stop}
         />
@@ -394,6 +406,8 @@ function App() {
     createLlmProfile,
     updateLlmProfile,
     deleteLlmProfile,
+    createEngineProfile,
+    updateEngineProfile,
   ])
 
   return (
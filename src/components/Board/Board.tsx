@@ -1,14 +1,15 @@
 import React, { useMemo } from 'react'
-import type { Move, Player } from '../../types'
+import type { Coord, Move, Player } from '../../types'
 
 type BoardProps = {
   boardSize: number
   board: Array<Player | null>
   lastMove: Move | null
+  forbidden?: Coord[]
   onCellClick: (x: number, y: number) => void
 }
 
-function BoardComponent({ boardSize, board, lastMove, onCellClick }: BoardProps) {
+function BoardComponent({ boardSize, board, lastMove, forbidden = [], onCellClick }: BoardProps) {
   const max = boardSize - 1
   const viewBox = `-0.5 -0.5 ${max + 1} ${max + 1}`
 
@@ -58,6 +59,14 @@ function BoardComponent({ boardSize, board, lastMove, onCellClick }: BoardProps)
             <circle className="last-ring" cx={lastMove.x} cy={lastMove.y} r={0.52} />
           )}
         </g>
+        <g className="board-forbidden">
+          {forbidden.map(({ x, y }) => (
+            <g key={`forbidden-${x}-${y}`}>
+              <line x1={x - 0.18} y1={y - 0.18} x2={x + 0.18} y2={y + 0.18} />
+              <line x1={x - 0.18} y1={y + 0.18} x2={x + 0.18} y2={y - 0.18} />
+            </g>
+          ))}
+        </g>
         <g className="board-hits">
           {gridIndices.map((row) =>
             gridIndices.map((col) => (
@@ -85,6 +94,7 @@ export const Board = React.memo(BoardComponent, (prev, next) => {
   // Only re-render if board content or lastMove changed
   if (prev.boardSize !== next.boardSize) return false
   if (prev.lastMove?.x !== next.lastMove?.x || prev.lastMove?.y !== next.lastMove?.y) return false
+  if (prev.forbidden !== next.forbidden) return false
   // Compare board arrays by reference first (fast path), then by content if needed
   if (prev.board === next.board) return true
   if (prev.board.length !== next.board.length) return false
@@ -9,7 +9,11 @@ type ActionBarProps = {
   mode: GameMode
   isPlaying?: boolean
   autoPlaySpeed?: AutoPlaySpeed
+  canUndo?: boolean
+  canRedo?: boolean
   onNewGame: () => void
+  onUndo?: () => void
+  onRedo?: () => void
   onSave: () => void
   onLoad: () => void
   onAutoPlayStart?: () => void
@@ -23,7 +27,11 @@ function ActionBarComponent({
   mode,
   isPlaying = false,
   autoPlaySpeed = 'medium',
+  canUndo = false,
+  canRedo = false,
   onNewGame,
+  onUndo,
+  onRedo,
   onSave,
   onLoad,
   onAutoPlayStart,
@@ -39,6 +47,16 @@ function ActionBarComponent({
         <Button variant="primary" onClick={onNewGame}>
           New Game
         </Button>
+        {!isAiVsAi && onUndo && onRedo && (
+          <>
+            <Button onClick={onUndo} disabled={!canUndo}>
+              Undo
+            </Button>
+            <Button onClick={onRedo} disabled={!canRedo}>
+              Redo
+            </Button>
+          </>
+        )}
         {isAiVsAi && onAutoPlayStart && onAutoPlayStop && onAutoPlayStep && onAutoPlaySpeedChange && (
           <WatchControls
             isPlaying={isPlaying}
@@ -15,6 +15,7 @@ function GameBoardComponent({ game, lastMove, onCellClick }: GameBoardProps) {
         boardSize={game.boardSize}
         board={game.board}
         lastMove={lastMove}
+        forbidden={game.forbidden}
         onCellClick={onCellClick}
       />
     </div>
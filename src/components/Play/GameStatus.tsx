@@ -7,6 +7,11 @@ type GameStatusProps = {
   expectedWin: number | null
 }
 
+function formatClock(ms: number) {
+  const seconds = Math.ceil(ms / 1000)
+  return `${Math.floor(seconds / 60)}:${String(seconds % 60).padStart(2, '0')}`
+}
+
 function GameStatusComponent({ game, busy, expectedWin }: GameStatusProps) {
   const moveNumber = game.moves.length
   const toMoveText = game.toMove === 'B' ? 'Black to move' : 'White to move'
@@ -24,6 +29,11 @@ function GameStatusComponent({ game, busy, expectedWin }: GameStatusProps) {
       </span>
       <span className="game-status-item game-status-move">Move {moveNumber}</span>
       <span className="game-status-item">Win rate {winPct}</span>
+      {game.clock && (
+        <span className="game-status-item">
+          Black {formatClock(game.clock.blackMs)} · White {formatClock(game.clock.whiteMs)}
+        </span>
+      )}
     </div>
   )
 }
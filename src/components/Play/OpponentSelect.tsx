@@ -1,5 +1,6 @@
 import React, { useState, useRef, useEffect } from 'react'
 import type { ProfileRating } from '../../types'
+import { profileKindLabel } from '../../types'
 
 type OpponentSelectProps = {
   profiles: ProfileRating[]
@@ -92,7 +93,7 @@ function OpponentSelectComponent({
                 >
                   <span className="opponent-select-item-name">{profile.name}</span>
                   <span className="opponent-select-item-type">
-                    {profile.kind === 'llm' ? 'LLM' : 'AI'}
+                    {profileKindLabel(profile.kind)}
                   </span>
                   <span className="opponent-select-item-rating">{Math.round(profile.rating)}</span>
                 </button>
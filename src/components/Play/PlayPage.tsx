@@ -24,6 +24,8 @@ type PlayPageProps = {
   autoPlaySpeed?: AutoPlaySpeed
   onMove: (x: number, y: number) => void
   onNewGame: (mode?: GameMode) => void
+  onUndo: () => void
+  onRedo: () => void
   onSave: () => void
   onLoad: () => void
   onSelectProfile: (id: string) => void
@@ -50,6 +52,8 @@ function PlayPageComponent({
   autoPlaySpeed = 'medium',
   onMove,
   onNewGame,
+  onUndo,
+  onRedo,
   onSave,
   onLoad,
   onSelectProfile,
@@ -140,7 +144,7 @@ function PlayPageComponent({
           )}
           <div className="play-board-area">
             <GameBoard game={game} lastMove={lastMove} onCellClick={onMove} />
-            <ResultOverlay result={game.result} onNewGame={handleNewGameClick} />
+            <ResultOverlay result={game.result} termination={game.termination} onNewGame={handleNewGameClick} />
           </div>
           <GameStatus game={game} busy={busy} expectedWin={expectedWin} />
         </div>
@@ -151,6 +155,10 @@ function PlayPageComponent({
         isPlaying={isAutoPlaying}
         autoPlaySpeed={autoPlaySpeed}
         onNewGame={handleNewGameClick}
+        canUndo={game.canUndo && !busy}
+        canRedo={game.canRedo && !busy}
+        onUndo={onUndo}
+        onRedo={onRedo}
         onSave={onSave}
         onLoad={onLoad}
         onAutoPlayStart={onAutoPlayStart}
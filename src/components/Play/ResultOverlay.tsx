@@ -1,13 +1,14 @@
 import React from 'react'
-import type { GameResult } from '../../types'
+import type { GameResult, Termination } from '../../types'
 import { Button } from '../Shared'
 
 type ResultOverlayProps = {
   result: GameResult | null
+  termination: Termination | null
   onNewGame: () => void
 }
 
-function ResultOverlayComponent({ result, onNewGame }: ResultOverlayProps) {
+function ResultOverlayComponent({ result, termination, onNewGame }: ResultOverlayProps) {
   if (!result) return null
 
   const getMessage = () => {
@@ -17,6 +18,8 @@ function ResultOverlayComponent({ result, onNewGame }: ResultOverlayProps) {
   }
 
   const getSubtext = () => {
+    if (termination === 'timeout') return result === 'B_WIN' ? 'White ran out of time' : 'Black ran out of time'
+    if (termination === 'forfeit') return result === 'B_WIN' ? 'White forfeited' : 'Black forfeited'
     if (result === 'B_WIN') return 'You connected five stones'
     if (result === 'W_WIN') return 'The opponent connected five stones'
     return 'The board is full with no winner'
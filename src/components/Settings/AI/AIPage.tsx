@@ -1,6 +1,7 @@
 import React, { useCallback, useMemo, useState } from 'react'
-import type { ProfileRating, RatingsSnapshot, SelfPlayProgress, SelfPlayReport, LlmConfig } from '../../../types'
+import type { ProfileRating, RatingsSnapshot, SelfPlayProgress, SelfPlayReport, LlmConfig, EngineConfig, ArchiveFormat } from '../../../types'
 import { BackHeader } from '../../Layout'
+import { EngineProfiles } from './EngineProfiles'
 import { HeuristicProfiles } from './HeuristicProfiles'
 import { LlmProfiles } from './LlmProfiles'
 import { LlmProfileForm, type LlmFormState } from './LlmProfileForm'
@@ -31,16 +32,22 @@ type AIPageProps = {
   selfPlayMaxLevel: number
   selfPlayIncludeLlm: boolean
   selfPlayLlmIds: string[]
+  selfPlayEngineIds: string[]
+  selfPlaySaveGames: ArchiveFormat | null
   onBack: () => void
   onCreateLlm: (name: string, config: LlmConfig, apiKey: string) => Promise<boolean>
   onUpdateLlm: (id: string, name: string, config: LlmConfig, apiKey: string | null) => Promise<boolean>
   onDeleteLlm: (id: string, deleteKey: boolean) => void
+  onCreateEngine: (name: string, config: EngineConfig) => Promise<boolean>
+  onUpdateEngine: (id: string, name: string, config: EngineConfig) => Promise<boolean>
   onSelfPlayGamesChange: (value: number) => void
   onSelfPlayParallelChange: (value: number) => void
   onSelfPlayMinLevelChange: (value: number) => void
   onSelfPlayMaxLevelChange: (value: number) => void
   onSelfPlayIncludeLlmChange: (value: boolean, profiles: ProfileRating[]) => void
   onSelfPlayToggleLlmId: (id: string, checked: boolean) => void
+  onSelfPlayToggleEngineId: (id: string, checked: boolean) => void
+  onSelfPlaySaveGamesChange: (value: ArchiveFormat | null) => void
   onSelfPlayStart: () => void
   onSelfPlayStop: () => void
 }
@@ -57,16 +64,22 @@ function AIPageComponent({
   selfPlayMaxLevel,
   selfPlayIncludeLlm,
   selfPlayLlmIds,
+  selfPlayEngineIds,
+  selfPlaySaveGames,
   onBack,
   onCreateLlm,
   onUpdateLlm,
   onDeleteLlm,
+  onCreateEngine,
+  onUpdateEngine,
   onSelfPlayGamesChange,
   onSelfPlayParallelChange,
   onSelfPlayMinLevelChange,
   onSelfPlayMaxLevelChange,
   onSelfPlayIncludeLlmChange,
   onSelfPlayToggleLlmId,
+  onSelfPlayToggleEngineId,
+  onSelfPlaySaveGamesChange,
   onSelfPlayStart,
   onSelfPlayStop,
 }: AIPageProps) {
@@ -75,6 +88,7 @@ function AIPageComponent({
 
   const profiles = useMemo(() => ratings?.profiles ?? [], [ratings?.profiles])
   const llmProfiles = useMemo(() => profiles.filter((p) => p.kind === 'llm'), [profiles])
+  const engineProfiles = useMemo(() => profiles.filter((p) => p.kind === 'engine'), [profiles])
 
   const handleEditLlm = useCallback((profile: ProfileRating) => {
     setLlmForm({
@@ -133,6 +147,13 @@ function AIPageComponent({
     [deleteKey, llmForm.id, onDeleteLlm],
   )
 
+  const handleDeleteEngine = useCallback(
+    (id: string) => {
+      onDeleteLlm(id, false)
+    },
+    [onDeleteLlm],
+  )
+
   const handleIncludeLlmChange = useCallback(
     (value: boolean) => {
       onSelfPlayIncludeLlmChange(value, profiles)
@@ -164,6 +185,12 @@ function AIPageComponent({
             onDeleteKeyChange={setDeleteKey}
           />
         </div>
+        <EngineProfiles
+          profiles={profiles}
+          onCreate={onCreateEngine}
+          onUpdate={onUpdateEngine}
+          onDelete={handleDeleteEngine}
+        />
         <SelfPlayPanel
           busy={selfPlayBusy}
           gamesPerPair={selfPlayGames}
@@ -173,6 +200,9 @@ function AIPageComponent({
           includeLlm={selfPlayIncludeLlm}
           llmIds={selfPlayLlmIds}
           llmProfiles={llmProfiles}
+          engineIds={selfPlayEngineIds}
+          engineProfiles={engineProfiles}
+          saveGames={selfPlaySaveGames}
           progress={selfPlayProgress}
           report={selfPlayReport}
           eta={selfPlayEta}
@@ -182,6 +212,8 @@ function AIPageComponent({
           onMaxLevelChange={onSelfPlayMaxLevelChange}
           onIncludeLlmChange={handleIncludeLlmChange}
           onToggleLlmId={onSelfPlayToggleLlmId}
+          onToggleEngineId={onSelfPlayToggleEngineId}
+          onSaveGamesChange={onSelfPlaySaveGamesChange}
           onStart={onSelfPlayStart}
           onStop={onSelfPlayStop}
         />
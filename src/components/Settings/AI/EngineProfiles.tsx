@@ -0,0 +1,158 @@
+import React, { useCallback, useState } from 'react'
+import type { EngineConfig, ProfileRating } from '../../../types'
+import { formatRecord, formatWinRate } from '../../../types'
+import { Button, FormField } from '../../Shared'
+
+type EngineFormState = {
+  id: string | null
+  name: string
+  path: string
+  args: string
+  timeoutTurnMs: number
+  maxMemoryMb: number
+}
+
+const defaultEngineForm: EngineFormState = {
+  id: null,
+  name: '',
+  path: '',
+  args: '',
+  timeoutTurnMs: 5000,
+  maxMemoryMb: 256,
+}
+
+type EngineProfilesProps = {
+  profiles: ProfileRating[]
+  onCreate: (name: string, config: EngineConfig) => Promise<boolean>
+  onUpdate: (id: string, name: string, config: EngineConfig) => Promise<boolean>
+  onDelete: (id: string) => void
+}
+
+function EngineProfilesComponent({ profiles, onCreate, onUpdate, onDelete }: EngineProfilesProps) {
+  const [form, setForm] = useState<EngineFormState>(defaultEngineForm)
+  const engineProfiles = profiles.filter((p) => p.kind === 'engine')
+  const isEditing = !!form.id
+
+  const handleEdit = useCallback((profile: ProfileRating) => {
+    setForm({
+      id: profile.id,
+      name: profile.name,
+      path: profile.engine?.path ?? '',
+      args: profile.engine?.args.join(' ') ?? '',
+      timeoutTurnMs: profile.engine?.timeoutTurnMs ?? 5000,
+      maxMemoryMb: profile.engine?.maxMemoryMb ?? 256,
+    })
+  }, [])
+
+  const handleSave = useCallback(async () => {
+    if (!form.name.trim() || !form.path.trim()) return
+    const config: EngineConfig = {
+      path: form.path.trim(),
+      args: form.args.split(/\s+/).filter((arg) => arg.length > 0),
+      timeoutTurnMs: form.timeoutTurnMs,
+      maxMemoryMb: form.maxMemoryMb,
+    }
+    const success = form.id
+      ? await onUpdate(form.id, form.name.trim(), config)
+      : await onCreate(form.name.trim(), config)
+    if (success) {
+      setForm(defaultEngineForm)
+    }
+  }, [form, onCreate, onUpdate])
+
+  const handleDelete = useCallback(
+    (id: string) => {
+      onDelete(id)
+      if (form.id === id) {
+        setForm(defaultEngineForm)
+      }
+    },
+    [form.id, onDelete],
+  )
+
+  return (
+    <div className="panel">
+      <h3>Engine Profiles</h3>
+      <p>Play against external programs that speak the Gomocup (pbrain) protocol.</p>
+      <div className="user-grid">
+        {engineProfiles.length === 0 && <span className="muted">No engine profiles yet.</span>}
+        {engineProfiles.map((profile) => (
+          <div key={profile.id} className="user-card">
+            <div className="user-info">
+              <strong>{profile.name}</strong>
+              <span className="user-meta">Path: {profile.engine?.path ?? '—'}</span>
+              <span className="user-meta">
+                Turn time: {profile.engine?.timeoutTurnMs ?? '—'} ms · Memory: {profile.engine?.maxMemoryMb ?? '—'} MB
+              </span>
+              <span className="user-meta">Elo: {Math.round(profile.rating)} · Games: {profile.games}</span>
+              <span className="user-meta">
+                Record: {formatRecord(profile.wins, profile.draws, profile.losses)} · Win rate: {formatWinRate(profile.wins, profile.draws, profile.losses)}
+              </span>
+            </div>
+            <div className="user-actions">
+              <Button onClick={() => handleEdit(profile)}>Edit</Button>
+              <Button variant="danger" onClick={() => handleDelete(profile.id)}>
+                Delete
+              </Button>
+            </div>
+          </div>
+        ))}
+      </div>
+      <h3>{isEditing ? 'Edit Engine Profile' : 'Add Engine Profile'}</h3>
+      <div className="llm-form">
+        <FormField label="Profile name">
+          <input
+            type="text"
+            value={form.name}
+            onChange={(e) => setForm((prev) => ({ ...prev, name: e.target.value }))}
+            placeholder="Engine profile name"
+          />
+        </FormField>
+        <FormField label="Executable" help="Full path to the pbrain-* program.">
+          <input
+            type="text"
+            value={form.path}
+            onChange={(e) => setForm((prev) => ({ ...prev, path: e.target.value }))}
+            placeholder="/path/to/pbrain-engine"
+          />
+        </FormField>
+        <FormField label="Arguments">
+          <input
+            type="text"
+            value={form.args}
+            onChange={(e) => setForm((prev) => ({ ...prev, args: e.target.value }))}
+            placeholder="Optional, separated by spaces"
+          />
+        </FormField>
+        <div className="field-row">
+          <FormField label="Turn time (ms)">
+            <input
+              type="number"
+              min="100"
+              max="60000"
+              value={form.timeoutTurnMs}
+              onChange={(e) => setForm((prev) => ({ ...prev, timeoutTurnMs: Number(e.target.value) }))}
+            />
+          </FormField>
+          <FormField label="Memory (MB)">
+            <input
+              type="number"
+              min="16"
+              max="4096"
+              value={form.maxMemoryMb}
+              onChange={(e) => setForm((prev) => ({ ...prev, maxMemoryMb: Number(e.target.value) }))}
+            />
+          </FormField>
+        </div>
+        <div className="button-row">
+          <Button variant="primary" onClick={handleSave}>
+            {isEditing ? 'Update profile' : 'Create profile'}
+          </Button>
+          {isEditing && <Button onClick={() => setForm(defaultEngineForm)}>Cancel</Button>}
+        </div>
+      </div>
+    </div>
+  )
+}
+
+export const EngineProfiles = React.memo(EngineProfilesComponent)
@@ -1,5 +1,5 @@
 import React from 'react'
-import type { ProfileRating, SelfPlayProgress, SelfPlayReport } from '../../../types'
+import type { ArchiveFormat, ProfileRating, SelfPlayProgress, SelfPlayReport } from '../../../types'
 import { Button, FormField, ProgressBar } from '../../Shared'
 
 type SelfPlayPanelProps = {
@@ -11,6 +11,9 @@ type SelfPlayPanelProps = {
   includeLlm: boolean
   llmIds: string[]
   llmProfiles: ProfileRating[]
+  engineIds: string[]
+  engineProfiles: ProfileRating[]
+  saveGames: ArchiveFormat | null
   progress: SelfPlayProgress | null
   report: SelfPlayReport | null
   eta: string | null
@@ -20,6 +23,8 @@ type SelfPlayPanelProps = {
   onMaxLevelChange: (value: number) => void
   onIncludeLlmChange: (value: boolean) => void
   onToggleLlmId: (id: string, checked: boolean) => void
+  onToggleEngineId: (id: string, checked: boolean) => void
+  onSaveGamesChange: (value: ArchiveFormat | null) => void
   onStart: () => void
   onStop: () => void
 }
@@ -33,6 +38,9 @@ function SelfPlayPanelComponent({
   includeLlm,
   llmIds,
   llmProfiles,
+  engineIds,
+  engineProfiles,
+  saveGames,
   progress,
   report,
   eta,
@@ -42,20 +50,23 @@ function SelfPlayPanelComponent({
   onMaxLevelChange,
   onIncludeLlmChange,
   onToggleLlmId,
+  onToggleEngineId,
+  onSaveGamesChange,
   onStart,
   onStop,
 }: SelfPlayPanelProps) {
   const selectableLlm = llmProfiles.filter((p) => p.kind === 'llm' && p.llm?.apiKeySet)
   const selectedLlmCount = selectableLlm.filter((p) => llmIds.includes(p.id)).length
+  const selectedEngineCount = engineProfiles.filter((p) => engineIds.includes(p.id)).length
   const filteredHeuristicCount = maxLevel - minLevel + 1
-  const profileCount = filteredHeuristicCount + (includeLlm ? selectedLlmCount : 0)
+  const profileCount = filteredHeuristicCount + (includeLlm ? selectedLlmCount : 0) + selectedEngineCount
   const totalPairs = profileCount > 1 ? (profileCount * (profileCount - 1)) / 2 : 0
   const estimatedGames = totalPairs * gamesPerPair
 
   return (
     <div className="panel">
       <h3>Self-Play Calibration</h3>
-      <p>Run AI and optional LLM games to stabilize the ladder. Higher counts take longer.</p>
+      <p>Run AI and optional LLM or engine games to stabilize the ladder. Higher counts take longer.</p>
       <div className="calibrate-grid">
         <FormField label="Games per pair" help="Each profile pair plays this many games.">
           <input
@@ -98,6 +109,16 @@ function SelfPlayPanelComponent({
           </div>
           <span className="field-help">Only calibrate AI levels within this range (1-12).</span>
         </div>
+        <FormField label="Save games" help="Keep every game in the user folder for review or training.">
+          <select
+            value={saveGames ?? ''}
+            onChange={(e) => onSaveGamesChange(e.target.value ? (e.target.value as ArchiveFormat) : null)}
+          >
+            <option value="">Off</option>
+            <option value="directory">One file per game</option>
+            <option value="jsonl">Single JSONL file</option>
+          </select>
+        </FormField>
         <label className="toggle-row">
           <input
             type="checkbox"
@@ -137,6 +158,22 @@ function SelfPlayPanelComponent({
           })}
         </div>
       )}
+      {engineProfiles.length > 0 && (
+        <div className="llm-select">
+          {engineProfiles.map((profile) => (
+            <label key={profile.id} className="toggle-row">
+              <input
+                type="checkbox"
+                checked={engineIds.includes(profile.id)}
+                onChange={(e) => onToggleEngineId(profile.id, e.target.checked)}
+              />
+              <span>
+                {profile.name} · Elo {Math.round(profile.rating)} · Engine
+              </span>
+            </label>
+          ))}
+        </div>
+      )}
       <div className="helper-row">
         <span>
           Levels: L{String(minLevel).padStart(2, '0')}-L{String(maxLevel).padStart(2, '0')} · Profiles: {profileCount} · Pairs: {totalPairs} · Estimated games: {estimatedGames}
@@ -154,7 +191,7 @@ function SelfPlayPanelComponent({
         {report
           ? `Last run: ${report.completedGames}/${report.totalGames} games (${report.gamesPerPair}/pair)${
               report.stopped ? ' · Stopped early' : ''
-            }`
+            }${report.gamesPath ? ` · Games saved to ${report.gamesPath}` : ''}`
           : 'Run AI vs AI games to stabilize the Elo ladder.'}
       </p>
     </div>
@@ -1,4 +1,5 @@
 export { AIPage } from './AIPage'
+export { EngineProfiles } from './EngineProfiles'
 export { HeuristicProfiles } from './HeuristicProfiles'
 export { LlmProfileForm } from './LlmProfileForm'
 export { LlmProfiles } from './LlmProfiles'
@@ -19,6 +19,7 @@ function LadderTableComponent({ entries, activeProfileId }: LadderTableProps) {
           if (filter === 'user') return entry.kind === 'user'
           if (filter === 'ai') return entry.kind === 'heuristic'
           if (filter === 'llm') return entry.kind === 'llm'
+          if (filter === 'engine') return entry.kind === 'engine'
           return true
         })
     return [...filtered].sort((a, b) => b.rating - a.rating)
@@ -33,6 +34,7 @@ function LadderTableComponent({ entries, activeProfileId }: LadderTableProps) {
           ['user', 'User'],
           ['ai', 'AI'],
           ['llm', 'LLM'],
+          ['engine', 'Engine'],
         ] as const).map(([key, label]) => (
           <Chip
             key={key}
@@ -64,6 +66,8 @@ function LadderTableComponent({ entries, activeProfileId }: LadderTableProps) {
                     ? 'User'
                     : entry.kind === 'llm'
                     ? 'LLM'
+                    : entry.kind === 'engine'
+                    ? 'Engine'
                     : 'AI'}
                 </td>
                 <td>{Math.round(entry.rating)}</td>
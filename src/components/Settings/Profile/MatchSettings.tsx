@@ -1,6 +1,6 @@
 import React from 'react'
 import type { ProfileRating } from '../../../types'
-import { formatRecord } from '../../../types'
+import { formatRecord, profileKindLabel } from '../../../types'
 import { Chip } from '../../Shared'
 
 type MatchSettingsProps = {
@@ -51,7 +51,7 @@ function MatchSettingsComponent({
         >
           {profiles.map((profile) => (
             <option key={profile.id} value={profile.id}>
-              {profile.name} ({profile.kind === 'llm' ? 'LLM' : 'AI'} · Elo {Math.round(profile.rating)} · {formatRecord(profile.wins, profile.draws, profile.losses)})
+              {profile.name} ({profileKindLabel(profile.kind)} · Elo {Math.round(profile.rating)} · {formatRecord(profile.wins, profile.draws, profile.losses)})
             </option>
           ))}
         </select>
@@ -1,6 +1,6 @@
 import React from 'react'
 import type { RatingEntry, ProfileRating, UserInfo } from '../../../types'
-import { formatRecord, formatWinRate, BLACK_ADVANTAGE, expectedScore } from '../../../types'
+import { formatRecord, formatWinRate, profileKindLabel, BLACK_ADVANTAGE, expectedScore } from '../../../types'
 
 type RatingOverviewProps = {
   player: RatingEntry | null
@@ -30,7 +30,7 @@ function RatingOverviewComponent({ player, activeProfile, activeUser }: RatingOv
         <span className="status-label">Current opponent</span>
         <strong>{activeProfile?.name ?? '—'}</strong>
         <span className="rating-sub">
-          Type: {activeProfile ? (activeProfile.kind === 'llm' ? 'LLM' : 'AI') : '—'}
+          Type: {activeProfile ? profileKindLabel(activeProfile.kind) : '—'}
         </span>
         <span className="rating-sub">Elo: {activeProfile ? Math.round(activeProfile.rating) : '—'}</span>
         <span className="rating-sub">
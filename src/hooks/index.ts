@@ -1,7 +1,11 @@
+export { useAnalysis } from './useAnalysis'
 export { useAutoPlay } from './useAutoPlay'
 export type { AutoPlaySpeed } from './useAutoPlay'
 export { useGame } from './useGame'
 export { useNavigation } from './useNavigation'
+export { useOpeningBook } from './useOpeningBook'
 export { useRating } from './useRating'
+export { useReview } from './useReview'
 export { useSelfPlay } from './useSelfPlay'
+export { useTuning } from './useTuning'
 export { useUsers } from './useUsers'
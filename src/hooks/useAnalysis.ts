@@ -0,0 +1,62 @@
+import { useCallback, useEffect, useState } from 'react'
+import { invoke } from '@tauri-apps/api/tauri'
+import { listen } from '@tauri-apps/api/event'
+import type { AnalysisUpdate, PositionAnalysis } from '../types'
+
+const isTauri = typeof window !== 'undefined' && '__TAURI__' in window
+
+export function useAnalysis() {
+  const [running, setRunning] = useState(false)
+  const [update, setUpdate] = useState<AnalysisUpdate | null>(null)
+  const [error, setError] = useState<string | null>(null)
+
+  useEffect(() => {
+    if (!isTauri) return
+    let unlistenUpdate: (() => void) | null = null
+    let unlistenDone: (() => void) | null = null
+
+    listen<AnalysisUpdate>('analysis_update', (event) => {
+      setUpdate(event.payload)
+    }).then((fn) => {
+      unlistenUpdate = fn
+    })
+
+    listen<PositionAnalysis>('analysis_done', () => {
+      setRunning(false)
+    }).then((fn) => {
+      unlistenDone = fn
+    })
+
+    return () => {
+      unlistenUpdate?.()
+      unlistenDone?.()
+    }
+  }, [])
+
+  const start = useCallback(
+    async (topN?: number) => {
+      if (running) return
+      setRunning(true)
+      setUpdate(null)
+      setError(null)
+      try {
+        await invoke<boolean>('start_analysis', { topN })
+      } catch (err) {
+        setError(String(err))
+        setRunning(false)
+      }
+    },
+    [running],
+  )
+
+  const stop = useCallback(async () => {
+    if (!running) return
+    try {
+      await invoke('stop_analysis')
+    } catch (err) {
+      setError(String(err))
+    }
+  }, [running])
+
+  return { running, update, error, start, stop }
+}
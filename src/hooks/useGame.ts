@@ -1,6 +1,6 @@
 import { useCallback, useRef, useState } from 'react'
 import { invoke } from '@tauri-apps/api/tauri'
-import type { GameMode, GameSnapshot } from '../types'
+import type { ForcedWin, GameMode, GameSnapshot, PositionAnalysis, SolveResult, TimeControl } from '../types'
 import { defaultGameMode, emptyBoard } from '../types'
 
 const defaultSnapshot: GameSnapshot = {
@@ -9,9 +9,16 @@ const defaultSnapshot: GameSnapshot = {
   ruleSet: 'standard',
   toMove: 'B',
   result: null,
+  termination: null,
   moves: [],
   mode: defaultGameMode,
+  opening: { rule: 'free', phase: 'done', decisions: [], swapped: false },
   canHumanMove: true,
+  forbidden: [],
+  canUndo: false,
+  canRedo: false,
+  rated: true,
+  clock: null,
 }
 
 const isTauri = typeof window !== 'undefined' && '__TAURI__' in window
@@ -90,9 +97,10 @@ export function useGame() {
     }
   }, [call])
 
+  // A fixed `seed` makes the AI's moves reproducible
   const newGame = useCallback(
-    async (mode?: GameMode) => {
-      const snapshot = await call<GameSnapshot>('new_game', { ruleSet: 'standard', mode })
+    async (mode?: GameMode, boardSize?: number, timeControl?: TimeControl, seed?: number) => {
+      const snapshot = await call<GameSnapshot>('new_game', { ruleSet: 'standard', mode, boardSize, timeControl, seed })
       if (snapshot) {
         setGame(snapshot)
       }
@@ -150,6 +158,37 @@ export function useGame() {
     [busy, game.result, call, playStoneSound],
   )
 
+  const undoMove = useCallback(async () => {
+    if (busy) return
+    const snapshot = await call<GameSnapshot>('undo_move')
+    if (snapshot) {
+      setGame(snapshot)
+    }
+  }, [busy, call])
+
+  const redoMove = useCallback(async () => {
+    if (busy) return
+    const snapshot = await call<GameSnapshot>('redo_move')
+    if (snapshot) {
+      setGame(snapshot)
+    }
+  }, [busy, call])
+
+  const analyzePosition = useCallback(
+    async (topN?: number) => call<PositionAnalysis>('analyze_position', { topN }),
+    [call],
+  )
+
+  const findForcedWin = useCallback(
+    async (maxNodes?: number) => call<ForcedWin | null>('find_forced_win', { maxNodes }),
+    [call],
+  )
+
+  const solvePosition = useCallback(
+    async (maxNodes?: number) => call<SolveResult>('solve_position', { maxNodes }),
+    [call],
+  )
+
   const saveGame = useCallback(
     async (path: string) => {
       await call('save_game', { path })
@@ -188,6 +227,11 @@ export function useGame() {
     newGame,
     makeMove,
     requestAiMove,
+    undoMove,
+    redoMove,
+    analyzePosition,
+    findForcedWin,
+    solvePosition,
     saveGame,
     loadGame,
     exportTraining,
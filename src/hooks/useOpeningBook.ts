@@ -0,0 +1,54 @@
+import { useCallback, useEffect, useState } from 'react'
+import { invoke } from '@tauri-apps/api/tauri'
+import type { BookSummary, RuleSetKind } from '../types'
+
+const isTauri = typeof window !== 'undefined' && '__TAURI__' in window
+
+export function useOpeningBook() {
+  const [summary, setSummary] = useState<BookSummary | null>(null)
+  const [busy, setBusy] = useState(false)
+  const [error, setError] = useState<string | null>(null)
+
+  useEffect(() => {
+    if (!isTauri) return
+    invoke<BookSummary | null>('get_opening_book')
+      .then(setSummary)
+      .catch((err) => setError(String(err)))
+  }, [])
+
+  // Builds the active book from the saved games in `recordsDir`; size and rules default to the current game's
+  const build = useCallback(
+    async (recordsDir: string, boardSize?: number, ruleSet?: RuleSetKind, maxPlies?: number) => {
+      setBusy(true)
+      setError(null)
+      try {
+        const result = await invoke<BookSummary>('build_opening_book', { recordsDir, boardSize, ruleSet, maxPlies })
+        setSummary(result)
+        return result
+      } catch (err) {
+        setError(String(err))
+        return null
+      } finally {
+        setBusy(false)
+      }
+    },
+    [],
+  )
+
+  const load = useCallback(async (path: string) => {
+    setBusy(true)
+    setError(null)
+    try {
+      const result = await invoke<BookSummary>('load_opening_book', { path })
+      setSummary(result)
+      return result
+    } catch (err) {
+      setError(String(err))
+      return null
+    } finally {
+      setBusy(false)
+    }
+  }, [])
+
+  return { summary, busy, error, build, load }
+}
@@ -1,6 +1,6 @@
 import { useCallback, useState } from 'react'
 import { invoke } from '@tauri-apps/api/tauri'
-import type { LlmConfig, RatingsSnapshot } from '../types'
+import type { EngineConfig, LlmConfig, RatingsSnapshot } from '../types'
 
 const isTauri = typeof window !== 'undefined' && '__TAURI__' in window
 
@@ -86,6 +86,62 @@ export function useRating() {
     [call],
   )
 
+  const createEngineProfile = useCallback(
+    async (name: string, config: EngineConfig) => {
+      const snapshot = await call<RatingsSnapshot>('create_engine_profile', { name, config })
+      if (snapshot) {
+        setRatings(snapshot)
+        return true
+      }
+      return false
+    },
+    [call],
+  )
+
+  const updateEngineProfile = useCallback(
+    async (id: string, name: string, config: EngineConfig) => {
+      const snapshot = await call<RatingsSnapshot>('update_engine_profile', { id, name, config })
+      if (snapshot) {
+        setRatings(snapshot)
+        return true
+      }
+      return false
+    },
+    [call],
+  )
+
+  const createHeuristicProfile = useCallback(
+    async (name: string, baseProfile: string, weightsPath: string) => {
+      const snapshot = await call<RatingsSnapshot>('create_heuristic_profile', {
+        name,
+        baseProfile,
+        weightsPath,
+      })
+      if (snapshot) {
+        setRatings(snapshot)
+        return true
+      }
+      return false
+    },
+    [call],
+  )
+
+  const updateHeuristicProfile = useCallback(
+    async (id: string, name: string, weightsPath: string | null) => {
+      const snapshot = await call<RatingsSnapshot>('update_heuristic_profile', {
+        id,
+        name,
+        weightsPath,
+      })
+      if (snapshot) {
+        setRatings(snapshot)
+        return true
+      }
+      return false
+    },
+    [call],
+  )
+
   const deleteLlmProfile = useCallback(
     async (id: string, deleteKey: boolean) => {
       const snapshot = await call<RatingsSnapshot>('delete_llm_profile', {
@@ -110,5 +166,9 @@ export function useRating() {
     createLlmProfile,
     updateLlmProfile,
     deleteLlmProfile,
+    createEngineProfile,
+    updateEngineProfile,
+    createHeuristicProfile,
+    updateHeuristicProfile,
   }
 }
@@ -0,0 +1,68 @@
+import { useCallback, useEffect, useState } from 'react'
+import { invoke } from '@tauri-apps/api/tauri'
+import { listen } from '@tauri-apps/api/event'
+import type { GameReview, ReviewProgress } from '../types'
+
+const isTauri = typeof window !== 'undefined' && '__TAURI__' in window
+
+export function useReview() {
+  const [busy, setBusy] = useState(false)
+  const [progress, setProgress] = useState<ReviewProgress | null>(null)
+  const [review, setReview] = useState<GameReview | null>(null)
+  const [error, setError] = useState<string | null>(null)
+
+  useEffect(() => {
+    if (!isTauri) return
+    let unlistenProgress: (() => void) | null = null
+    let unlistenDone: (() => void) | null = null
+    let unlistenError: (() => void) | null = null
+
+    listen<ReviewProgress>('review_progress', (event) => {
+      setProgress(event.payload)
+    }).then((fn) => {
+      unlistenProgress = fn
+    })
+
+    listen<GameReview>('review_done', (event) => {
+      setReview(event.payload)
+      setBusy(false)
+      setProgress(null)
+    }).then((fn) => {
+      unlistenDone = fn
+    })
+
+    listen<string>('review_error', (event) => {
+      setError(event.payload)
+      setBusy(false)
+      setProgress(null)
+    }).then((fn) => {
+      unlistenError = fn
+    })
+
+    return () => {
+      unlistenProgress?.()
+      unlistenDone?.()
+      unlistenError?.()
+    }
+  }, [])
+
+  // Reviews the saved game at `path`, or the current game when omitted
+  const start = useCallback(
+    async (path?: string) => {
+      if (busy) return
+      setBusy(true)
+      setReview(null)
+      setProgress(null)
+      setError(null)
+      try {
+        await invoke<boolean>('start_review', { path })
+      } catch (err) {
+        setError(String(err))
+        setBusy(false)
+      }
+    },
+    [busy],
+  )
+
+  return { busy, progress, review, error, start }
+}
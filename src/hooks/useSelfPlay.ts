@@ -1,7 +1,7 @@
 import { useCallback, useEffect, useRef, useState } from 'react'
 import { invoke } from '@tauri-apps/api/tauri'
 import { listen } from '@tauri-apps/api/event'
-import type { SelfPlayProgress, SelfPlayReport, ProfileRating } from '../types'
+import type { ArchiveFormat, SelfPlayProgress, SelfPlayReport, ProfileRating } from '../types'
 
 const isTauri = typeof window !== 'undefined' && '__TAURI__' in window
 
@@ -33,6 +33,8 @@ export function useSelfPlay(onComplete: () => Promise<void>) {
   const [maxLevel, setMaxLevel] = useState(12)
   const [includeLlm, setIncludeLlm] = useState(false)
   const [llmIds, setLlmIds] = useState<string[]>([])
+  const [engineIds, setEngineIds] = useState<string[]>([])
+  const [saveGames, setSaveGames] = useState<ArchiveFormat | null>(null)
 
   const startRef = useRef<number | null>(null)
 
@@ -100,8 +102,10 @@ export function useSelfPlay(onComplete: () => Promise<void>) {
         parallelism,
         includeLlm,
         llmIds: includeLlm ? llmIds : [],
+        engineIds,
         minLevel,
         maxLevel,
+        saveGames,
       })
       if (result === null) {
         setBusy(false)
@@ -112,7 +116,7 @@ export function useSelfPlay(onComplete: () => Promise<void>) {
       setBusy(false)
       startRef.current = null
     }
-  }, [busy, gamesPerPair, parallelism, includeLlm, llmIds, minLevel, maxLevel])
+  }, [busy, gamesPerPair, parallelism, includeLlm, llmIds, engineIds, minLevel, maxLevel, saveGames])
 
   const stop = useCallback(async () => {
     if (!busy) return
@@ -148,6 +152,16 @@ export function useSelfPlay(onComplete: () => Promise<void>) {
     })
   }, [])
 
+  const toggleEngineId = useCallback((id: string, checked: boolean) => {
+    setEngineIds((prev) => {
+      if (checked) {
+        if (prev.includes(id)) return prev
+        return [...prev, id]
+      }
+      return prev.filter((item) => item !== id)
+    })
+  }, [])
+
   return {
     busy,
     progress,
@@ -160,12 +174,16 @@ export function useSelfPlay(onComplete: () => Promise<void>) {
     maxLevel,
     includeLlm,
     llmIds,
+    engineIds,
+    saveGames,
     setGamesPerPair,
     setParallelism,
     setMinLevel,
     setMaxLevel,
+    setSaveGames,
     toggleIncludeLlm,
     toggleLlmId,
+    toggleEngineId,
     start,
     stop,
   }
@@ -0,0 +1,73 @@
+import { useCallback, useEffect, useState } from 'react'
+import { invoke } from '@tauri-apps/api/tauri'
+import { listen } from '@tauri-apps/api/event'
+import type { SprtReport, SprtSettings, TournamentPlayer } from '../types'
+
+const isTauri = typeof window !== 'undefined' && '__TAURI__' in window
+
+export function useSprt() {
+  const [busy, setBusy] = useState(false)
+  // Statistics after the latest pair while running, the final report once done
+  const [report, setReport] = useState<SprtReport | null>(null)
+  const [error, setError] = useState<string | null>(null)
+
+  useEffect(() => {
+    if (!isTauri) return
+    let unlistenProgress: (() => void) | null = null
+    let unlistenDone: (() => void) | null = null
+    let unlistenError: (() => void) | null = null
+
+    listen<SprtReport>('sprt_progress', (event) => {
+      setReport(event.payload)
+    }).then((fn) => {
+      unlistenProgress = fn
+    })
+
+    listen<SprtReport>('sprt_done', (event) => {
+      setReport(event.payload)
+      setBusy(false)
+    }).then((fn) => {
+      unlistenDone = fn
+    })
+
+    listen<string>('sprt_error', (event) => {
+      setError(event.payload)
+      setBusy(false)
+    }).then((fn) => {
+      unlistenError = fn
+    })
+
+    return () => {
+      unlistenProgress?.()
+      unlistenDone?.()
+      unlistenError?.()
+    }
+  }, [])
+
+  // Tests `candidate` against `baseline` until the SPRT accepts either hypothesis
+  const start = useCallback(
+    async (candidate: TournamentPlayer, baseline: TournamentPlayer, settings: SprtSettings) => {
+      if (busy) return
+      setBusy(true)
+      setReport(null)
+      setError(null)
+      try {
+        await invoke<boolean>('start_sprt', { candidate, baseline, settings })
+      } catch (err) {
+        setError(String(err))
+        setBusy(false)
+      }
+    },
+    [busy],
+  )
+
+  const stop = useCallback(async () => {
+    try {
+      await invoke('stop_sprt')
+    } catch (err) {
+      setError(String(err))
+    }
+  }, [])
+
+  return { busy, report, error, start, stop }
+}
@@ -0,0 +1,74 @@
+import { useCallback, useEffect, useState } from 'react'
+import { invoke } from '@tauri-apps/api/tauri'
+import { listen } from '@tauri-apps/api/event'
+import type { TuneProgress, TuneReport, TuneSettings } from '../types'
+
+const isTauri = typeof window !== 'undefined' && '__TAURI__' in window
+
+export function useTuning() {
+  const [busy, setBusy] = useState(false)
+  const [progress, setProgress] = useState<TuneProgress | null>(null)
+  const [report, setReport] = useState<TuneReport | null>(null)
+  const [error, setError] = useState<string | null>(null)
+
+  useEffect(() => {
+    if (!isTauri) return
+    let unlistenProgress: (() => void) | null = null
+    let unlistenDone: (() => void) | null = null
+    let unlistenError: (() => void) | null = null
+
+    listen<TuneProgress>('tuning_progress', (event) => {
+      setProgress(event.payload)
+    }).then((fn) => {
+      unlistenProgress = fn
+    })
+
+    listen<TuneReport>('tuning_done', (event) => {
+      setReport(event.payload)
+      setBusy(false)
+    }).then((fn) => {
+      unlistenDone = fn
+    })
+
+    listen<string>('tuning_error', (event) => {
+      setError(event.payload)
+      setBusy(false)
+    }).then((fn) => {
+      unlistenError = fn
+    })
+
+    return () => {
+      unlistenProgress?.()
+      unlistenDone?.()
+      unlistenError?.()
+    }
+  }, [])
+
+  // Tunes the weights of a built-in level (or those in `weightsPath`) and writes the result to `outputPath`
+  const start = useCallback(
+    async (profileId: string, outputPath: string, settings: TuneSettings, weightsPath?: string) => {
+      if (busy) return
+      setBusy(true)
+      setReport(null)
+      setProgress(null)
+      setError(null)
+      try {
+        await invoke<boolean>('start_tuning', { profileId, weightsPath, outputPath, settings })
+      } catch (err) {
+        setError(String(err))
+        setBusy(false)
+      }
+    },
+    [busy],
+  )
+
+  const stop = useCallback(async () => {
+    try {
+      await invoke('stop_tuning')
+    } catch (err) {
+      setError(String(err))
+    }
+  }, [])
+
+  return { busy, progress, report, error, start, stop }
+}
@@ -1,7 +1,21 @@
 export type Player = 'B' | 'W'
 export type GameResult = 'B_WIN' | 'W_WIN' | 'DRAW'
-export type RuleSetKind = 'standard'
-export type ProfileKind = 'heuristic' | 'llm'
+export type RuleSetKind = 'standard' | 'renju' | 'exact_five' | 'exact_five_black'
+export type ProfileKind = 'heuristic' | 'llm' | 'engine'
+export type OpeningRule = 'free' | 'swap' | 'swap2'
+export type OpeningPhase =
+  | 'proposer_place'
+  | 'responder_choice'
+  | 'responder_place'
+  | 'proposer_choice'
+  | 'done'
+export type OpeningChoice = 'take_black' | 'take_white' | 'place_two'
+export type Termination = 'five' | 'board_full' | 'timeout' | 'forfeit'
+
+export type Coord = {
+  x: number
+  y: number
+}
 
 export type Move = {
   x: number
@@ -27,15 +41,110 @@ export type GameModeHumanVsHuman = {
 
 export type GameMode = GameModeHumanVsAi | GameModeAiVsAi | GameModeHumanVsHuman
 
+export type OpeningDecision = {
+  ply: number
+  by: Player
+  choice: OpeningChoice
+}
+
+export type OpeningState = {
+  rule: OpeningRule
+  phase: OpeningPhase
+  decisions: OpeningDecision[]
+  swapped: boolean
+}
+
+export type TimeControl = {
+  mainMs: number
+  incrementMs: number
+}
+
+export type ClockState = {
+  timeControl: TimeControl
+  blackMs: number
+  whiteMs: number
+}
+
 export type GameSnapshot = {
   boardSize: number
   board: Array<Player | null>
   ruleSet: RuleSetKind
   toMove: Player
   result: GameResult | null
+  termination: Termination | null
   moves: Move[]
   mode: GameMode
+  opening: OpeningState
   canHumanMove: boolean
+  forbidden: Coord[]
+  canUndo: boolean
+  canRedo: boolean
+  rated: boolean
+  clock: ClockState | null
+}
+
+export type MoveAnalysis = {
+  coord: Coord
+  score: number
+  depth: number
+  nodes: number
+  pv: Coord[]
+}
+
+export type PositionAnalysis = {
+  toMove: Player
+  moves: MoveAnalysis[]
+}
+
+export type AnalysisUpdate = {
+  toMove: Player
+  depth: number
+  score: number
+  pv: Coord[]
+  nodes: number
+  nodesPerSecond: number
+  lines: MoveAnalysis[]
+}
+
+export type MoveLabel = 'best' | 'good' | 'inaccuracy' | 'mistake' | 'blunder'
+
+export type MoveReview = {
+  ply: number
+  player: Player
+  played: Coord
+  best: Coord
+  playedScore: number
+  bestScore: number
+  scoreDrop: number
+  label: MoveLabel
+  missedWin: boolean
+  bestLine: Coord[]
+}
+
+export type ReviewSummary = {
+  best: number
+  good: number
+  inaccuracies: number
+  mistakes: number
+  blunders: number
+  missedWins: number
+}
+
+export type GameReview = {
+  gameId?: string
+  boardSize: number
+  ruleSet: RuleSetKind
+  result: GameResult | null
+  nodeBudget: number
+  moves: MoveReview[]
+  black: ReviewSummary
+  white: ReviewSummary
+}
+
+export type ReviewProgress = {
+  completed: number
+  total: number
+  percent: number
 }
 
 export type RatingEntry = {
@@ -46,12 +155,60 @@ export type RatingEntry = {
   losses: number
 }
 
+export type ThreatKind = 'vcf' | 'vct'
+
+export type ForcedWin = {
+  kind: ThreatKind
+  moves: Coord[]
+}
+
+export type SolveOutcome = 'win' | 'loss' | 'unknown'
+
+export type ProofNode = {
+  coord: Coord
+  children: ProofNode[]
+}
+
+export type SolveResult = {
+  outcome: SolveOutcome
+  toMove: Player
+  nodes: number
+  line: Coord[]
+  tree: ProofNode[]
+}
+
 export type AiConfig = {
   depth: number
   maxCandidates: number
   randomness: number
   maxNodes: number
   defenseWeight: number
+  threatNodes: number
+  quiescenceNodes: number
+  moveTimeMs: number
+  hashMb: number
+  threads: number
+  weights: EvalWeights
+  useBook: boolean
+  bookVariety: number
+  seed?: number
+}
+
+export type EvalWeights = {
+  five: number
+  openFour: number
+  semiFour: number
+  brokenFour: number
+  openThree: number
+  brokenThree: number
+  semiThree: number
+  openTwo: number
+  semiTwo: number
+  openOne: number
+  openFourBonus: number
+  doubleThreeBonus: number
+  center: number
+  defense: number
 }
 
 export type LlmConfig = {
@@ -65,6 +222,13 @@ export type LlmConfig = {
   apiKeySet: boolean
 }
 
+export type EngineConfig = {
+  path: string
+  args: string[]
+  timeoutTurnMs: number
+  maxMemoryMb: number
+}
+
 export type ProfileRating = {
   id: string
   name: string
@@ -76,6 +240,7 @@ export type ProfileRating = {
   kind: ProfileKind
   config?: AiConfig
   llm?: LlmConfig
+  engine?: EngineConfig
 }
 
 export type RatingsSnapshot = {
@@ -103,21 +268,108 @@ export type SelfPlayReport = {
   totalGames: number
   completedGames: number
   stopped: boolean
+  seed: number
+  gamesPath?: string
 }
 
+export type ArchiveFormat = 'directory' | 'jsonl'
+
 export type SelfPlayProgress = {
   completed: number
   total: number
   percent: number
 }
 
+export type TuneSettings = {
+  iterations: number
+  gamesPerIteration: number
+  verificationGames: number
+  perturbation: number
+  learningRate: number
+  parallelism: number
+  seed?: number
+}
+
+export type TuneProgress = {
+  iteration: number
+  iterations: number
+  gamesPlayed: number
+  weights: EvalWeights
+}
+
+export type TuneReport = {
+  iterations: number
+  gamesPlayed: number
+  baseline: EvalWeights
+  tuned: EvalWeights
+  verificationGames: number
+  verificationScore?: number
+  eloGain?: number
+  eloMargin?: number
+  stopped: boolean
+}
+
+// A side of a tournament or SPRT: a ladder profile, a search config or an external engine
+export type TournamentPlayer = {
+  name?: string
+  profile?: string
+  config?: AiConfig
+  weights?: string
+  engine?: EngineConfig
+}
+
+export type SprtSettings = {
+  elo0: number
+  elo1: number
+  alpha: number
+  beta: number
+  maxPairs: number
+  openingPlies: number
+  parallelism: number
+  boardSize: number
+  ruleSet: RuleSetKind
+  seed?: number
+}
+
+export type SprtVerdict = 'h0' | 'h1'
+
+export type SprtReport = {
+  candidate: string
+  baseline: string
+  elo0: number
+  elo1: number
+  pairs: number
+  // Pairs by the candidate's points: 0, ½, 1, 1½ and 2
+  pentanomial: [number, number, number, number, number]
+  wins: number
+  draws: number
+  losses: number
+  llr: number
+  lowerBound: number
+  upperBound: number
+  elo?: number
+  eloMargin?: number
+  verdict?: SprtVerdict
+  stopped: boolean
+  seed: number
+}
+
+export type BookSummary = {
+  boardSize: number
+  ruleSet: RuleSetKind
+  games: number
+  positions: number
+  moves: number
+  skippedFiles: number
+}
+
 export type MainMenu = 'Game' | 'Rating' | 'AI' | 'Data' | 'Users'
-export type LadderFilter = 'all' | 'user' | 'ai' | 'llm'
+export type LadderFilter = 'all' | 'user' | 'ai' | 'llm' | 'engine'
 
 export type AppMode = 'welcome' | 'play' | 'settings'
 export type SettingsPage = 'home' | 'profile' | 'ai' | 'data' | 'users'
 
-export type LadderEntryKind = 'heuristic' | 'llm' | 'user'
+export type LadderEntryKind = 'heuristic' | 'llm' | 'engine' | 'user'
 
 export type LadderEntry = {
   id: string
@@ -130,6 +382,9 @@ export type LadderEntry = {
   losses: number
 }
 
+export const profileKindLabel = (kind: ProfileKind) =>
+  kind === 'llm' ? 'LLM' : kind === 'engine' ? 'Engine' : 'AI'
+
 export const formatRecord = (wins: number, draws: number, losses: number) =>
   `${wins}-${draws}-${losses}`
 